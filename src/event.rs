@@ -131,15 +131,15 @@ impl<S, ES, E> Source<ES, E> for &mut S
           ES: Sink,
 {
     fn max_timeout(&self) -> Option<Duration> {
-        (&**self).max_timeout()
+        (**self).max_timeout()
     }
 
     fn poll(&mut self, event_sink: &mut ES) -> Result<(), E> {
-        (&mut **self).poll(event_sink)
+        (**self).poll(event_sink)
     }
 
     fn blocking_poll(&mut self, event_sink: &mut ES, timeout: Option<Duration>) -> Result<(), E> {
-        (&mut **self).blocking_poll(event_sink, timeout)
+        (**self).blocking_poll(event_sink, timeout)
     }
 }
 
@@ -229,21 +229,21 @@ pub trait Sink {
     }
 }
 
-impl<'a, ES> Sink for &'a mut ES
+impl<ES> Sink for &mut ES
     where ES: Sink,
 {
     fn capacity_left(&self) -> Capacity {
-        (&**self).capacity_left()
+        (**self).capacity_left()
     }
 
     fn add(&mut self, event: Event) {
-        (&mut **self).add(event)
+        (**self).add(event)
     }
 
     fn extend<I>(&mut self, events: I)
         where I: Iterator<Item = Event>,
     {
-        (&mut **self).extend(events)
+        (**self).extend(events)
     }
 }
 
@@ -506,6 +506,13 @@ impl Ready {
     pub const fn is_hup(self) -> bool {
         self.contains(Self::HUP)
     }
+
+    /// Returns `self` without the flags in `other`.
+    #[inline]
+    #[cfg(feature = "std")]
+    pub(crate) const fn remove(self, other: Ready) -> Ready {
+        Ready(self.0 & !other.0)
+    }
 }
 
 impl BitOr for Ready {
@@ -543,8 +550,7 @@ macro_rules! fmt_debug {
 
             // This is silly but it is to circumvent a `unused_assignments`
             // warning for the last write to `first`.
-            #[allow(clippy::drop_copy)]
-            drop(first);
+            let _ = first;
 
             Ok(())
         }
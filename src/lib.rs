@@ -162,15 +162,41 @@ pub mod event;
 pub mod net;
 #[cfg(feature = "std")]
 pub mod os;
+#[cfg(all(feature = "std", unix))]
+pub mod process;
 
 #[cfg(all(feature = "std", unix))]
 pub mod unix {
     //! Unix only extensions.
+    //!
+    //! # Inheriting file descriptors
+    //!
+    //! All handles in this crate are created with the close-on-exec flag set,
+    //! which means they're closed when the process starts another program
+    //! using `exec(3)`. To pass a handle to such a program the flag must be
+    //! cleared. Handles that can be passed like this have two methods:
+    //! `set_inheritable`, which clears (`inheritable == true`) or sets the
+    //! flag, and `into_inheritable`, which clears the flag and converts the
+    //! handle into a raw file descriptor, so that it isn't closed when the
+    //! handle is dropped. The file descriptor number can then be passed to
+    //! the program, e.g. as argument or environment variable.
+    //!
+    //! Note that the flag applies to the file descriptor for the entire
+    //! process, so another thread calling `exec(3)` while the flag is
+    //! cleared also passes the file descriptor to its program.
 
     #[doc(inline)]
     pub use crate::sys::pipe::{new_pipe, Receiver, Sender};
     #[doc(inline)]
     pub use crate::sys::EventedFd;
+    #[doc(inline)]
+    pub use crate::sys::{stderr, stdin, stdout, Fifo, Pty, Tty, WindowSize};
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[doc(inline)]
+    pub use crate::sys::EventFd;
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[doc(inline)]
+    pub use crate::sys::{WatchEvent, WatchEventKind, WatchId, WatchMask, Watcher};
 }
 
 #[cfg(feature = "std")]
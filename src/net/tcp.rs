@@ -1,10 +1,11 @@
 use std::io::{self, Read, Write};
-use std::net::{Shutdown, SocketAddr};
+use std::net::{self, Shutdown, SocketAddr};
 #[cfg(unix)]
-use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
 
-use crate::os::{Evented, Interests, OsQueue, RegisterOption};
-use crate::{event, sys};
+use crate::event;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+use crate::sys;
 
 /// A non-blocking TCP stream between a local socket and a remote socket.
 ///
@@ -63,6 +64,21 @@ impl TcpStream {
         sys::TcpStream::connect(address).map(|inner| TcpStream { inner })
     }
 
+    /// Creates a new `TcpStream` from a standard library `TcpStream`.
+    ///
+    /// This sets the stream in non-blocking mode.
+    pub fn from_std(stream: net::TcpStream) -> io::Result<TcpStream> {
+        sys::TcpStream::from_std(stream).map(|inner| TcpStream { inner })
+    }
+
+    /// Converts the `TcpStream` into a standard library `TcpStream`.
+    ///
+    /// The returned stream is still in non-blocking mode, use
+    /// `set_nonblocking(false)` to change that.
+    pub fn into_std(self) -> net::TcpStream {
+        self.inner.into_std()
+    }
+
     /// Returns the socket address of the remote peer of this TCP connection.
     pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
         self.inner.peer_addr()
@@ -93,6 +109,33 @@ impl TcpStream {
         self.inner.nodelay()
     }
 
+    /// Sets the value of the `SO_BUSY_POLL` option on this socket, in
+    /// microseconds.
+    ///
+    /// This sets the approximate time to busy poll the device's receive queue
+    /// on blocking reads, and when polling an [`OsQueue`] it's registered
+    /// with. Increasing the value requires `CAP_NET_ADMIN`, the initial value
+    /// is set by the `net.core.busy_read` sysctl. See
+    /// [`OsQueue::set_busy_poll`] to busy poll in user space.
+    ///
+    /// This is only available on Linux and Android.
+    ///
+    /// [`OsQueue`]: crate::os::OsQueue
+    /// [`OsQueue::set_busy_poll`]: crate::os::OsQueue::set_busy_poll
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn set_busy_poll(&mut self, usecs: u32) -> io::Result<()> {
+        self.inner.set_busy_poll(usecs)
+    }
+
+    /// Gets the value of the `SO_BUSY_POLL` option on this socket, in
+    /// microseconds.
+    ///
+    /// This is only available on Linux and Android.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn busy_poll(&mut self) -> io::Result<u32> {
+        self.inner.busy_poll()
+    }
+
     /// Receives data on the socket from the remote address to which it is
     /// connected, without removing that data from the queue. On success,
     /// returns the number of bytes peeked.
@@ -120,6 +163,24 @@ impl TcpStream {
     pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
         self.inner.take_error()
     }
+
+    /// Set whether or not the stream is [inherited] by programs started with
+    /// `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    #[cfg(unix)]
+    pub fn set_inheritable(&mut self, inheritable: bool) -> io::Result<()> {
+        sys::set_cloexec(self.inner.as_raw_fd(), !inheritable)
+    }
+
+    /// Converts the stream into a raw file descriptor that is [inherited] by
+    /// programs started with `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    #[cfg(unix)]
+    pub fn into_inheritable(self) -> io::Result<RawFd> {
+        sys::into_inheritable(self)
+    }
 }
 
 impl Read for TcpStream {
@@ -138,20 +199,6 @@ impl Write for TcpStream {
     }
 }
 
-impl Evented for TcpStream {
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        self.inner.register(os_queue, id, interests, opt)
-    }
-
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        self.inner.reregister(os_queue, id, interests, opt)
-    }
-
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-        self.inner.deregister(os_queue)
-    }
-}
-
 #[cfg(unix)]
 impl FromRawFd for TcpStream {
     /// The caller must ensure that the stream is in non-blocking mode when
@@ -177,6 +224,43 @@ impl AsRawFd for TcpStream {
     }
 }
 
+impl Evented for TcpStream {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.inner.register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.inner.reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.inner.deregister(registrar)
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for TcpStream {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+#[cfg(unix)]
+impl From<OwnedFd> for TcpStream {
+    /// The caller must ensure that the stream is in non-blocking mode when
+    /// using this function.
+    fn from(fd: OwnedFd) -> TcpStream {
+        unsafe { TcpStream::from_raw_fd(fd.into_raw_fd()) }
+    }
+}
+
+#[cfg(unix)]
+impl From<TcpStream> for OwnedFd {
+    fn from(stream: TcpStream) -> OwnedFd {
+        unsafe { OwnedFd::from_raw_fd(stream.into_raw_fd()) }
+    }
+}
+
 /// A TCP socket listener.
 ///
 /// This works much like the `TcpListener` in the standard library, but this
@@ -242,6 +326,21 @@ impl TcpListener {
         sys::TcpListener::bind(address).map(|inner| TcpListener { inner })
     }
 
+    /// Creates a new `TcpListener` from a standard library `TcpListener`.
+    ///
+    /// This sets the listener in non-blocking mode.
+    pub fn from_std(listener: net::TcpListener) -> io::Result<TcpListener> {
+        sys::TcpListener::from_std(listener).map(|inner| TcpListener { inner })
+    }
+
+    /// Converts the `TcpListener` into a standard library `TcpListener`.
+    ///
+    /// The returned listener is still in non-blocking mode, use
+    /// `set_nonblocking(false)` to change that.
+    pub fn into_std(self) -> net::TcpListener {
+        self.inner.into_std()
+    }
+
     /// Create a independently owned handle to the underlying socket.
     ///
     /// The returned `TcpListener` is a reference to the same socket as `self`.
@@ -294,21 +393,23 @@ impl TcpListener {
     pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
         self.inner.take_error()
     }
-}
-
-impl Evented for TcpListener {
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        debug_assert!(!interests.is_writable(), "TcpListener only needs readable interests");
-        self.inner.register(os_queue, id, interests, opt)
-    }
 
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        debug_assert!(!interests.is_writable(), "TcpListener only needs readable interests");
-        self.inner.reregister(os_queue, id, interests, opt)
+    /// Set whether or not the listener is [inherited] by programs started with
+    /// `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    #[cfg(unix)]
+    pub fn set_inheritable(&mut self, inheritable: bool) -> io::Result<()> {
+        sys::set_cloexec(self.inner.as_raw_fd(), !inheritable)
     }
 
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-        self.inner.deregister(os_queue)
+    /// Converts the listener into a raw file descriptor that is [inherited] by
+    /// programs started with `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    #[cfg(unix)]
+    pub fn into_inheritable(self) -> io::Result<RawFd> {
+        sys::into_inheritable(self)
     }
 }
 
@@ -336,3 +437,42 @@ impl AsRawFd for TcpListener {
         self.inner.as_raw_fd()
     }
 }
+
+impl Evented for TcpListener {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        debug_assert!(!interests.is_writable(), "TcpListener only needs readable interests");
+        self.inner.register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        debug_assert!(!interests.is_writable(), "TcpListener only needs readable interests");
+        self.inner.reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.inner.deregister(registrar)
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for TcpListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+#[cfg(unix)]
+impl From<OwnedFd> for TcpListener {
+    /// The caller must ensure that the listener is in non-blocking mode when
+    /// using this function.
+    fn from(fd: OwnedFd) -> TcpListener {
+        unsafe { TcpListener::from_raw_fd(fd.into_raw_fd()) }
+    }
+}
+
+#[cfg(unix)]
+impl From<TcpListener> for OwnedFd {
+    fn from(listener: TcpListener) -> OwnedFd {
+        unsafe { OwnedFd::from_raw_fd(listener.into_raw_fd()) }
+    }
+}
@@ -1,10 +1,11 @@
 use std::io;
-use std::net::SocketAddr;
+use std::net::{self, SocketAddr};
 #[cfg(unix)]
-use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
 
-use crate::os::{Evented, Interests, OsQueue, RegisterOption};
-use crate::{event, sys};
+use crate::event;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+use crate::sys;
 
 /// A User Datagram Protocol socket.
 ///
@@ -119,6 +120,21 @@ impl UdpSocket {
         sys::UdpSocket::bind(address).map(|socket| UdpSocket { socket })
     }
 
+    /// Creates a new `UdpSocket` from a standard library `UdpSocket`.
+    ///
+    /// This sets the socket in non-blocking mode.
+    pub fn from_std(socket: net::UdpSocket) -> io::Result<UdpSocket> {
+        sys::UdpSocket::from_std(socket).map(|socket| UdpSocket { socket })
+    }
+
+    /// Converts the `UdpSocket` into a standard library `UdpSocket`.
+    ///
+    /// The returned socket is still in non-blocking mode, use
+    /// `set_nonblocking(false)` to change that.
+    pub fn into_std(self) -> net::UdpSocket {
+        self.socket.into_std()
+    }
+
     /// Connects the UDP socket by setting the default destination and limiting
     /// packets that are read, written and peeked to the address specified in
     /// `address`.
@@ -459,19 +475,45 @@ impl UdpSocket {
     pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
         self.socket.take_error()
     }
-}
 
-impl Evented for UdpSocket {
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        self.socket.register(os_queue, id, interests, opt)
+    /// Sets the value of the `SO_BUSY_POLL` option on this socket, in
+    /// microseconds.
+    ///
+    /// See [`TcpStream::set_busy_poll`] for more information.
+    ///
+    /// This is only available on Linux and Android.
+    ///
+    /// [`TcpStream::set_busy_poll`]: crate::net::TcpStream::set_busy_poll
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn set_busy_poll(&mut self, usecs: u32) -> io::Result<()> {
+        self.socket.set_busy_poll(usecs)
     }
 
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        self.socket.reregister(os_queue, id, interests, opt)
+    /// Gets the value of the `SO_BUSY_POLL` option on this socket, in
+    /// microseconds.
+    ///
+    /// This is only available on Linux and Android.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn busy_poll(&mut self) -> io::Result<u32> {
+        self.socket.busy_poll()
     }
 
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-        self.socket.deregister(os_queue)
+    /// Set whether or not the socket is [inherited] by programs started with
+    /// `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    #[cfg(unix)]
+    pub fn set_inheritable(&mut self, inheritable: bool) -> io::Result<()> {
+        sys::set_cloexec(self.socket.as_raw_fd(), !inheritable)
+    }
+
+    /// Converts the socket into a raw file descriptor that is [inherited] by
+    /// programs started with `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    #[cfg(unix)]
+    pub fn into_inheritable(self) -> io::Result<RawFd> {
+        sys::into_inheritable(self)
     }
 }
 
@@ -489,6 +531,43 @@ impl AsRawFd for UdpSocket {
     }
 }
 
+impl Evented for UdpSocket {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.socket.register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.socket.reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.socket.deregister(registrar)
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for UdpSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
+
+#[cfg(unix)]
+impl From<OwnedFd> for UdpSocket {
+    /// The caller must ensure that the socket is in non-blocking mode when
+    /// using this function.
+    fn from(fd: OwnedFd) -> UdpSocket {
+        unsafe { UdpSocket::from_raw_fd(fd.into_raw_fd()) }
+    }
+}
+
+#[cfg(unix)]
+impl From<UdpSocket> for OwnedFd {
+    fn from(socket: UdpSocket) -> OwnedFd {
+        unsafe { OwnedFd::from_raw_fd(socket.into_raw_fd()) }
+    }
+}
+
 #[cfg(unix)]
 impl FromRawFd for UdpSocket {
     unsafe fn from_raw_fd(fd: RawFd) -> UdpSocket {
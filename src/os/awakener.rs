@@ -1,6 +1,8 @@
 use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-use crate::os::OsQueue;
+use crate::os::{fork, OsQueue};
 use crate::{event, sys};
 
 /// Awakener allows cross-thread waking of [`OsQueue`].
@@ -25,6 +27,30 @@ use crate::{event, sys};
 /// [`wake`]: Awakener::wake
 /// [`try_clone`]: Awakener::try_clone
 ///
+/// After forking the `Awakener` is shared with the parent process, in the
+/// child process [`after_fork_child`] must be called before it can be used.
+///
+/// [`after_fork_child`]: Awakener::after_fork_child
+///
+/// # Signal handlers
+///
+/// [`wake`] is async-signal-safe, it only uses atomic operations and the
+/// `write(2)` system call, so it may be called from a signal handler. The
+/// exception is calling it in a forked child process before calling
+/// [`after_fork_child`], in which case the returned error is allocated.
+///
+/// # Coalescing
+///
+/// Multiple calls to [`wake`] before [`drain`] is called are coalesced into a
+/// single event. Only the first call makes a system call, later calls only
+/// update a counter in user space. This means that after receiving an event
+/// [`drain`] must be called, otherwise the `Awakener` won't wake the
+/// [`OsQueue`] again. [`drain`] also returns the number of calls to [`wake`]
+/// since the last call to it. The state is shared between an `Awakener` and
+/// its clones, but not between separately created `Awakener`s.
+///
+/// [`drain`]: Awakener::drain
+///
 /// # Implementation notes
 ///
 /// On platforms that support kqueue this will use the `EVFILT_USER` event
@@ -80,21 +106,120 @@ use crate::{event, sys};
 #[derive(Debug)]
 pub struct Awakener {
     inner: sys::Awakener,
+    state: Arc<WakeState>,
+    fork_generation: usize,
 }
 
 impl Awakener {
     /// Create a new `Awakener`.
     pub fn new(os_queue: &mut OsQueue, id: event::Id) -> io::Result<Awakener> {
-        sys::Awakener::new(os_queue.selector(), id).map(|inner| Awakener { inner })
+        sys::Awakener::new(os_queue.selector(), id).map(|inner| Awakener {
+            inner,
+            state: Arc::new(WakeState::default()),
+            fork_generation: fork::generation(),
+        })
     }
 
     /// Attempts to clone the `Awakener`.
     pub fn try_clone(&self) -> io::Result<Awakener> {
-        self.inner.try_clone().map(|inner| Awakener { inner })
+        self.inner.try_clone().map(|inner| Awakener {
+            inner,
+            state: self.state.clone(),
+            fork_generation: self.fork_generation,
+        })
     }
 
     /// Wake up the [`OsQueue`] associated with this `Awakener`.
+    ///
+    /// Returns an error if the process forked since the `Awakener` was
+    /// created, see [`after_fork_child`].
+    ///
+    /// [`after_fork_child`]: Awakener::after_fork_child
     pub fn wake(&self) -> io::Result<()> {
-        self.inner.wake()
+        fork::check(self.fork_generation, "Awakener")?;
+        let _ = self.state.count.fetch_add(1, Ordering::SeqCst);
+        if self.state.pending.swap(true, Ordering::SeqCst) {
+            // The `OsQueue` is already woken, but not yet polled.
+            return Ok(());
+        }
+        self.inner.wake().inspect_err(|_| self.state.pending.store(false, Ordering::SeqCst))
     }
+
+    /// Returns the number of calls to [`wake`] since the last call to `drain`,
+    /// resetting it to zero.
+    ///
+    /// This also resets the state of the underlying system resource, e.g. the
+    /// counter of the eventfd. This must be called on the polling side, after
+    /// receiving the event of the `Awakener`, for [`wake`] to wake the
+    /// [`OsQueue`] again.
+    ///
+    /// [`wake`]: Awakener::wake
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::io;
+    ///
+    /// use gaea::{event, poll};
+    /// use gaea::os::{Awakener, OsQueue};
+    ///
+    /// let mut os_queue = OsQueue::new()?;
+    /// let mut events = Vec::new();
+    /// let awakener = Awakener::new(&mut os_queue, event::Id(0))?;
+    ///
+    /// awakener.wake()?;
+    /// awakener.wake()?;
+    ///
+    /// // Both calls are coalesced into a single event.
+    /// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+    /// assert_eq!(events.len(), 1);
+    /// assert_eq!(awakener.drain()?, 2);
+    /// assert_eq!(awakener.drain()?, 0);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn drain(&self) -> io::Result<usize> {
+        fork::check(self.fork_generation, "Awakener")?;
+        self.inner.drain()?;
+        // Clear `pending` only after reading the system resource, otherwise a
+        // concurrent call to `wake` could set it again after its wake up was
+        // consumed above, after which no call would wake the `OsQueue` again.
+        // Calls to `wake` between clearing `pending` and reading `count` cause
+        // an event for which `drain` will return zero.
+        self.state.pending.store(false, Ordering::SeqCst);
+        Ok(self.state.count.swap(0, Ordering::SeqCst))
+    }
+
+    /// Re-initialise the `Awakener` in a child process after forking.
+    ///
+    /// This replaces the system resources shared with the parent process, so
+    /// that waking the `Awakener` in the child doesn't wake the parent's
+    /// [`OsQueue`], and vice versa. This must be called after
+    /// [`OsQueue::after_fork_child`] is called on `os_queue`, which must be the
+    /// queue the `Awakener` was created with.
+    ///
+    /// Clones of the `Awakener` (created before calling this) will keep
+    /// returning errors, new clones must be created using [`try_clone`].
+    ///
+    /// [`try_clone`]: Awakener::try_clone
+    pub fn after_fork_child(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
+        self.inner.after_fork_child(os_queue.selector())?;
+        // The pending wake up belonged to the parent's `OsQueue`.
+        self.state.pending.store(false, Ordering::SeqCst);
+        self.state.count.store(0, Ordering::SeqCst);
+        self.fork_generation = fork::generation();
+        Ok(())
+    }
+}
+
+/// State shared between an `Awakener` and its clones.
+#[derive(Debug, Default)]
+struct WakeState {
+    /// Whether or not the system queue was woken, without `Awakener::drain`
+    /// being called since.
+    pending: AtomicBool,
+    /// Number of calls to `Awakener::wake` since the last call to
+    /// `Awakener::drain`.
+    count: AtomicUsize,
 }
@@ -0,0 +1,110 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::event::{self, Event};
+use crate::sys;
+
+/// Statistics of the busy polling mode of [`OsQueue`].
+///
+/// See [`OsQueue::set_busy_poll`] and [`OsQueue::busy_poll_stats`].
+///
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`OsQueue::set_busy_poll`]: crate::os::OsQueue::set_busy_poll
+/// [`OsQueue::busy_poll_stats`]: crate::os::OsQueue::busy_poll_stats
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BusyPollStats {
+    spins: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl BusyPollStats {
+    /// Returns the total number of non-blocking polls made while spinning.
+    pub const fn spins(&self) -> u64 {
+        self.spins
+    }
+
+    /// Returns the number of times the spin phase found events, i.e. the
+    /// number of blocking calls avoided.
+    pub const fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of times the spin phase didn't find any events and
+    /// fell back to a blocking poll.
+    pub const fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+/// Busy polling mode of `OsQueue`.
+#[derive(Debug, Default)]
+pub(crate) struct BusyPoll {
+    /// Maximum time to spin, `None` if disabled.
+    spin: Option<Duration>,
+    stats: BusyPollStats,
+}
+
+impl BusyPoll {
+    pub(crate) fn set_spin(&mut self, spin: Option<Duration>) {
+        self.spin = spin;
+    }
+
+    pub(crate) const fn stats(&self) -> BusyPollStats {
+        self.stats
+    }
+
+    /// Poll `selector`, first spinning using non-blocking polls for at most
+    /// the spin duration (or `timeout` if shorter), before falling back to a
+    /// blocking poll with the remaining timeout.
+    ///
+    /// This polls the system selector directly, see `Selector::select`.
+    pub(crate) fn select<ES>(&mut self, selector: &sys::Selector, event_sink: &mut ES, timeout: Option<Duration>) -> io::Result<()>
+        where ES: event::Sink,
+    {
+        let spin = match (self.spin, timeout) {
+            (Some(spin), Some(timeout)) => spin.min(timeout),
+            (Some(spin), None) => spin,
+            (None, _) => Duration::from_millis(0),
+        };
+        if spin == Duration::from_millis(0) {
+            return selector.select(event_sink, timeout);
+        }
+
+        let start = Instant::now();
+        let mut event_sink = CountingSink { sink: event_sink, added: 0 };
+        loop {
+            self.stats.spins += 1;
+            selector.select(&mut event_sink, Some(Duration::from_millis(0)))?;
+            if event_sink.added != 0 {
+                self.stats.hits += 1;
+                return Ok(());
+            } else if start.elapsed() >= spin {
+                break;
+            }
+        }
+
+        self.stats.misses += 1;
+        let timeout = timeout.map(|timeout| timeout.checked_sub(start.elapsed()).unwrap_or_default());
+        selector.select(event_sink.sink, timeout)
+    }
+}
+
+/// `event::Sink` wrapper that counts the number of events added.
+struct CountingSink<'a, ES> {
+    sink: &'a mut ES,
+    added: usize,
+}
+
+impl<'a, ES> event::Sink for CountingSink<'a, ES>
+    where ES: event::Sink,
+{
+    fn capacity_left(&self) -> event::Capacity {
+        self.sink.capacity_left()
+    }
+
+    fn add(&mut self, event: Event) {
+        self.added += 1;
+        self.sink.add(event);
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use log::error;
+
+use crate::event::{self, Event};
+use crate::net::UdpSocket;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+
+/// Readable readiness was received and not yet drained.
+const READABLE: u8 = 1;
+/// Writable readiness was received and not yet drained.
+const WRITABLE: u8 = 1 << 1;
+
+/// Debug wrapper that checks if an edge-triggered handle is drained.
+///
+/// When a handle is registered using [`RegisterOption::EDGE`] a readiness
+/// event is only returned once the readiness changes. This means that after
+/// receiving a readable (writable) event the handle must be read from
+/// (written to) until it returns a [`WouldBlock`] error, otherwise no new
+/// events will be returned for it. See [draining readiness] in the module
+/// documentation.
+///
+/// `DrainChecked` enforces this. If the wrapped handle is registered using
+/// [`RegisterOption::EDGE`] it tracks all readable and writable events
+/// returned for it. If [`OsQueue`] is polled again before the handle is read
+/// from (written to) until it returned a `WouldBlock` error, an error is
+/// logged (using the [`log`] crate), or, if created using
+/// [`DrainChecked::panicking`], `OsQueue` panics. Reaching the end of the
+/// stream, or any error other than an interrupt, also counts as being drained.
+///
+/// It implements [`Read`] and [`Write`] if the wrapped handle does, e.g. for
+/// [`TcpStream`] and the Unix pipe [`Receiver`] and [`Sender`]. For
+/// [`UdpSocket`] it provides the sending and receiving methods directly.
+/// Using the wrapped handle directly, via [`get_mut`], bypasses the checks.
+///
+/// Tracking adds overhead to polling, it's intended for debug builds only.
+///
+/// [`WouldBlock`]: io::ErrorKind::WouldBlock
+/// [draining readiness]: crate::os#draining-readiness
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`log`]: https://crates.io/crates/log
+/// [`TcpStream`]: crate::net::TcpStream
+/// [`Receiver`]: crate::unix::Receiver
+/// [`Sender`]: crate::unix::Sender
+/// [`get_mut`]: DrainChecked::get_mut
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::{self, Read, Write};
+///
+/// use gaea::os::{DrainChecked, OsQueue, RegisterOption};
+/// use gaea::unix::{new_pipe, Receiver};
+/// use gaea::{event, poll};
+///
+/// let mut os_queue = OsQueue::new()?;
+/// let mut events = Vec::new();
+///
+/// let (mut sender, receiver) = new_pipe()?;
+/// // Only check in debug builds.
+/// #[cfg(debug_assertions)]
+/// let mut receiver = DrainChecked::panicking(receiver);
+/// os_queue.register(&mut receiver, event::Id(0), Receiver::INTERESTS, RegisterOption::EDGE)?;
+///
+/// sender.write_all(b"Hello")?;
+/// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+///
+/// // Read until `WouldBlock`.
+/// let mut buf = [0; 20];
+/// loop {
+///     match receiver.read(&mut buf) {
+///         Ok(_) => continue,
+///         Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+///         Err(err) => return Err(err.into()),
+///     }
+/// }
+///
+/// // If we didn't read until `WouldBlock` this would panic.
+/// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(std::time::Duration::from_millis(0)))?;
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DrainChecked<H> {
+    handle: H,
+    check: Arc<DrainCheck>,
+    /// Current edge-triggered registration, if any.
+    registration: Option<TrackedRegistration>,
+}
+
+impl<H> DrainChecked<H> {
+    /// Wrap `handle`, logging an error if it's not drained.
+    pub fn new(handle: H) -> DrainChecked<H> {
+        DrainChecked::with_panic(handle, false)
+    }
+
+    /// Wrap `handle`, panicking (in [`OsQueue`]'s poll) if it's not drained.
+    ///
+    /// [`OsQueue`]: crate::os::OsQueue
+    pub fn panicking(handle: H) -> DrainChecked<H> {
+        DrainChecked::with_panic(handle, true)
+    }
+
+    fn with_panic(handle: H, panic: bool) -> DrainChecked<H> {
+        let check = Arc::new(DrainCheck {
+            interests: AtomicU8::new(0),
+            pending: AtomicU8::new(0),
+            panic,
+        });
+        DrainChecked { handle, check, registration: None }
+    }
+
+    /// Returns a reference to the wrapped handle.
+    pub fn get_ref(&self) -> &H {
+        &self.handle
+    }
+
+    /// Returns a mutable reference to the wrapped handle.
+    ///
+    /// Reading from or writing to the handle directly bypasses the checks.
+    pub fn get_mut(&mut self) -> &mut H {
+        &mut self.handle
+    }
+
+    /// Returns the wrapped handle.
+    pub fn into_inner(self) -> H {
+        self.handle
+    }
+
+    /// Track the registration, `None` if not registered or not
+    /// edge-triggered.
+    fn track(&mut self, registration: Option<(event::Id, Interests, Arc<DrainRegistry>)>) {
+        // Dropping the registration removes it from the registry.
+        self.registration = None;
+        // A new registration (re)arms the handle.
+        self.check.pending.store(0, Ordering::Relaxed);
+        if let Some((id, interests, registry)) = registration {
+            let mut mask = 0;
+            if interests.is_readable() {
+                mask |= READABLE;
+            }
+            if interests.is_writable() {
+                mask |= WRITABLE;
+            }
+            self.check.interests.store(mask, Ordering::Relaxed);
+            registry.add(id, &self.check);
+            self.registration = Some(TrackedRegistration { id, registry, check: Arc::clone(&self.check) });
+        }
+    }
+
+    /// Mark the handle as drained for `readiness` if `result` indicates so.
+    fn drained<T>(&self, result: &io::Result<T>, readiness: u8, eof: bool) {
+        let drained = match result {
+            Ok(_) => eof,
+            Err(ref err) => err.kind() != io::ErrorKind::Interrupted,
+        };
+        if drained {
+            let _ = self.check.pending.fetch_and(!readiness, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<H> Evented for DrainChecked<H>
+    where H: Evented,
+{
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.handle.register(registrar, id, interests, opt)?;
+        self.track(registration(registrar, id, interests, opt));
+        Ok(())
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.handle.reregister(registrar, id, interests, opt)?;
+        self.track(registration(registrar, id, interests, opt));
+        Ok(())
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.handle.deregister(registrar)?;
+        self.track(None);
+        Ok(())
+    }
+}
+
+/// Returns the registration to track, if `opt` is edge-triggered.
+fn registration(registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> Option<(event::Id, Interests, Arc<DrainRegistry>)> {
+    if opt.is_edge() {
+        Some((id, interests, Arc::clone(registrar.selector().drain_registry())))
+    } else {
+        None
+    }
+}
+
+impl<H> Read for DrainChecked<H>
+    where H: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.handle.read(buf);
+        let eof = matches!(result, Ok(0)) && !buf.is_empty();
+        self.drained(&result, READABLE, eof);
+        result
+    }
+}
+
+impl<H> Write for DrainChecked<H>
+    where H: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.handle.write(buf);
+        let eof = matches!(result, Ok(0)) && !buf.is_empty();
+        self.drained(&result, WRITABLE, eof);
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.handle.flush()
+    }
+}
+
+impl DrainChecked<UdpSocket> {
+    /// See [`UdpSocket::send_to`].
+    pub fn send_to(&mut self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        let result = self.handle.send_to(buf, target);
+        self.drained(&result, WRITABLE, false);
+        result
+    }
+
+    /// See [`UdpSocket::send`].
+    pub fn send(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.handle.send(buf);
+        self.drained(&result, WRITABLE, false);
+        result
+    }
+
+    /// See [`UdpSocket::recv_from`].
+    pub fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let result = self.handle.recv_from(buf);
+        self.drained(&result, READABLE, false);
+        result
+    }
+
+    /// See [`UdpSocket::recv`].
+    pub fn recv(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.handle.recv(buf);
+        self.drained(&result, READABLE, false);
+        result
+    }
+}
+
+#[cfg(unix)]
+impl<H> AsRawFd for DrainChecked<H>
+    where H: AsRawFd,
+{
+    fn as_raw_fd(&self) -> RawFd {
+        self.handle.as_raw_fd()
+    }
+}
+
+/// Registration of a `DrainChecked` handle in a `DrainRegistry`, removed when
+/// dropped.
+#[derive(Debug)]
+struct TrackedRegistration {
+    id: event::Id,
+    registry: Arc<DrainRegistry>,
+    check: Arc<DrainCheck>,
+}
+
+impl Drop for TrackedRegistration {
+    fn drop(&mut self) {
+        self.registry.remove(self.id, &self.check);
+    }
+}
+
+/// Drain state of a single `DrainChecked` handle.
+#[derive(Debug)]
+struct DrainCheck {
+    /// Readiness (`READABLE` and/or `WRITABLE`) the handle is registered for.
+    interests: AtomicU8,
+    /// Readiness received, but not yet drained.
+    pending: AtomicU8,
+    /// Whether or not to panic if not drained.
+    panic: bool,
+}
+
+/// Registry of all `DrainChecked` handles registered with an `OsQueue`.
+#[derive(Debug, Default)]
+pub(crate) struct DrainRegistry {
+    /// Fast path for when no handles were ever registered.
+    active: AtomicBool,
+    checks: Mutex<HashMap<event::Id, Vec<Weak<DrainCheck>>>>,
+}
+
+impl DrainRegistry {
+    /// Returns true if any `DrainChecked` handle was ever registered.
+    pub(crate) fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    fn add(&self, id: event::Id, check: &Arc<DrainCheck>) {
+        self.active.store(true, Ordering::Relaxed);
+        self.checks.lock().unwrap().entry(id).or_default().push(Arc::downgrade(check));
+    }
+
+    fn remove(&self, id: event::Id, check: &Arc<DrainCheck>) {
+        let mut checks = self.checks.lock().unwrap();
+        if let Some(id_checks) = checks.get_mut(&id) {
+            id_checks.retain(|c| !Weak::ptr_eq(c, &Arc::downgrade(check)));
+            if id_checks.is_empty() {
+                let _ = checks.remove(&id);
+            }
+        }
+    }
+
+    /// Record the readiness `event` for all handles with the same id.
+    pub(crate) fn add_event(&self, event: &Event) {
+        let mut readiness = 0;
+        if event.readiness().is_readable() {
+            readiness |= READABLE;
+        }
+        if event.readiness().is_writable() {
+            readiness |= WRITABLE;
+        }
+        if readiness == 0 {
+            return;
+        }
+
+        if let Some(checks) = self.checks.lock().unwrap().get(&event.id()) {
+            for check in checks.iter().filter_map(Weak::upgrade) {
+                let readiness = readiness & check.interests.load(Ordering::Relaxed);
+                let _ = check.pending.fetch_or(readiness, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Check all handles, called before polling. Logs an error, or panics,
+    /// for each handle that wasn't drained.
+    pub(crate) fn check(&self) {
+        let mut panic = None;
+        for (id, checks) in self.checks.lock().unwrap().iter() {
+            for check in checks.iter().filter_map(Weak::upgrade) {
+                let pending = check.pending.swap(0, Ordering::Relaxed);
+                if pending == 0 {
+                    continue;
+                }
+                let msg = format!("edge-triggered handle with id {} received {} readiness, but \
+                    wasn't {} until it returned a `WouldBlock` error before polling again",
+                    id, readiness_name(pending), drain_name(pending));
+                if check.panic {
+                    panic = Some(msg);
+                } else {
+                    error!("{}", msg);
+                }
+            }
+        }
+        // Panic after unlocking, to not poison the mutex.
+        if let Some(msg) = panic {
+            panic!("{}", msg);
+        }
+    }
+}
+
+fn readiness_name(pending: u8) -> &'static str {
+    match pending {
+        READABLE => "readable",
+        WRITABLE => "writable",
+        _ => "readable and writable",
+    }
+}
+
+fn drain_name(pending: u8) -> &'static str {
+    match pending {
+        READABLE => "read from",
+        WRITABLE => "written to",
+        _ => "read from and written to",
+    }
+}
@@ -1,17 +1,19 @@
 use std::io;
 
 use crate::event;
-use crate::os::{Interests, OsQueue, RegisterOption};
+use crate::os::{Interests, RegisterOption, Registrar};
 
 /// A handle that may be registered with [`OsQueue`].
 ///
-/// Handles that implement `Evented` can be registered with [`OsQueue`]. The
-/// methods on the trait **should not** be called directly, instead the
-/// equivalent methods should be called on [`OsQueue`].
+/// Handles that implement `Evented` can be registered with [`OsQueue`], or
+/// using a [`Registrar`]. The methods on the trait **should not** be called
+/// directly, instead the equivalent methods should be called on [`OsQueue`]
+/// or [`Registrar`].
 ///
 /// See [`OsQueue` documentation] for more details.
 ///
-/// [`OsQueue` documentation]: OsQueue
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`OsQueue` documentation]: crate::os::OsQueue
 ///
 /// # Implementing `Evented`
 ///
@@ -21,8 +23,13 @@ use crate::os::{Interests, OsQueue, RegisterOption};
 /// of `Evented` delegates to a lower level handle. Examples of this are
 /// [`TcpStream`]s, or the *unix only* [`EventedFd`].
 ///
+/// On unix `Evented` is also implemented for [`BorrowedFd`], so types that
+/// wrap a file descriptor can delegate to the result of [`as_fd`].
+///
 /// [`TcpStream`]: crate::net::TcpStream
 /// [`EventedFd`]: crate::unix::EventedFd
+/// [`BorrowedFd`]: std::os::unix::io::BorrowedFd
+/// [`as_fd`]: std::os::unix::io::AsFd::as_fd
 ///
 /// # Dropping `Evented` types
 ///
@@ -30,7 +37,12 @@ use crate::os::{Interests, OsQueue, RegisterOption};
 /// before being dropped for them to not leak resources. This goes against the
 /// normal drop behaviour of types in Rust which cleanup after themselves, e.g.
 /// a `File` will close itself. However since deregistering needs mutable access
-/// to [`OsQueue`] this cannot be done while being dropped.
+/// to [`OsQueue`] this cannot be done while being dropped. To deregister a
+/// handle when it's dropped use [`OsQueue::register_owned`], which returns a
+/// [`Registered`] handle that does this.
+///
+/// [`OsQueue::register_owned`]: crate::os::OsQueue::register_owned
+/// [`Registered`]: crate::os::Registered
 ///
 /// # Examples
 ///
@@ -42,7 +54,7 @@ use crate::os::{Interests, OsQueue, RegisterOption};
 ///
 /// use gaea::event;
 /// use gaea::net::TcpStream;
-/// use gaea::os::{Evented, Interests, RegisterOption, OsQueue};
+/// use gaea::os::{Evented, Interests, RegisterOption, Registrar};
 ///
 /// # #[allow(dead_code)]
 /// pub struct MyEvented {
@@ -51,44 +63,44 @@ use crate::os::{Interests, OsQueue, RegisterOption};
 /// }
 ///
 /// impl Evented for MyEvented {
-///     fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+///     fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
 ///         // Delegate the `register` call to `socket`.
-///         self.socket.register(os_queue, id, interests, opt)
+///         self.socket.register(registrar, id, interests, opt)
 ///     }
 ///
-///     fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+///     fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
 ///         // Delegate the `reregister` call to `socket`.
-///         self.socket.reregister(os_queue, id, interests, opt)
+///         self.socket.reregister(registrar, id, interests, opt)
 ///     }
 ///
-///     fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
+///     fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
 ///         // Delegate the `deregister` call to `socket`.
-///         self.socket.deregister(os_queue)
+///         self.socket.deregister(registrar)
 ///     }
 /// }
 /// ```
 pub trait Evented {
-    /// Register `self` with the given `OsQueue` instance.
+    /// Register `self` with the `OsQueue` of `registrar`.
     ///
     /// This function should not be called directly, use [`OsQueue.register`]
     /// instead.
     ///
     /// [`OsQueue.register`]: crate::os::OsQueue::register
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()>;
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()>;
 
-    /// Reregister `self` with the given `OsQueue` instance.
+    /// Reregister `self` with the `OsQueue` of `registrar`.
     ///
     /// This function should not be called directly, use [`OsQueue.reregister`]
     /// instead.
     ///
     /// [`OsQueue.reregister`]: crate::os::OsQueue::reregister
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()>;
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()>;
 
-    /// Deregister `self` from the given `OsQueue` instance
+    /// Deregister `self` from the `OsQueue` of `registrar`.
     ///
     /// This function should not be called directly, use [`OsQueue.deregister`]
     /// instead.
     ///
     /// [`OsQueue.deregister`]: crate::os::OsQueue::deregister
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()>;
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()>;
 }
@@ -0,0 +1,47 @@
+//! Detection of use after `fork(2)`.
+//!
+//! Each call to `fork` increments a global generation counter in the child
+//! process, using a `pthread_atfork` handler. Types that share system
+//! resources, e.g. `OsQueue`, record the generation in which they were created
+//! and refuse to be used in a different generation, until they're
+//! re-initialised using their `after_fork_child` method.
+
+use std::io;
+use std::sync::Once;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::error;
+
+/// Generation of the current process, incremented in the child process on
+/// each fork.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Registers `after_fork` with `pthread_atfork`.
+static REGISTER: Once = Once::new();
+
+/// Called in the child process after a fork, must be async-signal-safe.
+unsafe extern "C" fn after_fork() {
+    let _ = GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Returns the current fork generation.
+pub(crate) fn generation() -> usize {
+    REGISTER.call_once(|| {
+        let res = unsafe { libc::pthread_atfork(None, None, Some(after_fork)) };
+        if res != 0 {
+            error!("unable to register fork handler: {}", io::Error::from_raw_os_error(res));
+        }
+    });
+    GENERATION.load(Ordering::Relaxed)
+}
+
+/// Returns an error if `generation` is not the current generation, i.e. if
+/// the process forked since `generation` was retrieved.
+pub(crate) fn check(generation: usize, name: &str) -> io::Result<()> {
+    if generation == GENERATION.load(Ordering::Relaxed) {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} used in a forked child process, call `{}::after_fork_child` first", name, name)))
+    }
+}
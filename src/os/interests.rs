@@ -23,14 +23,14 @@ const WRITABLE: u8 = 1 << 1;
 
 impl Interests {
     /// Readable interest.
-    pub const READABLE: Interests = Interests(unsafe { NonZeroU8::new_unchecked(READABLE) });
+    pub const READABLE: Interests = Interests(NonZeroU8::new(READABLE).unwrap());
 
     /// Writable interest.
-    pub const WRITABLE: Interests = Interests(unsafe { NonZeroU8::new_unchecked(WRITABLE) });
+    pub const WRITABLE: Interests = Interests(NonZeroU8::new(WRITABLE).unwrap());
 
     /// Both readable and writable interests, not public because `Interests`
     /// might be expanded in the future.
-    pub(crate) const BOTH: Interests = Interests(unsafe { NonZeroU8::new_unchecked(READABLE | WRITABLE) });
+    pub(crate) const BOTH: Interests = Interests(NonZeroU8::new(READABLE | WRITABLE).unwrap());
 
     /// Returns true if the value includes readable interest.
     #[inline]
@@ -16,12 +16,14 @@
 //! corresponding operation must be performed repeatedly until it returns
 //! [`WouldBlock`]. Unless this is done, there is no guarantee that another
 //! readiness event will be delivered, even if further data is received for the
-//! [`Evented`] handle. See [`RegisterOption`] for more.
+//! [`Evented`] handle. See [`RegisterOption`] for more. To detect handles that
+//! are not drained in debug builds wrap them in [`DrainChecked`].
 //!
 //! [`WouldBlock`]: std::io::ErrorKind::WouldBlock
 //! [edge-triggered]: crate::os::RegisterOption::EDGE
 //! [`Evented`]: crate::os::Evented
 //! [`RegisterOption`]: crate::os::RegisterOption
+//! [`DrainChecked`]: crate::os::DrainChecked
 //!
 //! ### Spurious events
 //!
@@ -82,12 +84,27 @@
 //! The timeout provided to [`event::Source::blocking_poll`] will be rounded
 //! up to the system clock granularity (usually 1ms), and kernel scheduling
 //! delays mean that the blocking interval may be overrun by a small amount.
+//! On Linux and Android timeouts have nanosecond precision, using
+//! `epoll_pwait2` if the kernel supports it or a timer file descriptor
+//! otherwise.
 //!
 //! ### Interrupts while polling
 //!
 //! Interrupts (`EINTR` in C and `io::ErrorKind::Interrupted` in Rust) are
 //! **not** handled, they are returned as errors. In most cases however these
 //! can simply be ignored, but it's up to the user how to deal with the "error".
+//! To only allow signals to interrupt polling use
+//! [`OsQueue::blocking_poll_with_sigmask`] (Linux and Android only).
+//!
+//! ### Forking
+//!
+//! After `fork(2)` the child process shares the system selector with its
+//! parent. [`OsQueue`], [`Awakener`] and [`Signals`] return errors when used in
+//! the child process until they're re-initialised using
+//! [`OsQueue::after_fork_child`].
+//!
+//! [`Awakener`]: crate::os::Awakener
+//! [`Signals`]: crate::os::Signals
 //!
 //! # Implementation notes
 //!
@@ -111,24 +128,47 @@
 //! [`signalfd`]: http://man7.org/linux/man-pages/man2/signalfd.2.html
 
 use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::sync::Arc;
 use std::time::Duration;
 
 use log::trace;
 
-use crate::{event, sys};
+use crate::event;
 
 mod awakener;
+mod busy_poll;
+mod drain_checked;
 mod evented;
+mod fork;
 mod interests;
 mod option;
+mod readiness_cache;
+mod registered;
+mod registrar;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+mod registration;
+mod selector;
+mod tracker;
 
 pub mod signals;
 
 pub use self::awakener::Awakener;
+pub use self::busy_poll::BusyPollStats;
+pub use self::drain_checked::DrainChecked;
 pub use self::evented::Evented;
 pub use self::interests::Interests;
 pub use self::option::RegisterOption;
-pub use self::signals::{Signal, SignalSet, Signals};
+pub use self::readiness_cache::{CachedIo, CachingSink, ReadinessCache};
+pub use self::registered::Registered;
+pub use self::registrar::Registrar;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub use self::registration::{Registration, RegistrationMismatch};
+pub use self::signals::{Signal, SignalInfo, SignalSet, Signals};
+
+pub(crate) use self::selector::Selector;
+
+use self::busy_poll::BusyPoll;
 
 /// Readiness event queue backed by the OS.
 ///
@@ -154,12 +194,80 @@ pub use self::signals::{Signal, SignalSet, Signals};
 /// [associated id]: event::Id
 /// [interests]: Interests
 /// [module documentation]: crate::os
+///
+/// # Multi-threaded use
+///
+/// Registering handles requires mutable access to `OsQueue`, which isn't
+/// possible while another thread is polling it. For this a [`Registrar`] can
+/// be created using [`registrar`], which can register, reregister and
+/// deregister handles from any thread.
+///
+/// [`registrar`]: OsQueue::registrar
+///
+/// # Nesting and embedding
+///
+/// `OsQueue` implements [`AsFd`], and thus [`Evented`], so it can be
+/// registered with another (parent) `OsQueue`. The parent will return a
+/// [readable] event, using the id provided when registering, once one or more
+/// events are ready in the nested queue. Those events can then be retrieved
+/// by [polling] the nested queue. This allows a whole group of handles to be
+/// managed under a single [`event::Id`] in the parent. Note that a queue can't
+/// be registered with itself.
+///
+/// Because `OsQueue` also implements [`AsRawFd`] it can be embedded in a
+/// foreign event loop, e.g. GLib or libuv, by watching the file descriptor for
+/// readability and polling the `OsQueue` once it is readable.
+///
+/// [`AsFd`]: std::os::unix::io::AsFd
+/// [readable]: crate::event::Ready::READABLE
+/// [polling]: crate::event::Source::poll
+///
+/// # Examples
+///
+/// Nesting a queue in another queue.
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::{self, Write};
+/// use std::time::Duration;
+///
+/// use gaea::{event, poll};
+/// use gaea::os::{OsQueue, RegisterOption};
+/// use gaea::unix::{new_pipe, Receiver};
+///
+/// let mut parent = OsQueue::new()?;
+/// let mut child = OsQueue::new()?;
+///
+/// // Register the child queue with the parent queue.
+/// parent.register(&mut child, event::Id(0), OsQueue::INTERESTS, RegisterOption::LEVEL)?;
+///
+/// // Register a handle with the child queue.
+/// let (mut sender, mut receiver) = new_pipe()?;
+/// child.register(&mut receiver, event::Id(1), Receiver::INTERESTS, RegisterOption::LEVEL)?;
+/// sender.write_all(b"Hello")?;
+///
+/// // The parent queue returns an event for the child queue.
+/// let mut events = Vec::new();
+/// poll::<_, io::Error>(&mut [&mut parent], &mut events, Some(Duration::from_millis(100)))?;
+/// assert_eq!(events[0].id(), event::Id(0));
+///
+/// // Which we can poll to retrieve the actual event.
+/// events.clear();
+/// poll::<_, io::Error>(&mut [&mut child], &mut events, Some(Duration::from_millis(0)))?;
+/// assert_eq!(events[0].id(), event::Id(1));
+/// #     Ok(())
+/// # }
+/// ```
 #[derive(Debug)]
 pub struct OsQueue {
-    selector: sys::Selector,
+    registrar: Registrar,
+    busy_poll: BusyPoll,
 }
 
 impl OsQueue {
+    /// The interests to use when registering with another `OsQueue`.
+    pub const INTERESTS: Interests = Interests::READABLE;
+
     /// Create a new OS backed readiness event queue.
     ///
     /// This function will make a syscall to the operating system to create the
@@ -189,7 +297,204 @@ impl OsQueue {
     /// # }
     /// ```
     pub fn new() -> io::Result<OsQueue> {
-        sys::Selector::new().map(|selector| OsQueue { selector })
+        Selector::new(false, None).map(OsQueue::from_selector)
+    }
+
+    /// Create a new OS backed readiness event queue that tracks all
+    /// registrations, to detect misuse.
+    ///
+    /// This is the same as [`OsQueue::new`], but the returned queue records the
+    /// file descriptor, [`event::Id`], [`Interests`] and [`RegisterOption`] of
+    /// each call to [`register`], [`reregister`] and [`deregister`]. It uses
+    /// this to log the following misuses as errors (using the [`log`] crate):
+    ///
+    ///  * registering the same handle twice,
+    ///  * dropping (closing) a handle without deregistering it,
+    ///  * reregistering or deregistering a handle that isn't registered,
+    ///  * using the same id for multiple handles,
+    ///  * receiving an event for an id that isn't registered.
+    ///
+    /// If backtraces are enabled, using the `RUST_BACKTRACE` or
+    /// `RUST_LIB_BACKTRACE` environment variables (see [`Backtrace`]), a
+    /// backtrace is taken at the point of registration and included in the
+    /// logged errors. Capturing a backtrace is expensive, so it's not done by
+    /// default.
+    ///
+    /// Tracking adds overhead to all registration calls and polling, it's
+    /// intended for debugging only.
+    ///
+    /// [`register`]: OsQueue::register
+    /// [`reregister`]: OsQueue::reregister
+    /// [`deregister`]: OsQueue::deregister
+    /// [`log`]: https://crates.io/crates/log
+    /// [`Backtrace`]: std::backtrace::Backtrace
+    pub fn with_tracking() -> io::Result<OsQueue> {
+        Selector::new(true, None).map(OsQueue::from_selector)
+    }
+
+    /// Create a new OS backed readiness event queue with a buffer for
+    /// `capacity` events.
+    ///
+    /// This is the same as [`OsQueue::new`], but sets the initial size of the
+    /// buffer used to retrieve events from the system selector, which limits
+    /// the number of events returned by a single poll. The buffer is reused
+    /// between polls and grows when a poll fills it entirely (up to 65536
+    /// events), so this only needs to be set if the number of events per poll
+    /// is known in advance. Also see [`set_fill_sink`] to poll until the event
+    /// sink is full.
+    ///
+    /// On platforms other than Linux and Android `capacity` is ignored, they
+    /// use a fixed size buffer of 128 events.
+    ///
+    /// [`set_fill_sink`]: OsQueue::set_fill_sink
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::io;
+    /// use std::time::Duration;
+    ///
+    /// use gaea::os::OsQueue;
+    /// use gaea::poll;
+    ///
+    /// // Retrieve up to 1024 events in a single poll.
+    /// let mut os_queue = OsQueue::with_capacity(1024)?;
+    /// let mut events = Vec::new();
+    ///
+    /// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(10)))?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn with_capacity(capacity: usize) -> io::Result<OsQueue> {
+        Selector::new(false, Some(capacity)).map(OsQueue::from_selector)
+    }
+
+    /// Set whether or not polling should continue, without blocking, until
+    /// the event sink's capacity is used up or no more events are ready.
+    ///
+    /// By default a single poll retrieves at most as many events as fit in
+    /// the events buffer (see [`with_capacity`]). When enabled, and the
+    /// buffer is filled, the system selector is polled again with a zero
+    /// timeout, until either the event sink is full, fewer events than
+    /// requested are returned or 65536 events are retrieved. Note that this
+    /// means that an event sink with [growable capacity] will receive all
+    /// ready events, and events for level triggered registrations can be
+    /// received multiple times in a single poll.
+    ///
+    /// This is only available on Linux and Android.
+    ///
+    /// [`with_capacity`]: OsQueue::with_capacity
+    /// [growable capacity]: event::Capacity::Growable
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn set_fill_sink(&mut self, fill: bool) {
+        self.selector().sys().set_fill_sink(fill);
+    }
+
+    /// Create a new [`Registrar`] for this `OsQueue`.
+    ///
+    /// The returned `Registrar` can be send to and shared between threads and
+    /// allows [`Evented`] handles to be registered with this `OsQueue` while
+    /// another thread is polling it. See [`Registrar`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::io::{self, Write};
+    /// use std::thread;
+    ///
+    /// use gaea::os::{OsQueue, RegisterOption};
+    /// use gaea::unix::{new_pipe, Receiver};
+    /// use gaea::{event, poll};
+    ///
+    /// let mut os_queue = OsQueue::new()?;
+    /// let mut events = Vec::new();
+    ///
+    /// let registrar = os_queue.registrar();
+    /// let (mut sender, mut receiver) = new_pipe()?;
+    ///
+    /// // Register the receiving end of the pipe on another thread, while the
+    /// // current thread polls the queue.
+    /// let handle = thread::spawn(move || -> io::Result<Receiver> {
+    ///     registrar.register(&mut receiver, event::Id(0), Receiver::INTERESTS, RegisterOption::EDGE)?;
+    ///     sender.write_all(b"Hello world")?;
+    ///     Ok(receiver)
+    /// });
+    ///
+    /// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+    /// assert_eq!(events[0].id(), event::Id(0));
+    /// # drop(handle.join().unwrap()?);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn registrar(&self) -> Registrar {
+        self.registrar.clone()
+    }
+
+    /// Re-initialise the queue in a child process after forking.
+    ///
+    /// After `fork(2)` the child process shares the system selector with the
+    /// parent process, which means registrations made in one process affect
+    /// the other. To prevent this the queue (and all its [`Registrar`]s)
+    /// returns an error when used in the child process, until this method is
+    /// called.
+    ///
+    /// This creates a new system selector and reregisters all handles with it,
+    /// using the same id, interests and option as the last (re)registration.
+    /// For this the queue always records the file descriptor and arguments of
+    /// each registration, even if it wasn't created using
+    /// [`OsQueue::with_tracking`]. Handles that were closed without being
+    /// deregistered are skipped.
+    ///
+    /// [`Awakener`]s and [`Signals`] created with this queue must be
+    /// re-initialised as well, by calling their `after_fork_child` method
+    /// *after* calling this method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::io;
+    /// use std::time::Duration;
+    ///
+    /// use gaea::{event, poll};
+    /// use gaea::os::{Awakener, OsQueue};
+    ///
+    /// let mut os_queue = OsQueue::new()?;
+    /// let mut awakener = Awakener::new(&mut os_queue, event::Id(0))?;
+    /// let mut events = Vec::new();
+    ///
+    /// match unsafe { libc::fork() } {
+    ///     -1 => return Err(io::Error::last_os_error().into()),
+    ///     0 => {
+    ///         // In the child process the queue can't be used.
+    ///         assert!(poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(0))).is_err());
+    ///
+    ///         // Until it's re-initialised, along with the awakener.
+    ///         os_queue.after_fork_child()?;
+    ///         awakener.after_fork_child(&mut os_queue)?;
+    ///
+    ///         awakener.wake()?;
+    ///         poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(100)))?;
+    ///         assert_eq!(events[0].id(), event::Id(0));
+    ///         unsafe { libc::_exit(0) };
+    ///     },
+    ///     pid => {
+    ///         let mut status = 0;
+    ///         assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+    ///         assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+    ///     },
+    /// }
+    ///
+    /// // The parent isn't affected by the child.
+    /// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(0)))?;
+    /// assert!(events.is_empty());
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn after_fork_child(&mut self) -> io::Result<()> {
+        self.selector().after_fork_child()
     }
 
     /// Register an [`Evented`] handle with the `OsQueue`.
@@ -278,7 +583,23 @@ impl OsQueue {
         where E: Evented + ?Sized,
     {
         trace!("registering handle: id={}, interests={:?}, opt={:?}", id, interests, opt);
-        handle.register(self, id, interests, opt)
+        handle.register(&self.registrar, id, interests, opt)
+    }
+
+    /// Register a handle with the `OsQueue`, taking ownership of it.
+    ///
+    /// This is the same as [`register`], but returns a [`Registered`] handle
+    /// that remembers the registration arguments and deregisters the handle
+    /// when dropped. See [`Registered`] for more.
+    ///
+    /// This is a separate method, rather than the return type of `register`,
+    /// because `register` only borrows the handle.
+    ///
+    /// [`register`]: OsQueue::register
+    pub fn register_owned<E>(&mut self, handle: E, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<Registered<E>>
+        where E: Evented,
+    {
+        Registered::new(self.registrar(), handle, id, interests, opt)
     }
 
     /// Re-register an `Evented` handle with `OsQueue`.
@@ -348,7 +669,7 @@ impl OsQueue {
         where E: Evented + ?Sized,
     {
         trace!("reregistering handle: id={}, interests={:?}, opt={:?}", id, interests, opt);
-        handle.reregister(self, id, interests, opt)
+        handle.reregister(&self.registrar, id, interests, opt)
     }
 
     /// Deregister an `Evented` handle from `OsQueue`.
@@ -407,16 +728,243 @@ impl OsQueue {
         where E: Evented + ?Sized,
     {
         trace!("deregistering handle");
-        handle.deregister(self)
+        handle.deregister(&self.registrar)
+    }
+
+    /// Enable or disable busy polling.
+    ///
+    /// In busy polling mode a [blocking poll] first spins, polling for events
+    /// without blocking for at most `spin` (or the timeout if shorter). Only
+    /// if no events are found in that period does it fall back to blocking.
+    /// This trades CPU time for lower latency, by avoiding the cost of going
+    /// to sleep and being woken up by the kernel. Passing `None` disables
+    /// busy polling, which is the default.
+    ///
+    /// [`busy_poll_stats`] can be used to see how often the spin phase found
+    /// events. See [`set_busy_poll_params`] to configure busy polling in the
+    /// kernel (Linux only).
+    ///
+    /// [blocking poll]: event::Source::blocking_poll
+    /// [`busy_poll_stats`]: OsQueue::busy_poll_stats
+    /// [`set_busy_poll_params`]: OsQueue::set_busy_poll_params
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::io;
+    /// use std::time::Duration;
+    ///
+    /// use gaea::os::OsQueue;
+    /// use gaea::poll;
+    ///
+    /// let mut os_queue = OsQueue::new()?;
+    /// let mut events = Vec::new();
+    ///
+    /// // Spin for 50 microseconds before blocking.
+    /// os_queue.set_busy_poll(Some(Duration::from_micros(50)));
+    ///
+    /// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(1)))?;
+    /// // No events, so the spin phase missed.
+    /// assert_eq!(os_queue.busy_poll_stats().misses(), 1);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn set_busy_poll(&mut self, spin: Option<Duration>) {
+        self.busy_poll.set_spin(spin);
+    }
+
+    /// Returns the statistics of the [busy polling mode].
+    ///
+    /// [busy polling mode]: OsQueue::set_busy_poll
+    pub fn busy_poll_stats(&self) -> BusyPollStats {
+        self.busy_poll.stats()
+    }
+
+    /// Set the kernel's busy poll parameters of the system selector, using
+    /// `EPIOCSPARAMS`.
+    ///
+    /// This makes the kernel busy poll the network devices of the registered
+    /// sockets for `usecs` microseconds, processing up to `budget` packets per
+    /// poll (0 uses the kernel's default), before going to sleep. If `prefer`
+    /// is true the kernel prefers busy polling over interrupts. Setting a
+    /// budget larger than the kernel's default requires `CAP_NET_ADMIN`.
+    /// Also see the `SO_BUSY_POLL` option, e.g. [`TcpStream::set_busy_poll`].
+    ///
+    /// This is only available on Linux (6.9+).
+    ///
+    /// [`TcpStream::set_busy_poll`]: crate::net::TcpStream::set_busy_poll
+    #[cfg(target_os = "linux")]
+    pub fn set_busy_poll_params(&mut self, usecs: u32, budget: u16, prefer: bool) -> io::Result<()> {
+        self.selector().sys().set_busy_poll_params(usecs, budget, prefer)
+    }
+
+    /// Poll for readiness events, unblocking the signals in `unblock` while
+    /// waiting.
+    ///
+    /// This is the same as [`event::Source::blocking_poll`], but the signals
+    /// in `unblock` are removed from the signal mask of the calling thread for
+    /// the duration of the call only. The signal mask is changed and restored
+    /// atomically by the kernel (using `epoll_pwait`), so there is no race
+    /// between checking a flag set by a signal handler and waiting for
+    /// events. Combined with blocking the signals at all other times this
+    /// ensures that signal handlers are only run while polling.
+    ///
+    /// If a signal handler ran while waiting this returns
+    /// [`PollOutcome::Interrupted`], rather than an `io::Error`. Note that
+    /// signals received using [`Signals`] are blocked and never call a signal
+    /// handler, don't unblock those using this function.
+    ///
+    /// This always waits in the kernel, it bypasses the spin phase enabled by
+    /// [`set_busy_poll`] and doesn't update the [`BusyPollStats`].
+    ///
+    /// This is only available on Linux and Android.
+    ///
+    /// [`set_busy_poll`]: OsQueue::set_busy_poll
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::time::Duration;
+    ///
+    /// use gaea::os::{OsQueue, PollOutcome, Signal};
+    ///
+    /// let mut os_queue = OsQueue::new()?;
+    /// let mut events = Vec::new();
+    ///
+    /// // Only allow the quit signal to interrupt us while waiting for events.
+    /// let outcome = os_queue.blocking_poll_with_sigmask(&mut events,
+    ///     Some(Duration::from_millis(10)), Signal::Quit.into())?;
+    /// assert_eq!(outcome, PollOutcome::Completed);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn blocking_poll_with_sigmask<ES>(&mut self, event_sink: &mut ES, timeout: Option<Duration>, unblock: SignalSet) -> io::Result<PollOutcome>
+        where ES: event::Sink,
+    {
+        trace!("polling OS queue with signal mask: timeout={:?}, unblock={:?}", timeout, unblock);
+        match self.selector().select_with_sigmask(event_sink, timeout, unblock) {
+            Ok(()) => Ok(PollOutcome::Completed),
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => Ok(PollOutcome::Interrupted),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns all registrations of this `OsQueue` as known to the kernel.
+    ///
+    /// This reads and parses `/proc/self/fdinfo` for the system selector,
+    /// which shows what the kernel actually has registered, rather than what
+    /// we believe to have registered. Note that the kernel removes a
+    /// registration once all duplicates of the registered file descriptor are
+    /// closed.
+    ///
+    /// This is only available on Linux and Android.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::os::unix::io::AsRawFd;
+    ///
+    /// use gaea::event;
+    /// use gaea::os::{Interests, OsQueue, RegisterOption};
+    /// use gaea::unix::{new_pipe, Receiver};
+    ///
+    /// let mut os_queue = OsQueue::new()?;
+    ///
+    /// let (sender, mut receiver) = new_pipe()?;
+    /// os_queue.register(&mut receiver, event::Id(0), Receiver::INTERESTS, RegisterOption::EDGE)?;
+    ///
+    /// let registrations = os_queue.registrations()?;
+    /// assert_eq!(registrations.len(), 1);
+    /// assert_eq!(registrations[0].fd(), receiver.as_raw_fd());
+    /// assert_eq!(registrations[0].id(), event::Id(0));
+    /// assert_eq!(registrations[0].interests(), Some(Interests::READABLE));
+    /// assert_eq!(registrations[0].opt(), RegisterOption::EDGE);
+    /// # drop(sender);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn registrations(&self) -> io::Result<Vec<Registration>> {
+        self.selector().registrations()
+    }
+
+    /// Compare the [registrations known to the kernel] with the registrations
+    /// recorded by the tracker, returning all differences.
+    ///
+    /// This can be used to find leaked registrations, e.g. a handle that was
+    /// registered using a duplicated file descriptor which is never
+    /// deregistered, and stale registrations, e.g. a handle that was dropped
+    /// without being deregistered. A [oneshot] registration that is disabled
+    /// by the kernel, after returning an event, is not considered a
+    /// difference.
+    ///
+    /// This requires the `OsQueue` to be created using [`with_tracking`], if
+    /// it wasn't this returns an error of kind `InvalidInput`.
+    ///
+    /// This is only available on Linux and Android.
+    ///
+    /// [registrations known to the kernel]: OsQueue::registrations
+    /// [oneshot]: RegisterOption::ONESHOT
+    /// [`with_tracking`]: OsQueue::with_tracking
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use gaea::event;
+    /// use gaea::os::{OsQueue, RegisterOption, RegistrationMismatch};
+    /// use gaea::unix::{new_pipe, Receiver};
+    ///
+    /// let mut os_queue = OsQueue::with_tracking()?;
+    ///
+    /// let (sender, mut receiver) = new_pipe()?;
+    /// os_queue.register(&mut receiver, event::Id(0), Receiver::INTERESTS, RegisterOption::EDGE)?;
+    /// assert!(os_queue.check_registrations()?.is_empty());
+    ///
+    /// // Dropping the receiver without deregistering it leaves a stale
+    /// // registration in the tracker.
+    /// drop(receiver);
+    /// match os_queue.check_registrations()?.as_slice() {
+    ///     [RegistrationMismatch::Stale(registration)] => assert_eq!(registration.id(), event::Id(0)),
+    ///     mismatches => panic!("unexpected mismatches: {:?}", mismatches),
+    /// }
+    /// # drop(sender);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn check_registrations(&self) -> io::Result<Vec<RegistrationMismatch>> {
+        self.selector().check_registrations()
+    }
+
+    /// Create a new `OsQueue` using `selector`.
+    fn from_selector(selector: Selector) -> OsQueue {
+        OsQueue { registrar: Registrar::new(Arc::new(selector)), busy_poll: BusyPoll::default() }
     }
 
     /// Get access to the system selector. Used by platform specific code, e.g.
-    /// `EventedFd`.
-    pub(crate) fn selector(&self) -> &sys::Selector {
-        &self.selector
+    /// `Signals`.
+    pub(crate) fn selector(&self) -> &Selector {
+        self.registrar.selector()
     }
 }
 
+/// Outcome of [`OsQueue::blocking_poll_with_sigmask`].
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PollOutcome {
+    /// Polling completed, either because events were added to the event sink
+    /// or because the timeout elapsed.
+    Completed,
+    /// Polling was interrupted by a signal handler, no events were added to
+    /// the event sink.
+    Interrupted,
+}
+
 impl<ES, E> event::Source<ES, E> for OsQueue
     where ES: event::Sink,
           E: From<io::Error>,
@@ -432,7 +980,36 @@ impl<ES, E> event::Source<ES, E> for OsQueue
 
     fn blocking_poll(&mut self, event_sink: &mut ES, timeout: Option<Duration>) -> Result<(), E> {
         trace!("polling OS queue: timeout={:?}", timeout);
-        self.selector.select(event_sink, timeout)
+        self.registrar.selector().select(&mut self.busy_poll, event_sink, timeout)
             .map_err(Into::into)
     }
 }
+
+impl Evented for OsQueue {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        debug_assert!(!interests.is_writable(), "OsQueue can never be written");
+        self.as_fd().register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        debug_assert!(!interests.is_writable(), "OsQueue can never be written");
+        self.as_fd().reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
+    }
+}
+
+impl AsFd for OsQueue {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // Safety: the file descriptor is valid as long as the selector is.
+        unsafe { BorrowedFd::borrow_raw(self.as_raw_fd()) }
+    }
+}
+
+impl AsRawFd for OsQueue {
+    fn as_raw_fd(&self) -> RawFd {
+        self.selector().as_raw_fd()
+    }
+}
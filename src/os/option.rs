@@ -83,6 +83,18 @@ use std::ops::BitOr;
 /// [`WouldBlock`]: std::io::ErrorKind::WouldBlock
 /// [`reregister`]: crate::os::OsQueue::reregister
 ///
+/// # Exclusive wake ups
+///
+/// When the same handle, e.g. a [`TcpListener`] cloned using [`try_clone`], is
+/// registered with multiple `OsQueue`s, each polled on its own thread, every
+/// queue is woken when the handle becomes ready. Using the [exclusive] option
+/// only one (or a few) of the queues that are blocked polling are woken,
+/// avoiding the "thundering herd" problem.
+///
+/// [`TcpListener`]: crate::net::TcpListener
+/// [`try_clone`]: crate::net::TcpListener::try_clone
+/// [exclusive]: crate::os::RegisterOption::EXCLUSIVE
+///
 /// # Notes
 ///
 /// It is not possible to combine edge and level triggers.
@@ -93,6 +105,8 @@ pub struct RegisterOption(u8);
 // Level trigger is 0.
 const EDGE: u8    = 1;
 const ONESHOT: u8 = 1 << 1;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+const EXCLUSIVE: u8 = 1 << 2;
 
 impl RegisterOption {
     /// Level-triggered notifications.
@@ -104,6 +118,32 @@ impl RegisterOption {
     /// Oneshot notifications.
     pub const ONESHOT: RegisterOption = RegisterOption(ONESHOT);
 
+    /// Exclusive wake ups, only supported on Linux.
+    ///
+    /// This maps to `EPOLLEXCLUSIVE`, see [`epoll_ctl(2)`]. It can't be
+    /// combined with the [oneshot] option and can only be used when
+    /// [registering] a handle, not when [reregistering]. Attempting either
+    /// will return an [`InvalidInput`] error.
+    ///
+    /// Note that the kernel also doesn't allow an exclusive registration to be
+    /// modified at all, the handle must be [deregistered] and registered again.
+    ///
+    /// The kernel also doesn't allow `EPOLLRDHUP` and `EPOLLPRI` to be used
+    /// with `EPOLLEXCLUSIVE`. This means that exclusive registrations don't
+    /// receive [hang up] readiness when only the peer closed its writing half
+    /// of a connection, and no readable readiness for out-of-band (priority)
+    /// data. A full hang up and errors are still reported.
+    ///
+    /// [`epoll_ctl(2)`]: http://man7.org/linux/man-pages/man2/epoll_ctl.2.html
+    /// [oneshot]: RegisterOption::ONESHOT
+    /// [registering]: crate::os::OsQueue::register
+    /// [reregistering]: crate::os::OsQueue::reregister
+    /// [`InvalidInput`]: std::io::ErrorKind::InvalidInput
+    /// [deregistered]: crate::os::OsQueue::deregister
+    /// [hang up]: crate::event::Ready::HUP
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub const EXCLUSIVE: RegisterOption = RegisterOption(EXCLUSIVE);
+
     /// Returns true if the value includes level trigger.
     #[inline]
     pub const fn is_level(self) -> bool {
@@ -121,6 +161,13 @@ impl RegisterOption {
     pub const fn is_oneshot(self) -> bool {
         self.0 & ONESHOT != 0
     }
+
+    /// Returns true if the value includes exclusive wake ups.
+    #[inline]
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub const fn is_exclusive(self) -> bool {
+        self.0 & EXCLUSIVE != 0
+    }
 }
 
 impl BitOr for RegisterOption {
@@ -133,6 +180,18 @@ impl BitOr for RegisterOption {
 
 impl fmt::Debug for RegisterOption {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        {
+            if self.is_exclusive() {
+                return f.pad(match (self.is_edge(), self.is_oneshot()) {
+                    (false, false) => "LEVEL | EXCLUSIVE",
+                    (true, false) => "EDGE | EXCLUSIVE",
+                    (false, true) => "LEVEL | ONESHOT | EXCLUSIVE",
+                    (true, true) => "EDGE | ONESHOT | EXCLUSIVE",
+                });
+            }
+        }
+
         f.pad(match (self.is_edge(), self.is_oneshot()) {
             (false, false) => "LEVEL",
             (true, false) => "EDGE",
@@ -161,6 +220,22 @@ mod tests {
         assert!(RegisterOption::ONESHOT.is_oneshot());
     }
 
+    #[test]
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    fn is_exclusive() {
+        assert!(!RegisterOption::LEVEL.is_exclusive());
+        assert!(!RegisterOption::EDGE.is_exclusive());
+        assert!(!RegisterOption::ONESHOT.is_exclusive());
+
+        assert!(RegisterOption::EXCLUSIVE.is_exclusive());
+        assert!(RegisterOption::EXCLUSIVE.is_level());
+        assert!(!RegisterOption::EXCLUSIVE.is_oneshot());
+
+        let opt = RegisterOption::EDGE | RegisterOption::EXCLUSIVE;
+        assert!(opt.is_edge());
+        assert!(opt.is_exclusive());
+    }
+
     #[test]
     fn bit_or() {
         let opt = RegisterOption::LEVEL | RegisterOption::ONESHOT;
@@ -182,4 +257,11 @@ mod tests {
         assert_eq!(format!("{:?}", RegisterOption::LEVEL | RegisterOption::ONESHOT), "LEVEL | ONESHOT");
         assert_eq!(format!("{:?}", RegisterOption::EDGE | RegisterOption::ONESHOT), "EDGE | ONESHOT");
     }
+
+    #[test]
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    fn fmt_debug_exclusive() {
+        assert_eq!(format!("{:?}", RegisterOption::EXCLUSIVE), "LEVEL | EXCLUSIVE");
+        assert_eq!(format!("{:?}", RegisterOption::EDGE | RegisterOption::EXCLUSIVE), "EDGE | EXCLUSIVE");
+    }
 }
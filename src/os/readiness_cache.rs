@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use crate::event::{self, Event, Ready};
+
+/// Cache of the readiness of handles, for use with edge-triggered handles.
+///
+/// When a handle is registered using [`RegisterOption::EDGE`] a readiness
+/// event is only returned once, the handle remains ready until an operation
+/// returns a [`WouldBlock`] error. This means that the readiness must be
+/// remembered across polls, which is what `ReadinessCache` does.
+///
+/// Readiness events are recorded by using the cache as an [event sink], or by
+/// using [`sink`] to record events while passing them to another event sink.
+/// Whether or not a handle is ready can then be checked using [`is_ready`].
+/// I/O operations should be done using [`io`], which wraps a handle and
+/// clears the cached readable (writable) readiness once a read (write)
+/// returns a `WouldBlock` error.
+///
+/// Note that ids don't have to be unique, all handles using the same id share
+/// the cached readiness.
+///
+/// [`RegisterOption::EDGE`]: crate::os::RegisterOption::EDGE
+/// [`WouldBlock`]: io::ErrorKind::WouldBlock
+/// [event sink]: event::Sink
+/// [`sink`]: ReadinessCache::sink
+/// [`is_ready`]: ReadinessCache::is_ready
+/// [`io`]: ReadinessCache::io
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::{self, Read, Write};
+///
+/// use gaea::os::{OsQueue, ReadinessCache, RegisterOption};
+/// use gaea::unix::{new_pipe, Receiver};
+/// use gaea::{event, poll, Ready};
+///
+/// let mut os_queue = OsQueue::new()?;
+/// let mut cache = ReadinessCache::new();
+///
+/// let (mut sender, mut receiver) = new_pipe()?;
+/// let id = event::Id(0);
+/// os_queue.register(&mut receiver, id, Receiver::INTERESTS, RegisterOption::EDGE)?;
+///
+/// sender.write_all(b"Hello world")?;
+/// poll::<_, io::Error>(&mut [&mut os_queue], &mut cache, None)?;
+/// assert!(cache.is_ready(id, Ready::READABLE));
+///
+/// // Read only part of the data, the receiver remains readable.
+/// let mut buf = [0; 5];
+/// cache.io(id, &mut receiver).read_exact(&mut buf)?;
+/// assert!(cache.is_ready(id, Ready::READABLE));
+///
+/// // Read the remainder, until `WouldBlock`.
+/// let mut buf = [0; 20];
+/// let n = cache.io(id, &mut receiver).read(&mut buf)?;
+/// assert_eq!(&buf[..n], b" world");
+/// let err = cache.io(id, &mut receiver).read(&mut buf).unwrap_err();
+/// assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+/// assert!(!cache.is_ready(id, Ready::READABLE));
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ReadinessCache {
+    readiness: HashMap<event::Id, Ready>,
+}
+
+impl ReadinessCache {
+    /// Create a new empty cache.
+    pub fn new() -> ReadinessCache {
+        ReadinessCache::default()
+    }
+
+    /// Returns true if the handle with `id` is ready for all of `readiness`.
+    pub fn is_ready(&self, id: event::Id, readiness: Ready) -> bool {
+        self.readiness(id).contains(readiness)
+    }
+
+    /// Returns the cached readiness of the handle with `id`.
+    pub fn readiness(&self, id: event::Id) -> Ready {
+        self.readiness.get(&id).cloned().unwrap_or(Ready::EMPTY)
+    }
+
+    /// Add `readiness` to the cached readiness of the handle with `id`.
+    pub fn set_ready(&mut self, id: event::Id, readiness: Ready) {
+        *self.readiness.entry(id).or_insert(Ready::EMPTY) |= readiness;
+    }
+
+    /// Remove `readiness` from the cached readiness of the handle with `id`.
+    pub fn clear(&mut self, id: event::Id, readiness: Ready) {
+        if let Some(cached) = self.readiness.get_mut(&id) {
+            *cached = cached.remove(readiness);
+            if *cached == Ready::EMPTY {
+                let _ = self.readiness.remove(&id);
+            }
+        }
+    }
+
+    /// Remove all cached readiness of the handle with `id`, e.g. when the
+    /// handle is deregistered.
+    pub fn remove(&mut self, id: event::Id) {
+        let _ = self.readiness.remove(&id);
+    }
+
+    /// Returns an event sink adapter that records all events in the cache,
+    /// before passing them to `event_sink`.
+    pub fn sink<'a, ES>(&'a mut self, event_sink: &'a mut ES) -> CachingSink<'a, ES>
+        where ES: event::Sink,
+    {
+        CachingSink { cache: self, sink: event_sink }
+    }
+
+    /// Wrap `handle`, with `id`, to clear the cached readiness once an
+    /// operation returns a [`WouldBlock`] error.
+    ///
+    /// [`WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn io<'a, H>(&'a mut self, id: event::Id, handle: &'a mut H) -> CachedIo<'a, H> {
+        CachedIo { cache: self, id, handle }
+    }
+}
+
+impl event::Sink for ReadinessCache {
+    fn capacity_left(&self) -> event::Capacity {
+        event::Capacity::Growable
+    }
+
+    fn add(&mut self, event: Event) {
+        self.set_ready(event.id(), event.readiness());
+    }
+}
+
+/// Event sink adapter that records events in a [`ReadinessCache`].
+///
+/// Created by [`ReadinessCache::sink`].
+#[derive(Debug)]
+pub struct CachingSink<'a, ES> {
+    cache: &'a mut ReadinessCache,
+    sink: &'a mut ES,
+}
+
+impl<'a, ES> event::Sink for CachingSink<'a, ES>
+    where ES: event::Sink,
+{
+    fn capacity_left(&self) -> event::Capacity {
+        self.sink.capacity_left()
+    }
+
+    fn add(&mut self, event: Event) {
+        self.cache.add(event);
+        self.sink.add(event);
+    }
+}
+
+/// Handle wrapper that clears the readiness cached in a [`ReadinessCache`]
+/// once an operation returns a [`WouldBlock`] error.
+///
+/// Created by [`ReadinessCache::io`].
+///
+/// [`WouldBlock`]: io::ErrorKind::WouldBlock
+#[derive(Debug)]
+pub struct CachedIo<'a, H> {
+    cache: &'a mut ReadinessCache,
+    id: event::Id,
+    handle: &'a mut H,
+}
+
+impl<'a, H> CachedIo<'a, H> {
+    /// Clear `readiness` if `result` is a `WouldBlock` error.
+    fn check<T>(&mut self, result: io::Result<T>, readiness: Ready) -> io::Result<T> {
+        if let Err(ref err) = result {
+            if err.kind() == io::ErrorKind::WouldBlock {
+                self.cache.clear(self.id, readiness);
+            }
+        }
+        result
+    }
+}
+
+impl<'a, H> Read for CachedIo<'a, H>
+    where H: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.handle.read(buf);
+        self.check(result, Ready::READABLE)
+    }
+}
+
+impl<'a, H> Write for CachedIo<'a, H>
+    where H: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.handle.write(buf);
+        self.check(result, Ready::WRITABLE)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.handle.flush();
+        self.check(result, Ready::WRITABLE)
+    }
+}
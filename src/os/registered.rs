@@ -0,0 +1,207 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+use log::error;
+
+use crate::event;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+
+/// A handle registered with an [`OsQueue`], which deregisters itself when
+/// dropped.
+///
+/// Created by [`OsQueue::register_owned`]. `Registered` owns the handle and
+/// remembers the [`event::Id`], [`Interests`] and [`RegisterOption`] used to
+/// register it. This allows the registration to be updated without having to
+/// supply all arguments again, see [`rearm`] and [`set_interests`]. The
+/// handle itself can be accessed as `Registered` implements `Deref` and
+/// `DerefMut`.
+///
+/// When `Registered` is dropped the handle is deregistered, any errors are
+/// logged. To handle the error use [`deregister`] instead.
+///
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`OsQueue::register_owned`]: crate::os::OsQueue::register_owned
+/// [`rearm`]: Registered::rearm
+/// [`set_interests`]: Registered::set_interests
+/// [`deregister`]: Registered::deregister
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::{self, Read, Write};
+///
+/// use gaea::os::{OsQueue, RegisterOption};
+/// use gaea::unix::{new_pipe, Receiver};
+/// use gaea::{event, poll};
+///
+/// let mut os_queue = OsQueue::new()?;
+/// let mut events = Vec::new();
+///
+/// let (mut sender, receiver) = new_pipe()?;
+/// let mut receiver = os_queue.register_owned(receiver, event::Id(0),
+///     Receiver::INTERESTS, RegisterOption::ONESHOT)?;
+///
+/// sender.write_all(b"Hello")?;
+/// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+/// assert_eq!(events[0].id(), event::Id(0));
+///
+/// // `Registered` dereferences to the handle.
+/// let mut buf = [0; 10];
+/// let n = receiver.read(&mut buf)?;
+/// assert_eq!(&buf[..n], b"Hello");
+///
+/// // Rearm the oneshot registration, using the same id, interests and option.
+/// receiver.rearm()?;
+///
+/// // Deregistered when dropped.
+/// drop(receiver);
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Registered<E>
+    where E: Evented,
+{
+    /// Always `Some`, only `None` after the handle is taken in `deregister`.
+    handle: Option<E>,
+    registrar: Registrar,
+    id: event::Id,
+    interests: Interests,
+    opt: RegisterOption,
+}
+
+impl<E> Registered<E>
+    where E: Evented,
+{
+    /// Register `handle` using `registrar`.
+    pub(crate) fn new(registrar: Registrar, mut handle: E, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<Registered<E>> {
+        registrar.register(&mut handle, id, interests, opt)
+            .map(|()| Registered { handle: Some(handle), registrar, id, interests, opt })
+    }
+
+    /// Returns the id used in the registration.
+    pub fn id(&self) -> event::Id {
+        self.id
+    }
+
+    /// Returns the interests used in the registration.
+    pub fn interests(&self) -> Interests {
+        self.interests
+    }
+
+    /// Returns the option used in the registration.
+    pub fn opt(&self) -> RegisterOption {
+        self.opt
+    }
+
+    /// Rearm the registration using the same id, interests and option.
+    ///
+    /// This is required for [oneshot] registrations to receive more events,
+    /// for other registrations this does nothing.
+    ///
+    /// [oneshot]: RegisterOption::ONESHOT
+    pub fn rearm(&mut self) -> io::Result<()> {
+        if self.opt.is_oneshot() {
+            let handle = self.handle.as_mut().unwrap();
+            self.registrar.reregister(handle, self.id, self.interests, self.opt)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Change the interests of the registration, using the same id and option.
+    ///
+    /// If `interests` are the same as the current interests this does nothing.
+    /// Note that this means that a [oneshot] registration isn't rearmed, use
+    /// [`rearm`] for that.
+    ///
+    /// [oneshot]: RegisterOption::ONESHOT
+    /// [`rearm`]: Registered::rearm
+    pub fn set_interests(&mut self, interests: Interests) -> io::Result<()> {
+        self.reregister(self.id, interests, self.opt)
+    }
+
+    /// Change the registration, see [`OsQueue::reregister`].
+    ///
+    /// If the `id`, `interests` and `opt` are the same as the current values
+    /// this does nothing.
+    ///
+    /// The kernel doesn't allow [exclusive] registrations to be modified, if
+    /// either the current or the new option is exclusive the handle is
+    /// deregistered and registered again. If registering fails the original
+    /// registration is restored, if possible.
+    ///
+    /// [`OsQueue::reregister`]: crate::os::OsQueue::reregister
+    /// [exclusive]: RegisterOption::EXCLUSIVE
+    pub fn reregister(&mut self, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        if self.id == id && self.interests == interests && self.opt == opt {
+            return Ok(());
+        }
+
+        let handle = self.handle.as_mut().unwrap();
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        let exclusive = self.opt.is_exclusive() || opt.is_exclusive();
+        #[cfg(not(any(target_os = "android", target_os = "linux")))]
+        let exclusive = false;
+        if exclusive {
+            // The kernel doesn't allow exclusive registrations to be modified,
+            // so we have to deregister and register the handle again.
+            self.registrar.deregister(handle)?;
+            if let Err(err) = self.registrar.register(handle, id, interests, opt) {
+                // Try to restore the original registration.
+                let _ = self.registrar.register(handle, self.id, self.interests, self.opt);
+                return Err(err);
+            }
+        } else {
+            self.registrar.reregister(handle, id, interests, opt)?;
+        }
+        self.id = id;
+        self.interests = interests;
+        self.opt = opt;
+        Ok(())
+    }
+
+    /// Deregister the handle, returning it.
+    ///
+    /// This is the same as dropping `Registered`, but returns the handle and
+    /// any error from deregistering. The handle is also returned if
+    /// deregistering fails.
+    pub fn deregister(mut self) -> Result<E, (E, io::Error)> {
+        let mut handle = self.handle.take().unwrap();
+        match self.registrar.deregister(&mut handle) {
+            Ok(()) => Ok(handle),
+            Err(err) => Err((handle, err)),
+        }
+    }
+}
+
+impl<E> Deref for Registered<E>
+    where E: Evented,
+{
+    type Target = E;
+
+    fn deref(&self) -> &E {
+        self.handle.as_ref().unwrap()
+    }
+}
+
+impl<E> DerefMut for Registered<E>
+    where E: Evented,
+{
+    fn deref_mut(&mut self) -> &mut E {
+        self.handle.as_mut().unwrap()
+    }
+}
+
+impl<E> Drop for Registered<E>
+    where E: Evented,
+{
+    fn drop(&mut self) {
+        if let Some(ref mut handle) = self.handle {
+            if let Err(err) = self.registrar.deregister(handle) {
+                error!("error deregistering handle: id={}: {}", self.id, err);
+            }
+        }
+    }
+}
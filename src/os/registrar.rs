@@ -0,0 +1,118 @@
+use std::io;
+use std::sync::Arc;
+
+use log::trace;
+
+use crate::event;
+use crate::os::{Evented, Interests, RegisterOption, Selector};
+
+/// Registration handle for an [`OsQueue`], that can be shared between threads.
+///
+/// Registering an [`Evented`] handle with [`OsQueue`] requires mutable access
+/// to the queue, which means that a handle can't be registered while another
+/// thread is polling it. A `Registrar` doesn't have this restriction, it can
+/// [register], [reregister] and [deregister] handles from any thread, while
+/// the `OsQueue` is being polled on another thread.
+///
+/// A `Registrar` is also what is passed to the methods of [`Evented`], so any
+/// `Evented` handle can be registered using it.
+///
+/// A `Registrar` is created by calling [`OsQueue::registrar`]. It can be
+/// cloned cheaply, all clones refer to the same `OsQueue`. The `Registrar`
+/// also keeps the underlying system selector alive, even if the `OsQueue` is
+/// dropped.
+///
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`OsQueue::registrar`]: crate::os::OsQueue::registrar
+/// [`Evented`]: crate::os::Evented
+/// [register]: Registrar::register
+/// [reregister]: Registrar::reregister
+/// [deregister]: Registrar::deregister
+///
+/// # Examples
+///
+/// Accepting connections on one thread, while another thread polls the
+/// `OsQueue` to which the accepted connections are registered.
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::thread;
+///
+/// use gaea::event;
+/// use gaea::net::{TcpListener, TcpStream};
+/// use gaea::os::{OsQueue, RegisterOption};
+///
+/// let os_queue = OsQueue::new()?;
+/// let registrar = os_queue.registrar();
+///
+/// let address = "127.0.0.1:0".parse()?;
+/// let mut listener = TcpListener::bind(address)?;
+///
+/// let handle = thread::spawn(move || {
+///     let mut id = 0;
+///     while let Ok((mut stream, _)) = listener.accept() {
+///         // Register the connection with the `OsQueue` that is being polled
+///         // on another thread.
+///         registrar.register(&mut stream, event::Id(id), TcpStream::INTERESTS, RegisterOption::EDGE)
+///             .expect("unable to register connection");
+///         id += 1;
+///     }
+/// });
+/// # handle.join().unwrap();
+/// # drop(os_queue);
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Registrar {
+    selector: Arc<Selector>,
+}
+
+impl Registrar {
+    /// Create a new `Registrar` from a shared selector, used by
+    /// `OsQueue::registrar`.
+    pub(crate) const fn new(selector: Arc<Selector>) -> Registrar {
+        Registrar { selector }
+    }
+
+    /// Get access to the system selector. Used by `Evented` implementations.
+    pub(crate) fn selector(&self) -> &Selector {
+        &self.selector
+    }
+
+    /// Register a handle with the associated `OsQueue`.
+    ///
+    /// See [`OsQueue::register`] for more information.
+    ///
+    /// [`OsQueue::register`]: crate::os::OsQueue::register
+    pub fn register<E>(&self, handle: &mut E, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()>
+        where E: Evented + ?Sized,
+    {
+        trace!("registering handle using registrar: id={}, interests={:?}, opt={:?}", id, interests, opt);
+        handle.register(self, id, interests, opt)
+    }
+
+    /// Re-register a handle with the associated `OsQueue`.
+    ///
+    /// See [`OsQueue::reregister`] for more information.
+    ///
+    /// [`OsQueue::reregister`]: crate::os::OsQueue::reregister
+    pub fn reregister<E>(&self, handle: &mut E, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()>
+        where E: Evented + ?Sized,
+    {
+        trace!("reregistering handle using registrar: id={}, interests={:?}, opt={:?}", id, interests, opt);
+        handle.reregister(self, id, interests, opt)
+    }
+
+    /// Deregister a handle from the associated `OsQueue`.
+    ///
+    /// See [`OsQueue::deregister`] for more information.
+    ///
+    /// [`OsQueue::deregister`]: crate::os::OsQueue::deregister
+    pub fn deregister<E>(&self, handle: &mut E) -> io::Result<()>
+        where E: Evented + ?Sized,
+    {
+        trace!("deregistering handle using registrar");
+        handle.deregister(self)
+    }
+}
@@ -0,0 +1,77 @@
+use std::os::unix::io::RawFd;
+
+use crate::event;
+use crate::os::{Interests, RegisterOption};
+
+/// Registration of a file descriptor with an [`OsQueue`], as seen by the
+/// kernel.
+///
+/// Returned by [`OsQueue::registrations`].
+///
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`OsQueue::registrations`]: crate::os::OsQueue::registrations
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Registration {
+    fd: RawFd,
+    id: event::Id,
+    interests: Option<Interests>,
+    opt: RegisterOption,
+}
+
+impl Registration {
+    /// Create a new `Registration`.
+    pub(crate) const fn new(fd: RawFd, id: event::Id, interests: Option<Interests>, opt: RegisterOption) -> Registration {
+        Registration { fd, id, interests, opt }
+    }
+
+    /// Returns the registered file descriptor.
+    pub const fn fd(&self) -> RawFd {
+        self.fd
+    }
+
+    /// Returns the id of the registration.
+    pub const fn id(&self) -> event::Id {
+        self.id
+    }
+
+    /// Returns the interests of the registration.
+    ///
+    /// This returns `None` if the registration is disabled, which is the case
+    /// for a [oneshot] registration after an event was returned.
+    ///
+    /// [oneshot]: RegisterOption::ONESHOT
+    pub const fn interests(&self) -> Option<Interests> {
+        self.interests
+    }
+
+    /// Returns the option of the registration.
+    pub const fn opt(&self) -> RegisterOption {
+        self.opt
+    }
+}
+
+/// Difference between the registrations of an [`OsQueue`] known to the
+/// kernel and those recorded by its tracker.
+///
+/// Returned by [`OsQueue::check_registrations`].
+///
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`OsQueue::check_registrations`]: crate::os::OsQueue::check_registrations
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RegistrationMismatch {
+    /// The kernel has a registration that isn't tracked, i.e. a leaked
+    /// registration, for example one made using a duplicated file descriptor
+    /// that was never deregistered.
+    Untracked(Registration),
+    /// A tracked registration isn't known to the kernel, i.e. a stale
+    /// registration, for example of a handle that was dropped without being
+    /// deregistered.
+    Stale(Registration),
+    /// The registration known to the kernel differs from the tracked one.
+    Different {
+        /// The registration as tracked.
+        tracked: Registration,
+        /// The registration as known to the kernel.
+        kernel: Registration,
+    },
+}
@@ -0,0 +1,226 @@
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{error, warn};
+
+use crate::event::{self, Event};
+use crate::os::busy_poll::BusyPoll;
+use crate::os::drain_checked::DrainRegistry;
+use crate::os::fork;
+use crate::os::tracker::{Misuse, Tracker};
+use crate::os::{Interests, RegisterOption};
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use crate::os::{Registration, RegistrationMismatch, SignalSet};
+use crate::sys;
+
+/// System selector used by `OsQueue`, shared with its `Registrar`s.
+///
+/// This wraps the platform specific selector and tracks all registrations, to
+/// restore them after forking and, if enabled, to detect misuse of `OsQueue`. It also holds the registry of
+/// `DrainChecked` handles.
+#[derive(Debug)]
+pub(crate) struct Selector {
+    sys: sys::Selector,
+    tracker: Mutex<Tracker>,
+    /// Whether or not misuse is detected, see `OsQueue::with_tracking`.
+    tracking: bool,
+    drain: Arc<DrainRegistry>,
+    /// Fork generation in which `sys` was created, see the `fork` module.
+    fork_generation: AtomicUsize,
+}
+
+impl Selector {
+    /// Create a new selector, using the default events capacity if
+    /// `capacity` is `None`.
+    pub(crate) fn new(tracking: bool, capacity: Option<usize>) -> io::Result<Selector> {
+        let sys = match capacity {
+            Some(capacity) => sys::Selector::with_capacity(capacity),
+            None => sys::Selector::new(),
+        };
+        sys.map(|sys| Selector {
+            sys,
+            tracker: Mutex::new(Tracker::new(tracking)),
+            tracking,
+            drain: Arc::new(DrainRegistry::default()),
+            fork_generation: AtomicUsize::new(fork::generation()),
+        })
+    }
+
+    /// Access to the platform specific selector.
+    #[allow(dead_code)] // Not used on all platforms.
+    pub(crate) fn sys(&self) -> &sys::Selector {
+        &self.sys
+    }
+
+    /// File descriptor of the platform specific selector.
+    pub(crate) fn as_raw_fd(&self) -> RawFd {
+        self.sys.as_raw_fd()
+    }
+
+    /// Registry of `DrainChecked` handles.
+    pub(crate) fn drain_registry(&self) -> &Arc<DrainRegistry> {
+        &self.drain
+    }
+
+    /// Poll the system selector, using the busy polling mode in `busy_poll`. If
+    /// busy polling is enabled the `DrainChecked` handles are checked once, not
+    /// on every spin.
+    pub(crate) fn select<ES>(&self, busy_poll: &mut BusyPoll, event_sink: &mut ES, timeout: Option<Duration>) -> io::Result<()>
+        where ES: event::Sink,
+    {
+        self.check_fork()?;
+        busy_poll.select(&self.sys, &mut self.checked_sink(event_sink), timeout)
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn select_with_sigmask<ES>(&self, event_sink: &mut ES, timeout: Option<Duration>, unblock: SignalSet) -> io::Result<()>
+        where ES: event::Sink,
+    {
+        self.check_fork()?;
+        self.sys.select_with_sigmask(&mut self.checked_sink(event_sink), timeout, unblock)
+    }
+
+    /// Returns an error if the process forked since the system selector was
+    /// created.
+    fn check_fork(&self) -> io::Result<()> {
+        fork::check(self.fork_generation.load(Ordering::Relaxed), "OsQueue")
+    }
+
+    /// Replace the system selector shared with the parent process and
+    /// reregister all handles. Handles that were closed without being
+    /// deregistered are removed from the tracker.
+    pub(crate) fn after_fork_child(&self) -> io::Result<()> {
+        self.sys.after_fork_child()?;
+        self.fork_generation.store(fork::generation(), Ordering::Relaxed);
+
+        let mut tracker = self.tracker.lock().unwrap();
+        let mut result = Ok(());
+        let mut closed = Vec::new();
+        for (fd, registration) in tracker.registrations() {
+            match self.sys.register(fd, registration.id, registration.interests, registration.opt) {
+                Ok(()) => {},
+                Err(ref err) if err.raw_os_error() == Some(libc::EBADF) => closed.push(fd),
+                Err(err) => if result.is_ok() {
+                    result = Err(err);
+                },
+            }
+        }
+        for fd in closed {
+            warn!("file descriptor {} closed without being deregistered, removing it after fork", fd);
+            let _ = tracker.deregister(fd);
+        }
+        result
+    }
+
+    /// Wrap `event_sink` to check the events, also checks if all
+    /// `DrainChecked` handles are drained.
+    fn checked_sink<'a, ES>(&'a self, event_sink: &'a mut ES) -> CheckedSink<'a, ES> {
+        let drain = if self.drain.is_active() {
+            self.drain.check();
+            Some(&*self.drain)
+        } else {
+            None
+        };
+        let tracker = if self.tracking { Some(&self.tracker) } else { None };
+        CheckedSink { sink: event_sink, tracker, drain }
+    }
+
+    pub(crate) fn register(&self, fd: RawFd, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.check_fork()?;
+        let result = self.sys.register(fd, id, interests, opt);
+        report(self.tracker.lock().unwrap().register(fd, id, interests, opt, &result));
+        result
+    }
+
+    pub(crate) fn reregister(&self, fd: RawFd, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.check_fork()?;
+        let result = self.sys.reregister(fd, id, interests, opt);
+        report(self.tracker.lock().unwrap().reregister(fd, id, interests, opt, &result));
+        result
+    }
+
+    pub(crate) fn deregister(&self, fd: RawFd) -> io::Result<()> {
+        self.check_fork()?;
+        let result = self.sys.deregister(fd);
+        report(self.tracker.lock().unwrap().deregister(fd));
+        result
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn registrations(&self) -> io::Result<Vec<Registration>> {
+        self.sys.registrations()
+    }
+
+    /// Compare the registrations known to the kernel with the tracked ones.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn check_registrations(&self) -> io::Result<Vec<RegistrationMismatch>> {
+        if !self.tracking {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                "registration tracking not enabled, use OsQueue::with_tracking"));
+        }
+
+        let mut kernel = self.registrations()?;
+        let tracker = self.tracker.lock().unwrap();
+        let mut mismatches = Vec::new();
+        for (fd, registration) in tracker.registrations() {
+            let tracked = Registration::new(fd, registration.id, Some(registration.interests), registration.opt);
+            match kernel.iter().position(|kernel| kernel.fd() == fd) {
+                Some(index) => {
+                    let kernel = kernel.swap_remove(index);
+                    // Oneshot registrations are disabled after returning an
+                    // event.
+                    let disabled = kernel.interests().is_none() && tracked.opt().is_oneshot();
+                    if kernel != tracked && !(disabled && kernel.id() == tracked.id() && kernel.opt() == tracked.opt()) {
+                        mismatches.push(RegistrationMismatch::Different { tracked, kernel });
+                    }
+                },
+                None => mismatches.push(RegistrationMismatch::Stale(tracked)),
+            }
+        }
+        mismatches.extend(kernel.into_iter().map(RegistrationMismatch::Untracked));
+        mismatches.sort_by_key(|mismatch| match mismatch {
+            RegistrationMismatch::Untracked(registration) |
+            RegistrationMismatch::Stale(registration) |
+            RegistrationMismatch::Different { tracked: registration, .. } => registration.fd(),
+        });
+        Ok(mismatches)
+    }
+}
+
+/// Report misuses of `OsQueue`.
+fn report(misuses: Vec<Misuse>) {
+    for misuse in misuses {
+        error!("OsQueue misuse: {}", misuse);
+    }
+}
+
+/// `event::Sink` wrapper that checks all events for unknown ids, if tracking
+/// is enabled, and records readiness of `DrainChecked` handles.
+struct CheckedSink<'a, ES> {
+    sink: &'a mut ES,
+    tracker: Option<&'a Mutex<Tracker>>,
+    drain: Option<&'a DrainRegistry>,
+}
+
+impl<'a, ES> event::Sink for CheckedSink<'a, ES>
+    where ES: event::Sink,
+{
+    fn capacity_left(&self) -> event::Capacity {
+        self.sink.capacity_left()
+    }
+
+    fn add(&mut self, event: Event) {
+        if let Some(tracker) = self.tracker {
+            if let Some(misuse) = tracker.lock().unwrap().check_event(event.id()) {
+                warn!("OsQueue misuse: {}", misuse);
+            }
+        }
+        if let Some(drain) = self.drain {
+            drain.add_event(&event);
+        }
+        self.sink.add(event);
+    }
+}
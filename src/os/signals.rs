@@ -5,7 +5,7 @@ use std::iter::FusedIterator;
 use std::ops::BitOr;
 
 use crate::event;
-use crate::os::OsQueue;
+use crate::os::{fork, OsQueue};
 use crate::sys;
 
 /// Notifications of process signals.
@@ -19,6 +19,20 @@ use crate::sys;
 ///
 /// [polled]: crate::poll
 ///
+/// After forking the `Signals` is shared with the parent process, in the child
+/// process [`after_fork_child`] must be called before it can be used.
+///
+/// [`after_fork_child`]: Signals::after_fork_child
+///
+/// ## Signal handler mode
+///
+/// Blocking signals only affects the calling thread, threads started before
+/// `Signals` was created (e.g. by a library) will still receive the signals
+/// and run their default action, which could terminate the process.
+/// [`Signals::with_handler`] doesn't block the signals, instead it installs a
+/// signal handler that writes to a pipe registered with the `OsQueue`. The
+/// previous signal dispositions are restored when `Signals` is dropped.
+///
 /// # Implementation notes
 ///
 /// On platforms that support kqueue this will use the `EVFILT_SIGNAL` event
@@ -70,7 +84,17 @@ use crate::sys;
 /// ```
 #[derive(Debug)]
 pub struct Signals {
-    inner: sys::Signals,
+    inner: SignalsInner,
+    fork_generation: usize,
+}
+
+/// The way signals are received.
+#[derive(Debug)]
+enum SignalsInner {
+    /// Signals are blocked and received using signalfd or kqueue.
+    Blocked(sys::Signals),
+    /// Signals are handled by a signal handler.
+    Handler(sys::SignalHandler),
 }
 
 impl Signals {
@@ -78,20 +102,227 @@ impl Signals {
     ///
     /// This will cause the associated `OsQueue` instance to receive events when
     /// the process receives one of the signals in the signal set.
+    ///
+    /// Returns an error of kind [`InvalidInput`] if the signal set contains a
+    /// real-time signal larger than `SIGRTMAX`.
+    ///
+    /// [`InvalidInput`]: io::ErrorKind::InvalidInput
     pub fn new(os_queue: &mut OsQueue, signals: SignalSet, id: event::Id) -> io::Result<Signals> {
         debug_assert!(signals.size() != 0, "can't create `Signals` with an empty signal set");
+        signals.check()?;
         sys::Signals::new(os_queue.selector(), signals, id)
-            .map(|inner| Signals { inner })
+            .map(|inner| Signals { inner: SignalsInner::Blocked(inner), fork_generation: fork::generation() })
+    }
+
+    /// Create a new signal notifier using a signal handler.
+    ///
+    /// Rather than blocking the signals, this installs a signal handler for
+    /// each signal in the signal set, which can run on any thread. The handler
+    /// writes to a non-blocking pipe registered with `os_queue`, after which
+    /// the signal can be received using [`receive`]. The signals are unblocked
+    /// in the calling thread.
+    ///
+    /// When `Signals` is dropped the previous signal dispositions are restored,
+    /// as is the signal mask of the calling thread (if dropped on the same
+    /// thread).
+    ///
+    /// [`receive`]: Signals::receive
+    ///
+    /// # Notes
+    ///
+    /// Only one signal handler per signal can be installed, this returns an
+    /// error of kind [`AlreadyExists`] if a handler is already installed by
+    /// another `Signals` for one of the signals.
+    ///
+    /// Signals received multiple times before calling `receive` are only
+    /// returned once, this includes real-time signals. [`receive_info`] only
+    /// returns the signal, not any of the other information.
+    ///
+    /// [`AlreadyExists`]: io::ErrorKind::AlreadyExists
+    /// [`receive_info`]: Signals::receive_info
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    ///
+    /// use gaea::{event, OsQueue, poll};
+    /// use gaea::os::{Signal, Signals};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut os_queue = OsQueue::new()?;
+    ///     let mut events = Vec::new();
+    ///
+    ///     let mut signals = Signals::with_handler(&mut os_queue, Signal::User1.into(), event::Id(0))?;
+    ///
+    ///     // Any thread can handle the signal.
+    ///     unsafe { libc::kill(libc::getpid(), libc::SIGUSR1) };
+    ///
+    ///     poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+    ///     assert_eq!(signals.receive()?, Some(Signal::User1));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_handler(os_queue: &mut OsQueue, signals: SignalSet, id: event::Id) -> io::Result<Signals> {
+        debug_assert!(signals.size() != 0, "can't create `Signals` with an empty signal set");
+        signals.check()?;
+        sys::SignalHandler::new(os_queue.selector(), signals, id)
+            .map(|inner| Signals { inner: SignalsInner::Handler(inner), fork_generation: fork::generation() })
     }
 
     /// Receive a signal, if any.
+    ///
+    /// Returns an error if the process forked since `Signals` was created, see
+    /// [`after_fork_child`].
+    ///
+    /// [`after_fork_child`]: Signals::after_fork_child
     pub fn receive(&mut self) -> io::Result<Option<Signal>> {
-        self.inner.receive()
+        self.receive_info().map(|info| info.map(|info| info.signal()))
+    }
+
+    /// Receive a signal, if any, including information about the signal, such
+    /// as the process that send it.
+    ///
+    /// See [`SignalInfo`] for what information is available. Returns an error
+    /// in the same cases as [`receive`].
+    ///
+    /// [`receive`]: Signals::receive
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    ///
+    /// use gaea::{event, OsQueue};
+    /// use gaea::os::{Signal, Signals};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut os_queue = OsQueue::new()?;
+    ///     let mut signals = Signals::new(&mut os_queue, Signal::User1.into(), event::Id(0))?;
+    ///
+    ///     // Send ourselves a signal.
+    ///     unsafe { libc::pthread_kill(libc::pthread_self(), libc::SIGUSR1) };
+    ///
+    ///     let info = signals.receive_info()?.expect("no signal received");
+    ///     assert_eq!(info.signal(), Signal::User1);
+    ///     # #[cfg(target_os = "linux")]
+    ///     assert_eq!(info.pid(), Some(std::process::id()));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn receive_info(&mut self) -> io::Result<Option<SignalInfo>> {
+        fork::check(self.fork_generation, "Signals")?;
+        match self.inner {
+            SignalsInner::Blocked(ref mut inner) => inner.receive(),
+            SignalsInner::Handler(ref mut inner) => inner.receive(),
+        }
+    }
+
+    /// Re-initialise `Signals` in a child process after forking.
+    ///
+    /// This replaces the system resources shared with the parent process. This
+    /// must be called after [`OsQueue::after_fork_child`] is called on
+    /// `os_queue`, which must be the queue `Signals` was created with.
+    pub fn after_fork_child(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
+        match self.inner {
+            SignalsInner::Blocked(ref mut inner) => inner.after_fork_child(os_queue.selector())?,
+            SignalsInner::Handler(ref mut inner) => inner.after_fork_child(os_queue.selector())?,
+        }
+        self.fork_generation = fork::generation();
+        Ok(())
+    }
+}
+
+/// Information about a received signal, returned by
+/// [`Signals::receive_info`].
+///
+/// Which information is available depends on the signal, how it was send and
+/// the platform. On platforms that use kqueue only the signal itself is
+/// available.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SignalInfo {
+    signal: Signal,
+    pid: Option<u32>,
+    uid: Option<u32>,
+    status: Option<i32>,
+    value: Option<(i32, usize)>,
+}
+
+impl SignalInfo {
+    /// Create a new `SignalInfo` with only the signal set.
+    pub(crate) const fn new(signal: Signal) -> SignalInfo {
+        SignalInfo { signal, pid: None, uid: None, status: None, value: None }
+    }
+
+    /// Create a new `SignalInfo` from the information read from a signalfd.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn from_signalfd(signal: Signal, info: &libc::signalfd_siginfo) -> SignalInfo {
+        let mut signal_info = SignalInfo::new(signal);
+        if signal == Signal::Child && info.ssi_code > 0 {
+            // Send by the kernel because a child process changed state.
+            signal_info.pid = Some(info.ssi_pid);
+            signal_info.uid = Some(info.ssi_uid);
+            signal_info.status = Some(info.ssi_status);
+        } else {
+            match info.ssi_code {
+                libc::SI_USER | libc::SI_TKILL => {
+                    signal_info.pid = Some(info.ssi_pid);
+                    signal_info.uid = Some(info.ssi_uid);
+                },
+                libc::SI_QUEUE | libc::SI_MESGQ => {
+                    signal_info.pid = Some(info.ssi_pid);
+                    signal_info.uid = Some(info.ssi_uid);
+                    signal_info.value = Some((info.ssi_int, info.ssi_ptr as usize));
+                },
+                libc::SI_TIMER => signal_info.value = Some((info.ssi_int, info.ssi_ptr as usize)),
+                _ => {},
+            }
+        }
+        signal_info
+    }
+
+    /// Returns the received signal.
+    pub const fn signal(&self) -> Signal {
+        self.signal
+    }
+
+    /// Returns the process id of the process that send the signal.
+    ///
+    /// For [`Signal::Child`], send by the system, this is the process id of
+    /// the child process that changed state.
+    pub const fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Returns the real user id of the process that send the signal.
+    pub const fn uid(&self) -> Option<u32> {
+        self.uid
+    }
+
+    /// Returns the status of the child process for [`Signal::Child`].
+    ///
+    /// If the child process exited this is its exit status, if it was killed,
+    /// stopped or continued by a signal this is the signal number.
+    pub const fn status(&self) -> Option<i32> {
+        self.status
+    }
+
+    /// Returns the value passed to `sigqueue(3)`, interpreted as `sival_int`.
+    pub fn value_int(&self) -> Option<i32> {
+        self.value.map(|(value, _)| value)
+    }
+
+    /// Returns the value passed to `sigqueue(3)`, interpreted as `sival_ptr`.
+    pub fn value_ptr(&self) -> Option<usize> {
+        self.value.map(|(_, value)| value)
     }
 }
 
 /// Set of [`Signal`]s used in registering signal notifications with [`Signals`].
 ///
+/// Like `sigset_t` the set is a bitmap indexed by signal number, it can hold
+/// all standard and real-time signals.
+///
 /// # Examples
 ///
 /// ```
@@ -107,11 +338,31 @@ impl Signals {
 /// assert!(set.contains(Signal::Interrupt | Signal::Quit));
 /// ```
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct SignalSet(u8);
+pub struct SignalSet(u128);
+
+/// Returns the bit in `SignalSet` for the raw Unix signal `raw_signal`.
+///
+/// Signals that don't fit in the set use the last bit, which is larger than
+/// `SIGRTMAX` on all platforms and thus rejected by `SignalSet::check`.
+const fn bit(raw_signal: libc::c_int) -> u128 {
+    if raw_signal > u128::BITS as libc::c_int {
+        1 << (u128::BITS - 1)
+    } else {
+        1 << (raw_signal - 1)
+    }
+}
 
-const INTERRUPT: u8 = 1;
-const QUIT: u8 = 1 << 1;
-const TERMINATE: u8 = 1 << 2;
+/// Signals in `SignalSet::all`.
+const ALL: u128 = bit(libc::SIGINT) | bit(libc::SIGQUIT) | bit(libc::SIGTERM);
+
+/// All standard signals, i.e. all signals except the real-time signals.
+const STANDARD: u128 = bit(libc::SIGHUP) | bit(libc::SIGINT) | bit(libc::SIGQUIT) |
+    bit(libc::SIGABRT) | bit(libc::SIGALRM) | bit(libc::SIGTERM) |
+    bit(libc::SIGUSR1) | bit(libc::SIGUSR2) | bit(libc::SIGCHLD) |
+    bit(libc::SIGCONT) | bit(libc::SIGTSTP) | bit(libc::SIGTTIN) |
+    bit(libc::SIGTTOU) | bit(libc::SIGPIPE) | bit(libc::SIGURG) |
+    bit(libc::SIGXCPU) | bit(libc::SIGXFSZ) | bit(libc::SIGVTALRM) |
+    bit(libc::SIGPROF) | bit(libc::SIGWINCH) | bit(libc::SIGIO);
 
 impl SignalSet {
     /// Create an empty signal set.
@@ -119,9 +370,46 @@ impl SignalSet {
         SignalSet(0)
     }
 
-    /// Create a new set with all signals.
+    /// Create a new set with the [interrupt], [terminate] and [quit] signals.
+    ///
+    /// See [`all_standard`] for a set with all standard signals.
+    ///
+    /// [interrupt]: Signal::Interrupt
+    /// [terminate]: Signal::Terminate
+    /// [quit]: Signal::Quit
+    /// [`all_standard`]: SignalSet::all_standard
     pub const fn all() -> SignalSet {
-        SignalSet(INTERRUPT | QUIT | TERMINATE)
+        SignalSet(ALL)
+    }
+
+    /// Create a new set with all standard signals.
+    ///
+    /// This doesn't include any real-time signals, those need to be added
+    /// separately.
+    ///
+    /// # Notes
+    ///
+    /// Receiving signals such as [`Signal::Child`], [`Signal::TerminalStop`]
+    /// or [`Signal::Continue`] with [`Signals`] replaces their default action,
+    /// e.g. the process will no longer be stopped by Ctrl+Z and child
+    /// processes must be reaped by the application.
+    pub const fn all_standard() -> SignalSet {
+        SignalSet(STANDARD)
+    }
+
+    /// Returns an error if the set contains a real-time signal larger than
+    /// `SIGRTMAX`.
+    pub(crate) fn check(self) -> io::Result<()> {
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        {
+            // Bits for all signals up to and including `SIGRTMAX`.
+            let valid = u128::MAX >> (u128::BITS - libc::SIGRTMAX() as u32);
+            if self.0 & !valid != 0 {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                    "real-time signal is larger than `SIGRTMAX`"));
+            }
+        }
+        Ok(())
     }
 
     /// Number of signals in the set.
@@ -160,11 +448,7 @@ impl SignalSet {
 
 impl From<Signal> for SignalSet {
     fn from(signal: Signal) -> Self {
-        SignalSet(match signal {
-            Signal::Interrupt => INTERRUPT,
-            Signal::Quit => QUIT,
-            Signal::Terminate => TERMINATE,
-        })
+        SignalSet(bit(signal.into_raw()))
     }
 }
 
@@ -206,16 +490,20 @@ impl Iterator for SignalSetIter {
 
     fn next(&mut self) -> Option<Self::Item> {
         let n = (self.0).0.trailing_zeros();
-        match n {
-            0 => Some(Signal::Interrupt),
-            1 => Some(Signal::Quit),
-            2 => Some(Signal::Terminate),
-            _ => None,
-        }.map(|signal| {
-            // Remove the signal from the set.
-            (self.0).0 &= !(1 << n);
-            signal
-        })
+        if n == u128::BITS {
+            return None;
+        }
+        // Remove the signal from the set.
+        (self.0).0 &= !(1 << n);
+        // Only supported signals are added to the set, so this always returns
+        // a signal.
+        let raw_signal = n as libc::c_int + 1;
+        let signal = Signal::from_raw(raw_signal);
+        // Real-time signals larger than `SIGRTMAX` can be added to the set,
+        // they're only rejected when creating `Signals`.
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        let signal = signal.or(Some(Signal::Realtime((raw_signal - libc::SIGRTMIN()) as u8)));
+        signal
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -237,6 +525,12 @@ impl ExactSizeIterator for SignalSetIter {
 impl FusedIterator for SignalSetIter {}
 
 /// Signal used in registering signal notifications with [`Signals`].
+///
+/// # Notes
+///
+/// `SIGKILL` and `SIGSTOP` can't be caught, and signals generated by faults in
+/// the program itself (`SIGSEGV`, `SIGBUS`, `SIGFPE`, `SIGILL`, `SIGTRAP` and
+/// `SIGSYS`) can't be blocked, so those are not supported.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Signal {
     /// Interrupt signal.
@@ -263,15 +557,143 @@ pub enum Signal {
     ///
     /// Corresponds to POSIX signal `SIGQUIT`.
     Quit,
+    /// Hangup signal.
+    ///
+    /// This signal is received when the controlling terminal is closed. Daemons
+    /// often use it as a request to reload their configuration.
+    ///
+    /// Corresponds to POSIX signal `SIGHUP`.
+    Hangup,
+    /// Abort signal.
+    ///
+    /// Corresponds to POSIX signal `SIGABRT`.
+    Abort,
+    /// Alarm clock signal, send when a timer set by `alarm(2)` expires.
+    ///
+    /// Corresponds to POSIX signal `SIGALRM`.
+    Alarm,
+    /// First user-defined signal.
+    ///
+    /// Corresponds to POSIX signal `SIGUSR1`.
+    User1,
+    /// Second user-defined signal.
+    ///
+    /// Corresponds to POSIX signal `SIGUSR2`.
+    User2,
+    /// Child process terminated, stopped or continued.
+    ///
+    /// Corresponds to POSIX signal `SIGCHLD`.
+    Child,
+    /// Continue executing, if stopped.
+    ///
+    /// Corresponds to POSIX signal `SIGCONT`.
+    Continue,
+    /// Terminal stop signal. This signal will for example be send when Ctrl+Z
+    /// is pressed in most terminals.
+    ///
+    /// Corresponds to POSIX signal `SIGTSTP`.
+    TerminalStop,
+    /// Background process attempting to read from its controlling terminal.
+    ///
+    /// Corresponds to POSIX signal `SIGTTIN`.
+    TerminalInput,
+    /// Background process attempting to write to its controlling terminal.
+    ///
+    /// Corresponds to POSIX signal `SIGTTOU`.
+    TerminalOutput,
+    /// Write on a pipe with no one to read it.
+    ///
+    /// Corresponds to POSIX signal `SIGPIPE`.
+    Pipe,
+    /// Out-of-band data is available on a socket.
+    ///
+    /// Corresponds to POSIX signal `SIGURG`.
+    Urgent,
+    /// CPU time limit exceeded.
+    ///
+    /// Corresponds to POSIX signal `SIGXCPU`.
+    CpuTimeLimit,
+    /// File size limit exceeded.
+    ///
+    /// Corresponds to POSIX signal `SIGXFSZ`.
+    FileSizeLimit,
+    /// Virtual timer expired.
+    ///
+    /// Corresponds to POSIX signal `SIGVTALRM`.
+    VirtualAlarm,
+    /// Profiling timer expired.
+    ///
+    /// Corresponds to POSIX signal `SIGPROF`.
+    Profile,
+    /// Size of the controlling terminal changed.
+    ///
+    /// Corresponds to signal `SIGWINCH`.
+    WindowChange,
+    /// I/O is possible on a file descriptor.
+    ///
+    /// Corresponds to signal `SIGIO`.
+    Io,
+    /// Real-time signal `SIGRTMIN + n`.
+    ///
+    /// Use [`Signal::realtime`] to check that `SIGRTMIN + n` is not larger
+    /// than `SIGRTMAX`, creating [`Signals`] with a larger signal returns an
+    /// error of kind [`InvalidInput`].
+    ///
+    /// [`InvalidInput`]: io::ErrorKind::InvalidInput
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    Realtime(u8),
 }
 
 impl Signal {
+    /// Create the real-time signal `SIGRTMIN + n`, returns `None` if it's
+    /// larger than `SIGRTMAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gaea::os::Signal;
+    ///
+    /// assert_eq!(Signal::realtime(0), Some(Signal::Realtime(0)));
+    /// assert_eq!(Signal::realtime(u8::MAX), None);
+    /// ```
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn realtime(n: u8) -> Option<Signal> {
+        if libc::SIGRTMIN() + libc::c_int::from(n) <= libc::SIGRTMAX() {
+            Some(Signal::Realtime(n))
+        } else {
+            None
+        }
+    }
+
     /// Convert the signal into a raw Unix signal.
+    ///
+    /// Real-time signals can be larger than `SIGRTMAX`, see
+    /// `SignalSet::check`.
     pub(crate) fn into_raw(self) -> libc::c_int {
         match self {
             Signal::Interrupt => libc::SIGINT,
-            Signal::Quit => libc::SIGQUIT,
             Signal::Terminate => libc::SIGTERM,
+            Signal::Quit => libc::SIGQUIT,
+            Signal::Hangup => libc::SIGHUP,
+            Signal::Abort => libc::SIGABRT,
+            Signal::Alarm => libc::SIGALRM,
+            Signal::User1 => libc::SIGUSR1,
+            Signal::User2 => libc::SIGUSR2,
+            Signal::Child => libc::SIGCHLD,
+            Signal::Continue => libc::SIGCONT,
+            Signal::TerminalStop => libc::SIGTSTP,
+            Signal::TerminalInput => libc::SIGTTIN,
+            Signal::TerminalOutput => libc::SIGTTOU,
+            Signal::Pipe => libc::SIGPIPE,
+            Signal::Urgent => libc::SIGURG,
+            Signal::CpuTimeLimit => libc::SIGXCPU,
+            Signal::FileSizeLimit => libc::SIGXFSZ,
+            Signal::VirtualAlarm => libc::SIGVTALRM,
+            Signal::Profile => libc::SIGPROF,
+            Signal::WindowChange => libc::SIGWINCH,
+            Signal::Io => libc::SIGIO,
+            #[cfg(any(target_os = "android", target_os = "linux"))]
+            Signal::Realtime(n) => libc::SIGRTMIN() + libc::c_int::from(n),
         }
     }
 
@@ -279,8 +701,29 @@ impl Signal {
     pub(crate) fn from_raw(raw_signal: libc::c_int) -> Option<Signal> {
         match raw_signal {
             libc::SIGINT => Some(Signal::Interrupt),
-            libc::SIGQUIT => Some(Signal::Quit),
             libc::SIGTERM => Some(Signal::Terminate),
+            libc::SIGQUIT => Some(Signal::Quit),
+            libc::SIGHUP => Some(Signal::Hangup),
+            libc::SIGABRT => Some(Signal::Abort),
+            libc::SIGALRM => Some(Signal::Alarm),
+            libc::SIGUSR1 => Some(Signal::User1),
+            libc::SIGUSR2 => Some(Signal::User2),
+            libc::SIGCHLD => Some(Signal::Child),
+            libc::SIGCONT => Some(Signal::Continue),
+            libc::SIGTSTP => Some(Signal::TerminalStop),
+            libc::SIGTTIN => Some(Signal::TerminalInput),
+            libc::SIGTTOU => Some(Signal::TerminalOutput),
+            libc::SIGPIPE => Some(Signal::Pipe),
+            libc::SIGURG => Some(Signal::Urgent),
+            libc::SIGXCPU => Some(Signal::CpuTimeLimit),
+            libc::SIGXFSZ => Some(Signal::FileSizeLimit),
+            libc::SIGVTALRM => Some(Signal::VirtualAlarm),
+            libc::SIGPROF => Some(Signal::Profile),
+            libc::SIGWINCH => Some(Signal::WindowChange),
+            libc::SIGIO => Some(Signal::Io),
+            #[cfg(any(target_os = "android", target_os = "linux"))]
+            raw_signal if raw_signal >= libc::SIGRTMIN() && raw_signal <= libc::SIGRTMAX() =>
+                Some(Signal::Realtime((raw_signal - libc::SIGRTMIN()) as u8)),
             _ => None,
         }
     }
@@ -304,7 +747,10 @@ impl BitOr<SignalSet> for Signal {
 
 #[cfg(test)]
 mod tests {
-    use crate::os::Signal;
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    use std::io;
+
+    use crate::os::{Signal, SignalSet};
 
     // More tests can be found in `tests/signals.rs`. This is only tested here
     // because it's not part of the public API.
@@ -314,9 +760,28 @@ mod tests {
         assert_eq!(Signal::from_raw(libc::SIGINT), Some(Signal::Interrupt));
         assert_eq!(Signal::from_raw(libc::SIGQUIT), Some(Signal::Quit));
         assert_eq!(Signal::from_raw(libc::SIGTERM), Some(Signal::Terminate));
+        assert_eq!(Signal::from_raw(libc::SIGHUP), Some(Signal::Hangup));
+        assert_eq!(Signal::from_raw(libc::SIGUSR1), Some(Signal::User1));
+        assert_eq!(Signal::from_raw(libc::SIGUSR2), Some(Signal::User2));
+        assert_eq!(Signal::from_raw(libc::SIGCHLD), Some(Signal::Child));
+        assert_eq!(Signal::from_raw(libc::SIGWINCH), Some(Signal::WindowChange));
+        assert_eq!(Signal::from_raw(libc::SIGPIPE), Some(Signal::Pipe));
 
         // Unsupported signals.
         assert_eq!(Signal::from_raw(libc::SIGSTOP), None);
+        assert_eq!(Signal::from_raw(libc::SIGKILL), None);
+        assert_eq!(Signal::from_raw(libc::SIGSEGV), None);
+        assert_eq!(Signal::from_raw(0), None);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    fn signal_from_raw_realtime() {
+        assert_eq!(Signal::from_raw(libc::SIGRTMIN()), Some(Signal::Realtime(0)));
+        assert_eq!(Signal::from_raw(libc::SIGRTMIN() + 2), Some(Signal::Realtime(2)));
+        let max = (libc::SIGRTMAX() - libc::SIGRTMIN()) as u8;
+        assert_eq!(Signal::from_raw(libc::SIGRTMAX()), Some(Signal::Realtime(max)));
+        assert_eq!(Signal::from_raw(libc::SIGRTMAX() + 1), None);
     }
 
     #[test]
@@ -324,12 +789,51 @@ mod tests {
         assert_eq!(Signal::Interrupt.into_raw(), libc::SIGINT);
         assert_eq!(Signal::Quit.into_raw(), libc::SIGQUIT);
         assert_eq!(Signal::Terminate.into_raw(), libc::SIGTERM);
+        assert_eq!(Signal::Hangup.into_raw(), libc::SIGHUP);
+        assert_eq!(Signal::User1.into_raw(), libc::SIGUSR1);
+        assert_eq!(Signal::User2.into_raw(), libc::SIGUSR2);
+        assert_eq!(Signal::Child.into_raw(), libc::SIGCHLD);
+        assert_eq!(Signal::WindowChange.into_raw(), libc::SIGWINCH);
+        assert_eq!(Signal::Pipe.into_raw(), libc::SIGPIPE);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    fn signal_into_raw_realtime() {
+        assert_eq!(Signal::Realtime(0).into_raw(), libc::SIGRTMIN());
+        assert_eq!(Signal::Realtime(3).into_raw(), libc::SIGRTMIN() + 3);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    fn signal_set_check() {
+        assert!(SignalSet::all_standard().check().is_ok());
+        let max = (libc::SIGRTMAX() - libc::SIGRTMIN()) as u8;
+        assert!((Signal::Realtime(0) | Signal::Realtime(max)).check().is_ok());
+        let err = SignalSet::from(Signal::Realtime(max + 1)).check().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = SignalSet::from(Signal::Realtime(u8::MAX)).check().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
     }
 
     #[test]
     fn raw_signal() {
-        assert_eq!(Signal::from_raw(libc::SIGINT).unwrap().into_raw(), libc::SIGINT);
-        assert_eq!(Signal::from_raw(libc::SIGQUIT).unwrap().into_raw(), libc::SIGQUIT);
-        assert_eq!(Signal::from_raw(libc::SIGTERM).unwrap().into_raw(), libc::SIGTERM);
+        for raw_signal in 1..128 {
+            if let Some(signal) = Signal::from_raw(raw_signal) {
+                assert_eq!(signal.into_raw(), raw_signal);
+            }
+        }
+    }
+
+    #[test]
+    fn signal_set_all_standard() {
+        let all = SignalSet::all_standard();
+        for signal in all {
+            assert_eq!(Signal::from_raw(signal.into_raw()), Some(signal));
+        }
+        assert!(all.contains(SignalSet::all()));
+        assert!(all.contains(Signal::Hangup | Signal::Child | Signal::WindowChange));
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        assert!(!all.contains(Signal::Realtime(0)));
     }
 }
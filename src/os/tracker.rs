@@ -0,0 +1,302 @@
+//! Registration tracking, used to detect misuse of `OsQueue`.
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::os::unix::io::RawFd;
+
+use crate::event;
+use crate::os::{Interests, RegisterOption};
+
+/// Tracker of all registrations made with an `OsQueue`.
+///
+/// It records the file descriptor along with the registration details for
+/// each call to `register`, `reregister` and `deregister`, which is used to
+/// restore the registrations after forking. If `checks` is enabled it also
+/// reports any misuse it detects, and records a backtrace for each
+/// registration if backtraces are enabled (see `Backtrace::capture`).
+#[derive(Debug)]
+pub(crate) struct Tracker {
+    checks: bool,
+    registrations: HashMap<RawFd, Registration>,
+    /// Number of registrations using a given id.
+    ids: HashMap<event::Id, usize>,
+}
+
+/// A single registration of a file descriptor.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    pub(crate) id: event::Id,
+    pub(crate) interests: Interests,
+    pub(crate) opt: RegisterOption,
+    /// Backtrace taken at the point of (re)registration, disabled if checks or
+    /// backtraces aren't enabled.
+    backtrace: Backtrace,
+}
+
+impl fmt::Display for Registration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "id={}, interests={:?}, opt={:?}", self.id, self.interests, self.opt)?;
+        if let BacktraceStatus::Captured = self.backtrace.status() {
+            write!(f, ", registered at:\n{}", self.backtrace)?;
+        }
+        Ok(())
+    }
+}
+
+/// Misuse of `OsQueue` detected by the [`Tracker`].
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum Misuse {
+    /// A file descriptor was registered while it is already registered.
+    DoubleRegistration {
+        fd: RawFd,
+        /// Formatted previous registration.
+        previous: String,
+    },
+    /// A file descriptor was closed without being deregistered, detected once
+    /// the same file descriptor number is registered again.
+    NotDeregistered {
+        fd: RawFd,
+        /// Formatted previous registration.
+        previous: String,
+    },
+    /// A file descriptor was reregistered or deregistered without being
+    /// registered.
+    NotRegistered {
+        fd: RawFd,
+    },
+    /// The same id is used for registrations of multiple file descriptors.
+    IdCollision {
+        id: event::Id,
+        fd: RawFd,
+        other_fd: RawFd,
+        /// Formatted registration of `other_fd`.
+        other: String,
+    },
+    /// An event was received for an id that isn't registered.
+    UnknownId {
+        id: event::Id,
+    },
+}
+
+impl fmt::Display for Misuse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Misuse::DoubleRegistration { fd, previous } =>
+                write!(f, "file descriptor {} is registered twice, previous registration: {}", fd, previous),
+            Misuse::NotDeregistered { fd, previous } =>
+                write!(f, "file descriptor {} was closed without being deregistered, previous registration: {}", fd, previous),
+            Misuse::NotRegistered { fd } =>
+                write!(f, "file descriptor {} is not registered", fd),
+            Misuse::IdCollision { id, fd, other_fd, other } =>
+                write!(f, "id {} of file descriptor {} is also used by file descriptor {}, registration: {}", id, fd, other_fd, other),
+            Misuse::UnknownId { id } =>
+                write!(f, "received event for unknown id {}", id),
+        }
+    }
+}
+
+impl Tracker {
+    /// Create a new empty tracker, detecting misuse if `checks` is true.
+    pub(crate) fn new(checks: bool) -> Tracker {
+        Tracker {
+            checks,
+            registrations: HashMap::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Track a call to `register`, `result` is the result of the system call.
+    pub(crate) fn register(&mut self, fd: RawFd, id: event::Id, interests: Interests, opt: RegisterOption, result: &io::Result<()>) -> Vec<Misuse> {
+        let mut misuses = Vec::new();
+        match result {
+            Ok(()) => {
+                // The kernel accepted the registration, so if we still have a
+                // registration for the file descriptor it must have been
+                // closed (which removes it from the kernel's queue), without
+                // being deregistered.
+                if let Some(previous) = self.remove(fd) {
+                    if self.checks {
+                        misuses.push(Misuse::NotDeregistered { fd, previous: previous.to_string() });
+                    }
+                }
+                self.check_id_collision(fd, id, &mut misuses);
+                self.insert(fd, id, interests, opt);
+            },
+            Err(ref err) if self.checks && err.raw_os_error() == Some(libc::EEXIST) => {
+                let previous = self.registrations.get(&fd)
+                    .map_or_else(|| "unknown".to_owned(), ToString::to_string);
+                misuses.push(Misuse::DoubleRegistration { fd, previous });
+            },
+            Err(_) => {},
+        }
+        misuses
+    }
+
+    /// Track a call to `reregister`, `result` is the result of the system
+    /// call.
+    pub(crate) fn reregister(&mut self, fd: RawFd, id: event::Id, interests: Interests, opt: RegisterOption, result: &io::Result<()>) -> Vec<Misuse> {
+        let mut misuses = Vec::new();
+        if self.checks && !self.registrations.contains_key(&fd) {
+            misuses.push(Misuse::NotRegistered { fd });
+        }
+        if result.is_ok() {
+            drop(self.remove(fd));
+            self.check_id_collision(fd, id, &mut misuses);
+            self.insert(fd, id, interests, opt);
+        }
+        misuses
+    }
+
+    /// Track a call to `deregister`.
+    pub(crate) fn deregister(&mut self, fd: RawFd) -> Vec<Misuse> {
+        match self.remove(fd) {
+            None if self.checks => vec![Misuse::NotRegistered { fd }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns all tracked registrations.
+    pub(crate) fn registrations(&self) -> impl Iterator<Item = (RawFd, &Registration)> {
+        self.registrations.iter().map(|(fd, registration)| (*fd, registration))
+    }
+
+    /// Check an event received from the system selector.
+    pub(crate) fn check_event(&self, id: event::Id) -> Option<Misuse> {
+        if self.ids.contains_key(&id) {
+            None
+        } else {
+            Some(Misuse::UnknownId { id })
+        }
+    }
+
+    fn check_id_collision(&self, fd: RawFd, id: event::Id, misuses: &mut Vec<Misuse>) {
+        if !self.checks {
+            return;
+        }
+        let other = self.registrations.iter()
+            .find(|(other_fd, registration)| **other_fd != fd && registration.id == id);
+        if let Some((other_fd, other)) = other {
+            misuses.push(Misuse::IdCollision { id, fd, other_fd: *other_fd, other: other.to_string() });
+        }
+    }
+
+    fn insert(&mut self, fd: RawFd, id: event::Id, interests: Interests, opt: RegisterOption) {
+        let backtrace = if self.checks { Backtrace::capture() } else { Backtrace::disabled() };
+        let registration = Registration { id, interests, opt, backtrace };
+        *self.ids.entry(id).or_insert(0) += 1;
+        let previous = self.registrations.insert(fd, registration);
+        debug_assert!(previous.is_none(), "tracker: file descriptor already tracked");
+    }
+
+    fn remove(&mut self, fd: RawFd) -> Option<Registration> {
+        let registration = self.registrations.remove(&fd)?;
+        if let Some(count) = self.ids.get_mut(&registration.id) {
+            *count -= 1;
+            if *count == 0 {
+                let _ = self.ids.remove(&registration.id);
+            }
+        }
+        Some(registration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::event;
+    use crate::os::{Interests, RegisterOption};
+
+    use super::{Misuse, Tracker};
+
+    const OK: io::Result<()> = Ok(());
+
+    #[test]
+    fn registration() {
+        let mut tracker = Tracker::new(true);
+        let id = event::Id(0);
+        assert!(tracker.register(1, id, Interests::READABLE, RegisterOption::EDGE, &OK).is_empty());
+        assert!(tracker.check_event(id).is_none());
+        assert!(tracker.reregister(1, id, Interests::WRITABLE, RegisterOption::LEVEL, &OK).is_empty());
+        assert!(tracker.check_event(id).is_none());
+
+        assert_eq!(tracker.registrations.len(), 1);
+        let registration = &tracker.registrations[&1];
+        assert_eq!(registration.interests, Interests::WRITABLE);
+        assert_eq!(registration.opt, RegisterOption::LEVEL);
+
+        assert!(tracker.deregister(1).is_empty());
+        assert_eq!(tracker.check_event(id), Some(Misuse::UnknownId { id }));
+        assert!(tracker.registrations.is_empty());
+    }
+
+    #[test]
+    fn double_registration() {
+        let mut tracker = Tracker::new(true);
+        assert!(tracker.register(1, event::Id(0), Interests::READABLE, RegisterOption::EDGE, &OK).is_empty());
+        let result = Err(io::Error::from_raw_os_error(libc::EEXIST));
+        match tracker.register(1, event::Id(1), Interests::READABLE, RegisterOption::EDGE, &result).as_slice() {
+            [Misuse::DoubleRegistration { fd: 1, previous }] => assert!(previous.starts_with("id=0,")),
+            misuses => panic!("unexpected misuses: {:?}", misuses),
+        }
+        // The original registration should remain.
+        assert!(tracker.check_event(event::Id(0)).is_none());
+        assert!(tracker.check_event(event::Id(1)).is_some());
+    }
+
+    #[test]
+    fn not_deregistered() {
+        let mut tracker = Tracker::new(true);
+        assert!(tracker.register(1, event::Id(0), Interests::READABLE, RegisterOption::EDGE, &OK).is_empty());
+        // File descriptor is closed and reused without being deregistered.
+        match tracker.register(1, event::Id(1), Interests::READABLE, RegisterOption::EDGE, &OK).as_slice() {
+            [Misuse::NotDeregistered { fd: 1, previous }] => assert!(previous.starts_with("id=0,")),
+            misuses => panic!("unexpected misuses: {:?}", misuses),
+        }
+        assert!(tracker.check_event(event::Id(0)).is_some());
+        assert!(tracker.check_event(event::Id(1)).is_none());
+    }
+
+    #[test]
+    fn not_registered() {
+        let mut tracker = Tracker::new(true);
+        assert_eq!(tracker.reregister(1, event::Id(0), Interests::READABLE, RegisterOption::EDGE, &OK),
+            vec![Misuse::NotRegistered { fd: 1 }]);
+        assert_eq!(tracker.deregister(2), vec![Misuse::NotRegistered { fd: 2 }]);
+    }
+
+    #[test]
+    fn without_checks() {
+        let mut tracker = Tracker::new(false);
+        let id = event::Id(0);
+        // Registrations are recorded, but misuse isn't reported.
+        assert!(tracker.register(1, id, Interests::READABLE, RegisterOption::EDGE, &OK).is_empty());
+        assert!(tracker.register(2, id, Interests::READABLE, RegisterOption::EDGE, &OK).is_empty());
+        assert!(tracker.register(1, id, Interests::WRITABLE, RegisterOption::EDGE, &OK).is_empty());
+        assert!(tracker.reregister(3, id, Interests::READABLE, RegisterOption::EDGE, &OK).is_empty());
+        assert!(tracker.deregister(4).is_empty());
+        let mut fds: Vec<_> = tracker.registrations().map(|(fd, _)| fd).collect();
+        fds.sort_unstable();
+        assert_eq!(fds, vec![1, 2, 3]);
+        assert_eq!(tracker.registrations[&1].interests, Interests::WRITABLE);
+    }
+
+    #[test]
+    fn id_collision() {
+        let mut tracker = Tracker::new(true);
+        let id = event::Id(0);
+        assert!(tracker.register(1, id, Interests::READABLE, RegisterOption::EDGE, &OK).is_empty());
+        match tracker.register(2, id, Interests::READABLE, RegisterOption::EDGE, &OK).as_slice() {
+            [Misuse::IdCollision { id: event::Id(0), fd: 2, other_fd: 1, .. }] => {},
+            misuses => panic!("unexpected misuses: {:?}", misuses),
+        }
+
+        // After deregistering one the id should still be known.
+        assert!(tracker.deregister(1).is_empty());
+        assert!(tracker.check_event(id).is_none());
+        assert!(tracker.deregister(2).is_empty());
+        assert!(tracker.check_event(id).is_some());
+    }
+}
@@ -0,0 +1,378 @@
+//! Non-blocking child processes.
+//!
+//! [`Command`] spawns child processes, much like the standard library's
+//! [`std::process::Command`]. But unlike the standard library, the piped
+//! standard streams of the spawned [`Child`] are non-blocking [`Sender`]s and
+//! [`Receiver`]s, which can be registered with [`OsQueue`].
+//!
+//! On Linux the `Child` itself can also be registered with `OsQueue`, using a
+//! [pidfd], it becomes readable once the child process exits. After which
+//! [`Child::try_wait`] will return the exit status of the process. This
+//! requires Linux 5.3 or later, see [`Child::pidfd`].
+//!
+//! [`Sender`]: crate::unix::Sender
+//! [`Receiver`]: crate::unix::Receiver
+//! [`OsQueue`]: crate::os::OsQueue
+//! [pidfd]: http://man7.org/linux/man-pages/man2/pidfd_open.2.html
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use std::io::{self, Read};
+//!
+//! use gaea::os::{OsQueue, RegisterOption};
+//! use gaea::process::{Child, Command, Stdio};
+//! use gaea::unix::Receiver;
+//! use gaea::{event, poll};
+//!
+//! const STDOUT_ID: event::Id = event::Id(0);
+//! const CHILD_ID: event::Id = event::Id(1);
+//!
+//! let mut os_queue = OsQueue::new()?;
+//! let mut events = Vec::new();
+//!
+//! let mut child = Command::new("echo").arg("Hello world").stdout(Stdio::piped()).spawn()?;
+//! let mut stdout = child.stdout.take().unwrap();
+//! os_queue.register(&mut stdout, STDOUT_ID, Receiver::INTERESTS, RegisterOption::LEVEL)?;
+//! # #[cfg(target_os = "linux")]
+//! os_queue.register(&mut child, CHILD_ID, Child::INTERESTS, RegisterOption::LEVEL)?;
+//!
+//! let mut output = Vec::new();
+//! let mut stdout_closed = false;
+//! let mut status = None;
+//! while !stdout_closed || status.is_none() {
+//!     poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+//!     for event in events.drain(..) {
+//!         match event.id() {
+//!             // Reads until the end of the stream, i.e. when the child
+//!             // process closed its stdout.
+//!             STDOUT_ID => match stdout.read_to_end(&mut output) {
+//!                 Ok(_) => stdout_closed = true,
+//!                 Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {},
+//!                 Err(err) => return Err(err.into()),
+//!             },
+//!             // The child process exited.
+//!             CHILD_ID => status = child.try_wait()?,
+//!             _ => unreachable!(),
+//!         }
+//!     }
+//!     # #[cfg(not(target_os = "linux"))]
+//!     # if stdout_closed { status = Some(child.wait()?); }
+//! }
+//!
+//! assert!(status.unwrap().success());
+//! assert_eq!(output, b"Hello world\n");
+//! #     Ok(())
+//! # }
+//! ```
+
+use std::ffi::OsStr;
+use std::os::unix::io::{AsRawFd, OwnedFd};
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use std::os::unix::io::{AsFd, BorrowedFd, FromRawFd};
+use std::path::Path;
+use std::{io, process, ptr};
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use crate::event;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+use crate::sys::set_nonblocking;
+use crate::unix::{Receiver, Sender};
+
+pub use std::process::{ExitStatus, Stdio};
+
+/// A process builder, used to spawn non-blocking [`Child`] processes.
+///
+/// This is a wrapper around [`std::process::Command`], see its documentation
+/// for the details. The differences are that the [`Stdio::piped`] standard
+/// streams of the spawned `Child` are non-blocking, and that on Linux the
+/// `Child` can be registered with [`OsQueue`] to be notified when it exits.
+///
+/// [`OsQueue`]: crate::os::OsQueue
+///
+/// # Examples
+///
+/// See the [module documentation].
+///
+/// [module documentation]: index.html
+#[derive(Debug)]
+pub struct Command {
+    inner: process::Command,
+}
+
+impl Command {
+    /// Create a new `Command` to start the `program`.
+    pub fn new<S>(program: S) -> Command
+        where S: AsRef<OsStr>,
+    {
+        Command { inner: process::Command::new(program) }
+    }
+
+    /// Add an argument to pass to the program.
+    pub fn arg<S>(&mut self, arg: S) -> &mut Command
+        where S: AsRef<OsStr>,
+    {
+        let _ = self.inner.arg(arg);
+        self
+    }
+
+    /// Add multiple arguments to pass to the program.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Command
+        where I: IntoIterator<Item = S>,
+              S: AsRef<OsStr>,
+    {
+        let _ = self.inner.args(args);
+        self
+    }
+
+    /// Set an environment variable for the child process.
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Command
+        where K: AsRef<OsStr>,
+              V: AsRef<OsStr>,
+    {
+        let _ = self.inner.env(key, val);
+        self
+    }
+
+    /// Remove an environment variable for the child process.
+    pub fn env_remove<K>(&mut self, key: K) -> &mut Command
+        where K: AsRef<OsStr>,
+    {
+        let _ = self.inner.env_remove(key);
+        self
+    }
+
+    /// Clear all environment variables for the child process.
+    pub fn env_clear(&mut self) -> &mut Command {
+        let _ = self.inner.env_clear();
+        self
+    }
+
+    /// Set the working directory for the child process.
+    pub fn current_dir<P>(&mut self, dir: P) -> &mut Command
+        where P: AsRef<Path>,
+    {
+        let _ = self.inner.current_dir(dir);
+        self
+    }
+
+    /// Configuration for the child process's standard input (stdin) handle.
+    pub fn stdin<T>(&mut self, cfg: T) -> &mut Command
+        where T: Into<Stdio>,
+    {
+        let _ = self.inner.stdin(cfg);
+        self
+    }
+
+    /// Configuration for the child process's standard output (stdout) handle.
+    pub fn stdout<T>(&mut self, cfg: T) -> &mut Command
+        where T: Into<Stdio>,
+    {
+        let _ = self.inner.stdout(cfg);
+        self
+    }
+
+    /// Configuration for the child process's standard error (stderr) handle.
+    pub fn stderr<T>(&mut self, cfg: T) -> &mut Command
+        where T: Into<Stdio>,
+    {
+        let _ = self.inner.stderr(cfg);
+        self
+    }
+
+    /// Spawn the command as a child process.
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        self.inner.spawn().and_then(Child::new)
+    }
+}
+
+impl From<process::Command> for Command {
+    fn from(inner: process::Command) -> Command {
+        Command { inner }
+    }
+}
+
+/// A spawned child process.
+///
+/// Created by [`Command::spawn`]. The standard streams, if piped, are
+/// non-blocking and can be registered with [`OsQueue`].
+///
+/// On Linux `Child` can be registered with [`OsQueue`] itself, it becomes
+/// readable once the process exits, after which [`try_wait`] returns its exit
+/// status. If no process file descriptor is available, see [`pidfd`],
+/// registering returns an error.
+///
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`try_wait`]: Child::try_wait
+/// [`pidfd`]: Child::pidfd
+///
+/// # Notes
+///
+/// Like [`std::process::Child`] the child process is not killed or waited on
+/// when `Child` is dropped.
+#[derive(Debug)]
+pub struct Child {
+    /// Handle to the child's standard input (stdin), if piped.
+    pub stdin: Option<Sender>,
+    /// Handle to the child's standard output (stdout), if piped.
+    pub stdout: Option<Receiver>,
+    /// Handle to the child's standard error (stderr), if piped.
+    pub stderr: Option<Receiver>,
+    inner: process::Child,
+    /// Process file descriptor, readable once the process exits. `None` if
+    /// `pidfd_open(2)` failed.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pidfd: Option<OwnedFd>,
+}
+
+impl Child {
+    /// The interests to use when registering to receive exit notifications.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub const INTERESTS: Interests = Interests::READABLE;
+
+    /// Take the standard streams from `child`, making them non-blocking. If
+    /// this fails the child process is killed.
+    fn new(mut child: process::Child) -> io::Result<Child> {
+        let pid = child.id();
+        let stdin = child.stdin.take().map(|stdin| nonblocking(stdin).map(Sender::from)).transpose();
+        let stdout = child.stdout.take().map(|stdout| nonblocking(stdout).map(Receiver::from)).transpose();
+        let stderr = child.stderr.take().map(|stderr| nonblocking(stderr).map(Receiver::from)).transpose();
+        // Not supported before Linux 5.3, or we could be out of file
+        // descriptors. In both cases the child process can still be used,
+        // only without exit notifications.
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        let pidfd = pidfd_open(pid).ok();
+
+        let result = (move || Ok(Child {
+            stdin: stdin?,
+            stdout: stdout?,
+            stderr: stderr?,
+            inner: child,
+            #[cfg(any(target_os = "android", target_os = "linux"))]
+            pidfd,
+        }))();
+        if result.is_err() {
+            // Don't leave the child process running without a handle. It isn't
+            // waited on yet, so the process id can't be reused.
+            let pid = pid as libc::pid_t;
+            let _ = unsafe { libc::kill(pid, libc::SIGKILL) };
+            let _ = unsafe { libc::waitpid(pid, ptr::null_mut(), 0) };
+        }
+        result
+    }
+
+    /// Returns the process id of the child process.
+    pub fn id(&self) -> u32 {
+        self.inner.id()
+    }
+
+    /// Returns the process file descriptor used for exit notifications.
+    ///
+    /// Returns `None` if opening it failed, e.g. because `pidfd_open(2)` is
+    /// not supported (it requires Linux 5.3) or the process is out of file
+    /// descriptors. In that case registering the `Child` with [`OsQueue`]
+    /// returns an error, use [`try_wait`] or [`wait`] instead.
+    ///
+    /// This is only available on Linux and Android.
+    ///
+    /// [`OsQueue`]: crate::os::OsQueue
+    /// [`try_wait`]: Child::try_wait
+    /// [`wait`]: Child::wait
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn pidfd(&self) -> Option<BorrowedFd<'_>> {
+        self.pidfd.as_ref().map(AsFd::as_fd)
+    }
+
+    /// Returns the process file descriptor, or an error if it's not available.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    fn evented_pidfd(&self) -> io::Result<BorrowedFd<'_>> {
+        self.pidfd().ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported,
+            "no process file descriptor available for exit notifications"))
+    }
+
+    /// Attempts to collect the exit status of the child process, without
+    /// blocking.
+    ///
+    /// Returns `Ok(None)` if the process is still running. Once the process
+    /// exited, i.e. after a readable event on Linux, this returns its exit
+    /// status.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.inner.try_wait()
+    }
+
+    /// Wait for the child process to exit, **blocking** the current thread.
+    ///
+    /// The standard input is closed before waiting, to prevent a deadlock.
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        drop(self.stdin.take());
+        self.inner.wait()
+    }
+
+    /// Forces the child process to exit by sending it `SIGKILL`.
+    pub fn kill(&mut self) -> io::Result<()> {
+        self.inner.kill()
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+impl Evented for Child {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        debug_assert!(!interests.is_writable(), "Child can never be written");
+        self.evented_pidfd()?.register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        debug_assert!(!interests.is_writable(), "Child can never be written");
+        self.evented_pidfd()?.reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.evented_pidfd()?.deregister(registrar)
+    }
+}
+
+/// Set the non-blocking flag on a standard stream of a child process.
+fn nonblocking<T>(stream: T) -> io::Result<OwnedFd>
+    where OwnedFd: From<T>,
+{
+    let fd = OwnedFd::from(stream);
+    set_nonblocking(fd.as_raw_fd()).map(|()| fd)
+}
+
+/// Open a process file descriptor for the process with id `pid`, with the
+/// close-on-exec flag set.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn pidfd_open(pid: u32) -> io::Result<OwnedFd> {
+    match unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) } {
+        -1 => Err(io::Error::last_os_error()),
+        fd => Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) }),
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+mod tests {
+    use std::io;
+    use std::process;
+
+    use crate::event;
+    use crate::os::{OsQueue, RegisterOption};
+
+    use super::Child;
+
+    #[test]
+    fn child_without_pidfd() {
+        let mut os_queue = OsQueue::new().unwrap();
+        let inner = process::Command::new("true").spawn().unwrap();
+        let mut child = Child { stdin: None, stdout: None, stderr: None, inner, pidfd: None };
+        assert!(child.pidfd().is_none());
+
+        let err = os_queue.register(&mut child, event::Id(0), Child::INTERESTS, RegisterOption::LEVEL)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        // Waiting still works.
+        assert!(child.wait().unwrap().success());
+    }
+}
@@ -15,5 +15,10 @@ mod unix;
 #[cfg(unix)]
 pub use self::unix::*;
 
-/// Size of stack allocated system events array.
+/// Size of stack allocated system events array, or the initial size of the
+/// heap allocated array used by epoll.
 const EVENTS_CAP: usize = 128;
+
+/// Maximum size the heap allocated events array used by epoll grows to.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+const MAX_EVENTS_CAP: usize = 64 * 1024;
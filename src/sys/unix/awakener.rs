@@ -2,12 +2,11 @@
 mod eventfd {
     use std::fs::File;
     use std::io::{self, Read, Write};
-    use std::mem;
-    use std::os::unix::io::FromRawFd;
+    use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
 
     use crate::event;
-    use crate::os::{Interests, RegisterOption};
-    use crate::sys::Selector;
+    use crate::os::{Interests, RegisterOption, Selector};
+    use crate::sys::replace_fd;
 
     /// Awakener backed by `eventfd`.
     ///
@@ -18,51 +17,77 @@ mod eventfd {
     #[derive(Debug)]
     pub struct Awakener {
         fd: File,
+        id: event::Id,
     }
 
     impl Awakener {
         pub fn new(selector: &Selector, id: event::Id) -> io::Result<Awakener> {
-            let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
-            if fd == -1 {
-                return Err(io::Error::last_os_error());
-            }
-
+            let fd = new_eventfd()?;
             selector.register(fd, id, Interests::READABLE, RegisterOption::EDGE)?;
             Ok(Awakener {
                 fd: unsafe { File::from_raw_fd(fd) },
+                id,
             })
         }
 
         pub fn try_clone(&self) -> io::Result<Awakener> {
-            self.fd.try_clone().map(|fd| Awakener { fd })
+            self.fd.try_clone().map(|fd| Awakener { fd, id: self.id })
         }
 
+        /// Replace the eventfd shared with the parent process with a new one,
+        /// keeping the file descriptor number the same.
+        pub fn after_fork_child(&mut self, selector: &Selector) -> io::Result<()> {
+            let fd = self.fd.as_raw_fd();
+            // The registration is copied to the new selector, but it refers
+            // to the file shared with the parent process.
+            match selector.deregister(fd) {
+                Ok(()) => {},
+                Err(ref err) if err.raw_os_error() == Some(libc::ENOENT) => {},
+                Err(err) => return Err(err),
+            }
+            replace_fd(new_eventfd()?, fd)?;
+            selector.register(fd, self.id, Interests::READABLE, RegisterOption::EDGE)
+        }
+
+        /// Must be async-signal-safe.
         pub fn wake(&self) -> io::Result<()> {
-            let buf: [u8; 8] = unsafe { mem::transmute(1u64) };
-            match (&self.fd).write(&buf) {
-                Ok(_) => Ok(()),
-                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+            let buf: [u8; 8] = 1u64.to_ne_bytes();
+            loop {
+                match (&self.fd).write(&buf) {
+                    Ok(_) => return Ok(()),
                     // Writing only blocks if the counter is going to overflow.
                     // So we'll reset the counter to 0 and wake it again.
-                    self.reset()?;
-                    self.wake()
-                },
-                Err(err) => Err(err),
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => self.drain()?,
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err),
+                }
             }
         }
 
-        /// Reset the eventfd object, only need to call this if `wake` fails.
-        fn reset(&self) -> io::Result<()> {
+        /// Reset the counter of the eventfd object.
+        pub fn drain(&self) -> io::Result<()> {
             let mut buf: [u8; 8] = [0; 8];
-            match (&self.fd).read(&mut buf) {
-                Ok(_) => Ok(()),
-                // If the `Awakener` hasn't been awoken yet this will return a
-                // `WouldBlock` error which we can safely ignore.
-                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => Ok(()),
-                Err(err) => Err(err),
+            loop {
+                match (&self.fd).read(&mut buf) {
+                    Ok(_) => return Ok(()),
+                    // If the `Awakener` hasn't been awoken yet this will return
+                    // a `WouldBlock` error which we can safely ignore.
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err),
+                }
             }
         }
     }
+
+    fn new_eventfd() -> io::Result<RawFd> {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if fd == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(fd)
+        }
+    }
 }
 
 #[cfg(any(target_os = "linux", target_os = "android"))]
@@ -70,10 +95,10 @@ pub use self::eventfd::Awakener;
 
 #[cfg(any(target_os = "freebsd", target_os = "macos"))]
 mod kqueue {
-    use std::io;
+    use std::{io, mem};
 
-    use crate::event;
-    use crate::sys::Selector;
+    use crate::os::Selector;
+    use crate::{event, sys};
 
     /// Awakener backed by kqueue user space notifications (`EVFILT_USER`).
     ///
@@ -83,13 +108,13 @@ mod kqueue {
     /// descriptor. Now waking is as simple as adding an event to the kqueue.
     #[derive(Debug)]
     pub struct Awakener {
-        selector: Selector,
+        selector: sys::Selector,
         id: event::Id,
     }
 
     impl Awakener {
         pub fn new(selector: &Selector, id: event::Id) -> io::Result<Awakener> {
-            selector.try_clone().and_then(|selector| {
+            selector.sys().try_clone().and_then(|selector| {
                 selector.setup_awakener(id)
                     .map(|()| Awakener { selector, id })
             })
@@ -102,9 +127,24 @@ mod kqueue {
             })
         }
 
+        /// Setup the awakener again on the new kqueue of `selector`.
+        pub fn after_fork_child(&mut self, selector: &Selector) -> io::Result<()> {
+            let awakener = Awakener::new(selector, self.id)?;
+            // kqueues aren't inherited by child processes, so the file
+            // descriptor is already closed (and might be reused).
+            mem::forget(mem::replace(self, awakener));
+            Ok(())
+        }
+
+        /// Must be async-signal-safe.
         pub fn wake(&self) -> io::Result<()> {
             self.selector.wake(self.id)
         }
+
+        /// User space events don't need to be reset.
+        pub fn drain(&self) -> io::Result<()> {
+            Ok(())
+        }
     }
 }
 
@@ -118,8 +158,8 @@ mod pipe {
     use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
 
     use crate::event;
-    use crate::os::{Interests, RegisterOption};
-    use crate::sys::Selector;
+    use crate::os::{Interests, RegisterOption, Selector};
+    use crate::sys::replace_fd;
     use crate::unix::new_pipe;
 
     /// Awakener backed by a unix pipe.
@@ -130,6 +170,7 @@ mod pipe {
     pub struct Awakener {
         sender: File,
         receiver: File,
+        id: event::Id,
     }
 
     impl Awakener {
@@ -139,6 +180,7 @@ mod pipe {
             Ok(Awakener {
                 sender: unsafe { File::from_raw_fd(sender.into_raw_fd()) },
                 receiver: unsafe { File::from_raw_fd(receiver.into_raw_fd()) },
+                id,
             })
         }
 
@@ -146,31 +188,51 @@ mod pipe {
             Ok(Awakener {
                 sender: self.sender.try_clone()?,
                 receiver: self.receiver.try_clone()?,
+                id: self.id,
             })
         }
 
+        /// Replace the pipe shared with the parent process with a new one,
+        /// keeping the file descriptor numbers the same.
+        pub fn after_fork_child(&mut self, selector: &Selector) -> io::Result<()> {
+            let receiver_fd = self.receiver.as_raw_fd();
+            // The registration is copied to the new selector, but it refers
+            // to the file shared with the parent process.
+            match selector.deregister(receiver_fd) {
+                Ok(()) => {},
+                Err(ref err) if err.raw_os_error() == Some(libc::ENOENT) => {},
+                Err(err) => return Err(err),
+            }
+            let (sender, receiver) = new_pipe()?;
+            replace_fd(sender.into_raw_fd(), self.sender.as_raw_fd())?;
+            replace_fd(receiver.into_raw_fd(), receiver_fd)?;
+            selector.register(receiver_fd, self.id, Interests::READABLE, RegisterOption::EDGE)
+        }
+
+        /// Must be async-signal-safe.
         pub fn wake(&self) -> io::Result<()> {
-            match (&self.sender).write(&[1]) {
-                Ok(_) => Ok(()),
-                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
-                    // The reading end is full so we'll empty the buffer and try
-                    // again.
-                    self.empty();
-                    self.wake()
-                },
-                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => self.wake(),
-                Err(err) => Err(err)
+            loop {
+                match (&self.sender).write(&[1]) {
+                    Ok(_) => return Ok(()),
+                    // The reading end is full so we'll empty the buffer and
+                    // try again.
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => self.drain()?,
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err)
+                }
             }
         }
 
-        /// Empty the pipe's buffer, only need to call this if `wake` fails.
-        /// This ignores any errors.
-        fn empty(&self)  {
-            let mut buf = [0; 4096];
+        /// Empty the pipe's buffer.
+        pub fn drain(&self) -> io::Result<()> {
+            let mut buf = [0; 64];
             loop {
                 match (&self.receiver).read(&mut buf) {
                     Ok(n) if n > 0 => continue,
-                    _ => return,
+                    Ok(_) => return Ok(()),
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err),
                 }
             }
         }
@@ -1,62 +1,212 @@
 use std::cmp::min;
-use std::os::unix::io::RawFd;
+use std::fs;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use std::{io, mem, ptr};
+use std::{io, ptr};
 
 use log::error;
 
 use crate::event::{self, Event, Ready};
-use crate::os::{Interests, RegisterOption};
-use crate::sys::EVENTS_CAP;
+use crate::os::signals::SignalSet;
+use crate::os::{Interests, RegisterOption, Registration};
+use crate::sys::{EVENTS_CAP, MAX_EVENTS_CAP};
+
+use super::replace_fd;
+use super::signals::sigmask_without;
+
+/// Whether or not the kernel supports `epoll_pwait2` (Linux 5.11+), set to
+/// false the first time it returns `ENOSYS`.
+static HAS_EPOLL_PWAIT2: AtomicBool = AtomicBool::new(true);
+
+/// Size of the kernel's `sigset_t`, which is smaller than libc's
+/// `sigset_t`, required when calling `epoll_pwait2` directly.
+#[cfg(any(target_arch = "mips", target_arch = "mips64"))]
+const KERNEL_SIGSET_SIZE: usize = 128 / 8;
+#[cfg(not(any(target_arch = "mips", target_arch = "mips64")))]
+const KERNEL_SIGSET_SIZE: usize = 64 / 8;
 
 #[derive(Debug)]
 pub struct Selector {
     epfd: RawFd,
+    /// Timer used to support timeouts with sub-millisecond precision when
+    /// `epoll_pwait2` is not available. Lazily created.
+    timer: OnceLock<Timer>,
+    /// Reusable buffer for events returned by the kernel. Grows (up to
+    /// `MAX_EVENTS_CAP`) when filled by a single call.
+    ///
+    /// Only locked while polling, which requires mutable access to `OsQueue`,
+    /// so it's never contended.
+    ep_events: Mutex<Vec<libc::epoll_event>>,
+    /// Whether or not to keep polling, with a zero timeout, until the event
+    /// sink's capacity is used up.
+    fill_sink: AtomicBool,
 }
 
 impl Selector {
     pub fn new() -> io::Result<Selector> {
+        Selector::with_capacity(EVENTS_CAP)
+    }
+
+    pub fn with_capacity(capacity: usize) -> io::Result<Selector> {
         let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
         if epfd == -1 {
             Err(io::Error::last_os_error())
         } else {
-            Ok(Selector { epfd })
+            Ok(Selector {
+                epfd,
+                timer: OnceLock::new(),
+                ep_events: Mutex::new(new_ep_events(capacity.clamp(1, MAX_EVENTS_CAP))),
+                fill_sink: AtomicBool::new(false),
+            })
         }
     }
 
+    /// Set whether or not `select` should keep polling until the event sink's
+    /// capacity is used up.
+    pub fn set_fill_sink(&self, fill: bool) {
+        self.fill_sink.store(fill, Ordering::Relaxed);
+    }
+
     pub fn select<ES>(&self, event_sink: &mut ES, timeout: Option<Duration>) -> io::Result<()>
         where ES: event::Sink,
     {
-        let mut ep_events: [libc::epoll_event; EVENTS_CAP] = unsafe { mem::uninitialized() };
-        let events_cap = event_sink.capacity_left().min(EVENTS_CAP) as libc::c_int;
-        if events_cap == 0 {
-            // epoll can't deal with 0 capacity event arrays.
-            return Ok(())
+        self.select_sigmask(event_sink, timeout, None)
+    }
+
+    /// Same as `select`, but unblocks the signals in `unblock` while waiting.
+    /// If a signal handler is called while waiting this returns an error of
+    /// kind `Interrupted`.
+    pub fn select_with_sigmask<ES>(&self, event_sink: &mut ES, timeout: Option<Duration>, unblock: SignalSet) -> io::Result<()>
+        where ES: event::Sink,
+    {
+        let sigmask = sigmask_without(unblock)?;
+        self.select_sigmask(event_sink, timeout, Some(&sigmask))
+    }
+
+    fn select_sigmask<ES>(&self, event_sink: &mut ES, mut timeout: Option<Duration>, sigmask: Option<&libc::sigset_t>) -> io::Result<()>
+        where ES: event::Sink,
+    {
+        let mut ep_events = self.ep_events.lock().unwrap();
+        let fill_sink = self.fill_sink.load(Ordering::Relaxed);
+        let mut total = 0;
+        loop {
+            let events_cap = event_sink.capacity_left().min(ep_events.len());
+            if events_cap == 0 {
+                // epoll can't deal with 0 capacity event arrays.
+                return Ok(())
+            }
+
+            let buf = &mut ep_events[..events_cap];
+            let result = match timeout {
+                // Timeouts of whole milliseconds are supported by `epoll_wait`.
+                Some(timeout) if timeout.subsec_nanos() % 1_000_000 != 0 => self.wait_precise(buf, timeout, sigmask),
+                timeout => epoll_pwait(self.epfd, buf, timeout.map(duration_to_millis).unwrap_or(-1), sigmask),
+            };
+            let n_events = match result {
+                Ok(n_events) => n_events,
+                // Events were already added to the sink, so we can't return
+                // an interrupt as error.
+                Err(ref err) if total != 0 && err.kind() == io::ErrorKind::Interrupted => return Ok(()),
+                Err(err) => return Err(err),
+            };
+
+            // If `n_events` is 0 we reached the time limit and no events are pulled.
+            event_sink.extend(buf[..n_events].iter().map(ep_event_to_event));
+
+            if n_events == ep_events.len() && ep_events.len() < MAX_EVENTS_CAP {
+                // Filled the entire buffer, so there are likely more events
+                // ready, grow the buffer for the next call.
+                let new_len = min(ep_events.len() * 2, MAX_EVENTS_CAP);
+                *ep_events = new_ep_events(new_len);
+            }
+
+            total += n_events;
+            // If we didn't fill the buffer there are no more events ready.
+            // Level triggered registrations are returned again each call, so
+            // we limit the total number of events to not loop forever.
+            if !fill_sink || n_events < events_cap || total >= MAX_EVENTS_CAP {
+                return Ok(());
+            }
+            timeout = Some(Duration::from_millis(0));
+        }
+    }
+
+    /// Wait for events with a timeout of nanosecond precision. Uses
+    /// `epoll_pwait2` if available, falling back to a timer file descriptor
+    /// otherwise.
+    fn wait_precise(&self, ep_events: &mut [libc::epoll_event], timeout: Duration, sigmask: Option<&libc::sigset_t>) -> io::Result<usize> {
+        if HAS_EPOLL_PWAIT2.load(Ordering::Relaxed) {
+            match epoll_pwait2(self.epfd, ep_events, timeout, sigmask) {
+                Err(ref err) if err.raw_os_error() == Some(libc::ENOSYS) => {
+                    HAS_EPOLL_PWAIT2.store(false, Ordering::Relaxed);
+                },
+                result => return result,
+            }
         }
+        self.wait_timer(ep_events, timeout, sigmask)
+    }
 
-        let timeout_ms = timeout.map(duration_to_millis).unwrap_or(-1);
+    /// Wait for events, using the timer to wake up after `timeout`.
+    fn wait_timer(&self, ep_events: &mut [libc::epoll_event], timeout: Duration, sigmask: Option<&libc::sigset_t>) -> io::Result<usize> {
+        let timer = self.timer()?;
+        timerfd_settime(timer.fd, timeout)?;
+        // Wait until either events are ready or the timer expires, the timer
+        // should wake us up, but in case it doesn't we use the rounded up
+        // timeout. Then collect the events, without blocking.
+        let mut timer_events = [libc::epoll_event { events: 0, u64: 0 }; 2];
+        let result = epoll_pwait(timer.epfd, &mut timer_events, duration_to_millis(timeout), sigmask)
+            .and_then(|_| epoll_pwait(self.epfd, ep_events, 0, None));
+        // Disarm the timer, to not wake up future calls.
+        timerfd_settime(timer.fd, Duration::from_secs(0))?;
+        let _ = timer_expired(timer.fd);
+        result
+    }
 
-        let n_events = unsafe {
-            libc::epoll_wait(self.epfd, ep_events.as_mut_ptr(), events_cap, timeout_ms)
-        };
-        match n_events {
-            -1 => Err(io::Error::last_os_error()),
-            0 => Ok(()), // Reached the time limit, no events are pulled.
-            n => {
-                let ep_events = ep_events[..n as usize].iter()
-                    .map(ep_event_to_event);
-                event_sink.extend(ep_events);
-                Ok(())
-            },
+    /// Returns the timer, creating it if needed.
+    fn timer(&self) -> io::Result<&Timer> {
+        if let Some(timer) = self.timer.get() {
+            return Ok(timer);
+        }
+
+        let timer = Timer::new(self.epfd)?;
+        // If another thread beat us to it our timer is dropped.
+        Ok(self.timer.get_or_init(|| timer))
+    }
+
+    /// Replace the epoll instance, and timer, shared with the parent process
+    /// with new ones, keeping the file descriptor numbers the same. This drops
+    /// all registrations.
+    pub fn after_fork_child(&self) -> io::Result<()> {
+        let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if epfd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        replace_fd(epfd, self.epfd)?;
+
+        if let Some(timer) = self.timer.get() {
+            timer.after_fork_child(self.epfd)?;
         }
+        Ok(())
     }
 
     pub fn register(&self, fd: RawFd, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        if opt.is_exclusive() && opt.is_oneshot() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                "can't combine `RegisterOption::EXCLUSIVE` with `RegisterOption::ONESHOT`"));
+        }
+
         let mut epoll_event = new_epoll_event(interests, opt, id);
         epoll_ctl(self.epfd, libc::EPOLL_CTL_ADD, fd, &mut epoll_event)
     }
 
     pub fn reregister(&self, fd: RawFd, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        if opt.is_exclusive() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                "can't use `RegisterOption::EXCLUSIVE` when reregistering"));
+        }
+
         let mut epoll_event = new_epoll_event(interests, opt, id);
         epoll_ctl(self.epfd, libc::EPOLL_CTL_MOD, fd, &mut epoll_event)
     }
@@ -64,15 +214,146 @@ impl Selector {
     pub fn deregister(&self, fd: RawFd) -> io::Result<()> {
         epoll_ctl(self.epfd, libc::EPOLL_CTL_DEL, fd, ptr::null_mut())
     }
+
+    /// Set the kernel's busy poll parameters using `EPIOCSPARAMS`.
+    #[cfg(target_os = "linux")]
+    pub fn set_busy_poll_params(&self, usecs: u32, budget: u16, prefer: bool) -> io::Result<()> {
+        let params = libc::epoll_params {
+            busy_poll_usecs: usecs,
+            busy_poll_budget: budget,
+            prefer_busy_poll: prefer as u8,
+            __pad: 0,
+        };
+        if unsafe { libc::ioctl(self.epfd, libc::EPIOCSPARAMS, &params) } == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns all registrations as known to the kernel, read from
+    /// `/proc/self/fdinfo`.
+    pub fn registrations(&self) -> io::Result<Vec<Registration>> {
+        fs::read_to_string(format!("/proc/self/fdinfo/{}", self.epfd))
+            .and_then(|fdinfo| parse_fdinfo(&fdinfo))
+    }
+}
+
+/// Timer file descriptor, registered with a private epoll instance along with
+/// the epoll instance of the `Selector`. Waiting on the private epoll instance
+/// returns once either events are ready in the `Selector` or the timer
+/// expires. This keeps the timer out of the user's registrations.
+#[derive(Debug)]
+struct Timer {
+    epfd: RawFd,
+    fd: RawFd,
+}
+
+impl Timer {
+    fn new(selector_epfd: RawFd) -> io::Result<Timer> {
+        let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if epfd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_CLOEXEC | libc::TFD_NONBLOCK) };
+        if fd == -1 {
+            let err = io::Error::last_os_error();
+            close(epfd, "timer epoll");
+            return Err(err);
+        }
+        // Closes both file descriptors on error.
+        let timer = Timer { epfd, fd };
+        timer.register(selector_epfd).map(|()| timer)
+    }
+
+    /// Register the timer and `selector_epfd` with the private epoll instance.
+    fn register(&self, selector_epfd: RawFd) -> io::Result<()> {
+        for fd in [self.fd, selector_epfd] {
+            let mut epoll_event = libc::epoll_event { events: libc::EPOLLIN as u32, u64: 0 };
+            epoll_ctl(self.epfd, libc::EPOLL_CTL_ADD, fd, &mut epoll_event)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the epoll instance and timer shared with the parent process,
+    /// see `Selector::after_fork_child`.
+    fn after_fork_child(&self, selector_epfd: RawFd) -> io::Result<()> {
+        let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if epfd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        replace_fd(epfd, self.epfd)?;
+        let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_CLOEXEC | libc::TFD_NONBLOCK) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        replace_fd(fd, self.fd)?;
+        self.register(selector_epfd)
+    }
 }
 
-/// Convert a `Duration` to milliseconds.
+impl Drop for Timer {
+    fn drop(&mut self) {
+        close(self.fd, "timer");
+        close(self.epfd, "timer epoll");
+    }
+}
+
+/// Parse the contents of `/proc/self/fdinfo/$epfd`. Each registration is a
+/// line in the following format:
+///
+/// ```text
+/// tfd:        5 events:       19 data:                0  pos:0 ino:61f sdev:d
+/// ```
+///
+/// Where `tfd` is the registered file descriptor (decimal), `events` the epoll
+/// events mask (hexadecimal) and `data` the user data (hexadecimal).
+fn parse_fdinfo(fdinfo: &str) -> io::Result<Vec<Registration>> {
+    fdinfo.lines()
+        .filter(|line| line.starts_with("tfd:"))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut field = |name, radix| match (fields.next(), fields.next()) {
+                (Some(n), Some(value)) if n == name => u64::from_str_radix(value, radix).ok(),
+                _ => None,
+            };
+            match (field("tfd:", 10), field("events:", 16), field("data:", 16)) {
+                (Some(fd), Some(events), Some(data)) => {
+                    let (interests, opt) = from_epoll_events(events as u32);
+                    Ok(Registration::new(fd as RawFd, event::Id(data as usize), interests, opt))
+                },
+                _ => Err(io::Error::new(io::ErrorKind::InvalidData,
+                    format!("unable to parse epoll fdinfo: {}", line))),
+            }
+        })
+        .collect()
+}
+
+/// Create a new buffer of `len` events.
+fn new_ep_events(len: usize) -> Vec<libc::epoll_event> {
+    vec![libc::epoll_event { events: 0, u64: 0 }; len]
+}
+
+/// Convert a `Duration` to milliseconds, rounding up.
 ///
 /// # Notes
 ///
 /// Uses 24 hours as maximum to match kqueue.
 pub fn duration_to_millis(duration: Duration) -> libc::c_int {
-    min(duration.as_millis(), 24 * 60 * 60 * 1_000) as libc::c_int
+    let millis = duration.as_nanos().div_ceil(1_000_000);
+    min(millis, 24 * 60 * 60 * 1_000) as libc::c_int
+}
+
+/// Create a `timespec` from a duration.
+fn timespec_from_duration(duration: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: min(duration.as_secs(), libc::time_t::MAX as u64) as libc::time_t,
+        // `Duration::subsec_nanos` is guaranteed to be less than one
+        // billion (the number of nanoseconds in a second), making the
+        // cast to i32 safe. The cast itself is needed for platforms
+        // where C's long is only 32 bits.
+        tv_nsec: libc::c_long::from(duration.subsec_nanos() as i32),
+    }
 }
 
 /// Convert a `epoll_event` into an `Event`.
@@ -114,7 +395,13 @@ fn new_epoll_event(interests: Interests, opt: RegisterOption, id: event::Id) ->
 }
 
 fn to_epoll_events(interests: Interests, opt: RegisterOption) -> u32 {
-    let mut events = libc::EPOLLPRI | libc::EPOLLRDHUP;
+    // `EPOLLEXCLUSIVE` can only be combined with `EPOLLIN`, `EPOLLOUT`,
+    // `EPOLLET` and `EPOLLWAKEUP`.
+    let mut events = if opt.is_exclusive() {
+        libc::EPOLLEXCLUSIVE
+    } else {
+        libc::EPOLLPRI | libc::EPOLLRDHUP
+    };
 
     if interests.is_readable() {
         events |= libc::EPOLLIN;
@@ -134,6 +421,96 @@ fn to_epoll_events(interests: Interests, opt: RegisterOption) -> u32 {
     events as u32
 }
 
+/// Convert epoll events back into `Interests` and `RegisterOption`, the
+/// reverse of `to_epoll_events`. Returns `None` as interests if the
+/// registration is disabled, i.e. a oneshot registration that triggered.
+fn from_epoll_events(events: u32) -> (Option<Interests>, RegisterOption) {
+    let interests = match (contains_flag(events, libc::EPOLLIN), contains_flag(events, libc::EPOLLOUT)) {
+        (true, true) => Some(Interests::READABLE | Interests::WRITABLE),
+        (true, false) => Some(Interests::READABLE),
+        (false, true) => Some(Interests::WRITABLE),
+        (false, false) => None,
+    };
+
+    let mut opt = RegisterOption::LEVEL;
+    if contains_flag(events, libc::EPOLLET) {
+        opt = opt | RegisterOption::EDGE;
+    }
+    if contains_flag(events, libc::EPOLLONESHOT) {
+        opt = opt | RegisterOption::ONESHOT;
+    }
+    if contains_flag(events, libc::EPOLLEXCLUSIVE) {
+        opt = opt | RegisterOption::EXCLUSIVE;
+    }
+    (interests, opt)
+}
+
+/// Calls `epoll_pwait`, if `sigmask` is `None` this is the same as
+/// `epoll_wait`.
+fn epoll_pwait(epfd: RawFd, ep_events: &mut [libc::epoll_event], timeout_ms: libc::c_int, sigmask: Option<&libc::sigset_t>) -> io::Result<usize> {
+    let n_events = unsafe {
+        libc::epoll_pwait(epfd, ep_events.as_mut_ptr(), ep_events.len() as libc::c_int,
+            timeout_ms, sigmask_ptr(sigmask))
+    };
+    if n_events == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n_events as usize)
+    }
+}
+
+fn epoll_pwait2(epfd: RawFd, ep_events: &mut [libc::epoll_event], timeout: Duration, sigmask: Option<&libc::sigset_t>) -> io::Result<usize> {
+    let timeout = timespec_from_duration(timeout);
+    let n_events = unsafe {
+        libc::syscall(libc::SYS_epoll_pwait2, epfd, ep_events.as_mut_ptr(),
+            ep_events.len() as libc::c_int, &timeout, sigmask_ptr(sigmask),
+            KERNEL_SIGSET_SIZE)
+    };
+    if n_events == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n_events as usize)
+    }
+}
+
+#[allow(trivial_casts)]
+fn sigmask_ptr(sigmask: Option<&libc::sigset_t>) -> *const libc::sigset_t {
+    sigmask.map(|sigmask| sigmask as *const libc::sigset_t).unwrap_or(ptr::null())
+}
+
+/// Arm the timer to expire once after `timeout`, or disarm it if `timeout` is
+/// zero.
+fn timerfd_settime(timer: RawFd, timeout: Duration) -> io::Result<()> {
+    let new_value = libc::itimerspec {
+        it_interval: timespec_from_duration(Duration::from_secs(0)),
+        it_value: timespec_from_duration(timeout),
+    };
+    if unsafe { libc::timerfd_settime(timer, 0, &new_value, ptr::null_mut()) } == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns true if the timer expired, resetting it.
+fn timer_expired(timer: RawFd) -> bool {
+    let mut expirations = [0; 8];
+    let n = unsafe { libc::read(timer, expirations.as_mut_ptr() as *mut libc::c_void, expirations.len()) };
+    n == expirations.len() as isize
+}
+
+fn close(fd: RawFd, name: &str) {
+    if unsafe { libc::close(fd) } == -1 {
+        // Possible errors:
+        // - EBADF, EIO: can't recover.
+        // - EINTR: could try again but we're can't be sure if the file
+        //          descriptor was closed or not, so to be safe we don't
+        //          close it again.
+        let err = io::Error::last_os_error();
+        error!("error closing {}: {}", name, err);
+    }
+}
+
 fn epoll_ctl(epfd: RawFd, op: libc::c_int, fd: RawFd, event: *mut libc::epoll_event) -> io::Result<()> {
     if unsafe { libc::epoll_ctl(epfd, op, fd, event) } == -1 {
         // Possible errors:
@@ -146,16 +523,94 @@ fn epoll_ctl(epfd: RawFd, op: libc::c_int, fd: RawFd, event: *mut libc::epoll_ev
     }
 }
 
+impl AsRawFd for Selector {
+    fn as_raw_fd(&self) -> RawFd {
+        self.epfd
+    }
+}
+
 impl Drop for Selector {
     fn drop(&mut self) {
-        if unsafe { libc::close(self.epfd) } == -1 {
-            // Possible errors:
-            // - EBADF, EIO: can't recover.
-            // - EINTR: could try again but we're can't be sure if the file
-            //          descriptor was closed or not, so to be safe we don't
-            //          close it again.
-            let err = io::Error::last_os_error();
-            error!("error closing epoll: {}", err);
+        close(self.epfd, "epoll");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::io::AsRawFd;
+    use std::time::{Duration, Instant};
+
+    use crate::event;
+    use crate::os::{Interests, RegisterOption, Registration};
+    use crate::sys::pipe::new_pipe;
+
+    use super::{duration_to_millis, parse_fdinfo, to_epoll_events, Selector};
+
+    #[test]
+    fn duration_to_millis_rounds_up() {
+        assert_eq!(duration_to_millis(Duration::from_millis(0)), 0);
+        assert_eq!(duration_to_millis(Duration::from_nanos(1)), 1);
+        assert_eq!(duration_to_millis(Duration::from_micros(500)), 1);
+        assert_eq!(duration_to_millis(Duration::from_millis(1)), 1);
+        assert_eq!(duration_to_millis(Duration::from_micros(1001)), 2);
+        assert_eq!(duration_to_millis(Duration::from_secs(48 * 60 * 60)), 24 * 60 * 60 * 1_000);
+    }
+
+    #[test]
+    fn timer_fallback() {
+        let selector = Selector::new().unwrap();
+        let mut ep_events: [libc::epoll_event; 4] = unsafe { std::mem::zeroed() };
+        for _ in 0..3 {
+            let timeout = Duration::from_micros(300);
+            let start = Instant::now();
+            assert_eq!(selector.wait_timer(&mut ep_events, timeout, None).unwrap(), 0);
+            assert!(start.elapsed() >= timeout);
+        }
+
+        // The timer should be disarmed.
+        let timer = selector.timer.get().unwrap();
+        assert_eq!(super::epoll_pwait(timer.epfd, &mut ep_events, 5, None).unwrap(), 0);
+        // And not registered with the selector.
+        assert!(selector.registrations().unwrap().is_empty());
+
+        // Events are returned before the timer expires, for all ids.
+        let (sender, _receiver) = new_pipe().unwrap();
+        let id = event::Id(usize::MAX);
+        selector.register(sender.as_raw_fd(), id, Interests::WRITABLE, RegisterOption::EDGE).unwrap();
+        let start = Instant::now();
+        assert_eq!(selector.wait_timer(&mut ep_events, Duration::from_secs(1), None).unwrap(), 1);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(super::ep_event_to_event(&ep_events[0]).id(), id);
+    }
+
+    #[test]
+    fn parsing_fdinfo() {
+        let fdinfo = "pos:\t0\nflags:\t02000002\nmnt_id:\t17\nino:\t26\n\
+            tfd:        5 events: 4000201d data:                5  pos:0 ino:2b1b sdev:f\n\
+            tfd:        4 events: 80000019 data:               1f  pos:0 ino:2b1b sdev:f\n\
+            tfd:        3 events: 80000000 data:                2  pos:0 ino:2b1b sdev:f\n";
+        let registrations = parse_fdinfo(fdinfo).unwrap();
+        assert_eq!(registrations, vec![
+            Registration::new(5, event::Id(5), Some(Interests::READABLE | Interests::WRITABLE), RegisterOption::ONESHOT),
+            Registration::new(4, event::Id(31), Some(Interests::READABLE), RegisterOption::EDGE),
+            // Triggered oneshot registration.
+            Registration::new(3, event::Id(2), None, RegisterOption::EDGE),
+        ]);
+
+        assert!(parse_fdinfo("tfd: 5 events: xyz data: 0").is_err());
+        assert!(parse_fdinfo("pos:\t0\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn epoll_events_round_trip() {
+        let tests = &[
+            (Interests::READABLE, RegisterOption::LEVEL),
+            (Interests::WRITABLE, RegisterOption::EDGE),
+            (Interests::READABLE | Interests::WRITABLE, RegisterOption::EDGE | RegisterOption::ONESHOT),
+            (Interests::READABLE, RegisterOption::EDGE | RegisterOption::EXCLUSIVE),
+        ];
+        for (interests, opt) in tests.iter().cloned() {
+            assert_eq!(super::from_epoll_events(to_epoll_events(interests, opt)), (Some(interests), opt));
         }
     }
 }
@@ -1,24 +1,27 @@
-use std::io;
-use std::os::unix::io::RawFd;
+use std::fs::File;
+use std::io::{self, Read, Write};
+#[cfg(feature = "nightly")]
+use std::io::{IoSlice, IoSliceMut};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
 
 use crate::event;
-use crate::os::{Evented, Interests, OsQueue, RegisterOption};
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
 
-/// Adapter for a `RawFd` providing an [`Evented`] implementation.
+/// Owned file descriptor that can be registered with [`OsQueue`].
 ///
-/// `EventedFd` enables registering any type with an file descriptor with
-/// [`OsQueue`].
+/// `EventedFd` enables registering any file descriptor, that can be registered
+/// with the underlying OS selector, with [`OsQueue`]. It takes ownership of
+/// the file descriptor, closing it when dropped, and implements [`Read`] and
+/// [`Write`] by reading from and writing to it.
 ///
-/// While only implementations for TCP and UDP are provided, registering any
-/// file descriptor, that can be registered with the underlying OS selector, can
-/// be registered with `OsQueue`. `EventedFd` provides the necessary bridge.
+/// [`Evented`] is also implemented for [`BorrowedFd`], so a file descriptor
+/// that is owned elsewhere can be registered without taking ownership of it.
 ///
-/// Note that `EventedFd` takes a reference to a `RawFd`. This is because
-/// `EventedFd` **does not** take ownership of the file descriptor.
-/// Specifically, it will not manage any lifecycle related operations, such as
-/// closing the file descriptor on drop. It is expected that the `EventedFd` is
-/// constructed right before a call to [`OsQueue::register`]. See the examples
-/// below for more detail.
+/// Note that `EventedFd` doesn't change the file descriptor in any way, e.g.
+/// it doesn't set the non-blocking flag.
+///
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`Evented`]: crate::os::Evented
 ///
 /// # Deregistering
 ///
@@ -28,12 +31,12 @@ use crate::os::{Evented, Interests, OsQueue, RegisterOption};
 ///
 /// # Examples
 ///
-/// Basic usage
+/// Registering an owned file descriptor.
 ///
 /// ```
 /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// use std::net::TcpListener;
-/// use std::os::unix::io::AsRawFd;
+/// use std::os::unix::io::OwnedFd;
 ///
 /// use gaea::event;
 /// use gaea::os::{Interests, RegisterOption, OsQueue};
@@ -41,57 +44,140 @@ use crate::os::{Evented, Interests, OsQueue, RegisterOption};
 ///
 /// // Bind a listener from the standard library.
 /// let listener = TcpListener::bind("127.0.0.1:0")?;
+/// listener.set_nonblocking(true)?;
 ///
 /// let mut os_queue = OsQueue::new()?;
 ///
 /// // Register the listener using `EventedFd`.
-/// os_queue.register(&mut EventedFd(&listener.as_raw_fd()), event::Id(0), Interests::READABLE, RegisterOption::EDGE)?;
+/// let mut listener = EventedFd::new(OwnedFd::from(listener));
+/// os_queue.register(&mut listener, event::Id(0), Interests::READABLE, RegisterOption::EDGE)?;
 /// #     Ok(())
 /// # }
 /// ```
 ///
-/// Implementing `Evented` for a custom type backed by a `RawFd`.
+/// Registering a borrowed file descriptor.
 ///
 /// ```
-/// use std::io;
-/// use std::os::unix::io::RawFd;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::net::TcpListener;
+/// use std::os::unix::io::AsFd;
 ///
 /// use gaea::event;
-/// use gaea::os::{Evented, Interests, RegisterOption, OsQueue};
-/// use gaea::unix::EventedFd;
-///
-/// # #[allow(dead_code)]
-/// pub struct MyIo {
-///     fd: RawFd,
-/// }
+/// use gaea::os::{Interests, RegisterOption, OsQueue};
 ///
-/// impl Evented for MyIo {
-///     fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-///         EventedFd(&self.fd).register(os_queue, id, interests, opt)
-///     }
+/// let listener = TcpListener::bind("127.0.0.1:0")?;
+/// listener.set_nonblocking(true)?;
 ///
-///     fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-///         EventedFd(&self.fd).reregister(os_queue, id, interests, opt)
-///     }
+/// let mut os_queue = OsQueue::new()?;
 ///
-///     fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-///         EventedFd(&self.fd).deregister(os_queue)
-///     }
-/// }
+/// // `BorrowedFd` implements `Evented`, the listener remains owned by us.
+/// os_queue.register(&mut listener.as_fd(), event::Id(0), Interests::READABLE, RegisterOption::EDGE)?;
+/// #     Ok(())
+/// # }
 /// ```
 #[derive(Debug)]
-pub struct EventedFd<'a>(pub &'a RawFd);
+pub struct EventedFd {
+    inner: File,
+}
+
+impl EventedFd {
+    /// Create a new `EventedFd`, taking ownership of `fd`.
+    pub fn new(fd: OwnedFd) -> EventedFd {
+        EventedFd { inner: File::from(fd) }
+    }
+
+    /// Returns the owned file descriptor.
+    pub fn into_inner(self) -> OwnedFd {
+        OwnedFd::from(self.inner)
+    }
+}
+
+impl From<OwnedFd> for EventedFd {
+    fn from(fd: OwnedFd) -> EventedFd {
+        EventedFd::new(fd)
+    }
+}
+
+impl From<EventedFd> for OwnedFd {
+    fn from(fd: EventedFd) -> OwnedFd {
+        fd.into_inner()
+    }
+}
+
+impl AsFd for EventedFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl AsRawFd for EventedFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl FromRawFd for EventedFd {
+    unsafe fn from_raw_fd(fd: RawFd) -> EventedFd {
+        EventedFd { inner: File::from_raw_fd(fd) }
+    }
+}
+
+impl IntoRawFd for EventedFd {
+    fn into_raw_fd(self) -> RawFd {
+        self.inner.into_raw_fd()
+    }
+}
+
+impl Read for EventedFd {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+
+    #[cfg(feature = "nightly")]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        self.inner.read_vectored(bufs)
+    }
+}
+
+impl Write for EventedFd {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    #[cfg(feature = "nightly")]
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.inner.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Evented for EventedFd {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
+    }
+}
 
-impl<'a> Evented for EventedFd<'a> {
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        os_queue.selector().register(*self.0, id, interests, opt)
+impl<'a> Evented for BorrowedFd<'a> {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        registrar.selector().register(self.as_raw_fd(), id, interests, opt)
     }
 
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        os_queue.selector().reregister(*self.0, id, interests, opt)
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        registrar.selector().reregister(self.as_raw_fd(), id, interests, opt)
     }
 
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-        os_queue.selector().deregister(*self.0)
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        registrar.selector().deregister(self.as_raw_fd())
     }
 }
@@ -0,0 +1,202 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+
+use crate::event;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+use crate::sys::unix::{into_inheritable, set_cloexec};
+
+/// Non-blocking event counter backed by Linux's `eventfd(2)`.
+///
+/// An `EventFd` holds an 64 bit counter maintained by the kernel. [`add`]
+/// adds to the counter and [`read_count`] reads the counter and resets it to
+/// zero. The `EventFd` is readable while the counter is larger than zero and
+/// writable while at least one can be added without overflowing. In semaphore
+/// mode (see [`new_semaphore`]) [`read_count`] only decrements the counter by
+/// one.
+///
+/// The `EventFd` can be shared between threads using [`try_clone`], and
+/// between processes by passing the file descriptor, e.g. using
+/// [`into_inheritable`] or `SCM_RIGHTS` messages.
+///
+/// The file descriptor is created with the close-on-exec flag set.
+///
+/// [`add`]: EventFd::add
+/// [`read_count`]: EventFd::read_count
+/// [`new_semaphore`]: EventFd::new_semaphore
+/// [`try_clone`]: EventFd::try_clone
+/// [`into_inheritable`]: EventFd::into_inheritable
+///
+/// # Deregistering
+///
+/// `EventFd` will deregister itself when dropped, **iff** the file descriptor
+/// is not duplicated (via `dup(2)` or [`try_clone`]).
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io;
+/// use std::thread;
+///
+/// use gaea::os::{OsQueue, RegisterOption};
+/// use gaea::unix::EventFd;
+/// use gaea::{event, poll};
+///
+/// let mut os_queue = OsQueue::new()?;
+/// let mut events = Vec::new();
+///
+/// let mut counter = EventFd::new(0)?;
+/// os_queue.register(&mut counter, event::Id(0), EventFd::INTERESTS, RegisterOption::LEVEL)?;
+///
+/// // Add to the counter from another thread.
+/// let mut counter2 = counter.try_clone()?;
+/// let handle = thread::spawn(move || {
+///     counter2.add(2).expect("unable to add to counter");
+///     counter2.add(3).expect("unable to add to counter");
+/// });
+/// handle.join().unwrap();
+///
+/// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+/// assert_eq!(events[0].id(), event::Id(0));
+/// assert_eq!(counter.read_count()?, 5);
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct EventFd {
+    inner: File,
+}
+
+impl EventFd {
+    /// The interests to use when registering to receive both readable and
+    /// writable events.
+    pub const INTERESTS: Interests = Interests::BOTH;
+
+    /// Create a new `EventFd` with the counter set to `initial`.
+    pub fn new(initial: u32) -> io::Result<EventFd> {
+        EventFd::with_flags(initial, 0)
+    }
+
+    /// Create a new `EventFd` in semaphore mode, with the counter set to
+    /// `initial`.
+    ///
+    /// In semaphore mode [`read_count`] decrements the counter by one, always
+    /// returning one, rather than resetting it to zero (`EFD_SEMAPHORE`).
+    ///
+    /// [`read_count`]: EventFd::read_count
+    pub fn new_semaphore(initial: u32) -> io::Result<EventFd> {
+        EventFd::with_flags(initial, libc::EFD_SEMAPHORE)
+    }
+
+    fn with_flags(initial: u32, flags: libc::c_int) -> io::Result<EventFd> {
+        let fd = unsafe { libc::eventfd(initial, flags | libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if fd == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(EventFd { inner: unsafe { File::from_raw_fd(fd) } })
+        }
+    }
+
+    /// Reads the counter.
+    ///
+    /// Returns the value of the counter and resets it to zero, or in semaphore
+    /// mode returns one and decrements the counter by one. If the counter is
+    /// zero this returns an error of kind [`WouldBlock`].
+    ///
+    /// [`WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn read_count(&mut self) -> io::Result<u64> {
+        let mut buf = [0; 8];
+        self.inner.read(&mut buf).map(|n| {
+            debug_assert_eq!(n, buf.len());
+            u64::from_ne_bytes(buf)
+        })
+    }
+
+    /// Adds `n` to the counter.
+    ///
+    /// If adding `n` would overflow the counter this returns an error of kind
+    /// [`WouldBlock`]. `n` may not be `u64::MAX`, for which an error of kind
+    /// [`InvalidInput`] is returned.
+    ///
+    /// [`WouldBlock`]: io::ErrorKind::WouldBlock
+    /// [`InvalidInput`]: io::ErrorKind::InvalidInput
+    pub fn add(&mut self, n: u64) -> io::Result<()> {
+        self.inner.write(&n.to_ne_bytes()).map(|n| debug_assert_eq!(n, 8))
+    }
+
+    /// Creates a new independently owned handle to the same counter.
+    pub fn try_clone(&self) -> io::Result<EventFd> {
+        self.inner.try_clone().map(|inner| EventFd { inner })
+    }
+
+    /// Set whether or not `EventFd` is [inherited] by programs started with
+    /// `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    pub fn set_inheritable(&mut self, inheritable: bool) -> io::Result<()> {
+        set_cloexec(self.inner.as_raw_fd(), !inheritable)
+    }
+
+    /// Converts `EventFd` into a raw file descriptor that is [inherited] by
+    /// programs started with `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    pub fn into_inheritable(self) -> io::Result<RawFd> {
+        into_inheritable(self)
+    }
+}
+
+impl Evented for EventFd {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
+    }
+}
+
+impl AsFd for EventFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl AsRawFd for EventFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl From<OwnedFd> for EventFd {
+    /// The caller must ensure that the file descriptor is an eventfd in
+    /// non-blocking mode.
+    fn from(fd: OwnedFd) -> EventFd {
+        EventFd { inner: File::from(fd) }
+    }
+}
+
+impl From<EventFd> for OwnedFd {
+    fn from(eventfd: EventFd) -> OwnedFd {
+        OwnedFd::from(eventfd.inner)
+    }
+}
+
+impl FromRawFd for EventFd {
+    /// The caller must ensure that the file descriptor is an eventfd in
+    /// non-blocking mode.
+    unsafe fn from_raw_fd(fd: RawFd) -> EventFd {
+        EventFd { inner: File::from_raw_fd(fd) }
+    }
+}
+
+impl IntoRawFd for EventFd {
+    fn into_raw_fd(self) -> RawFd {
+        self.inner.into_raw_fd()
+    }
+}
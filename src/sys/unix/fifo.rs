@@ -0,0 +1,161 @@
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+use std::os::unix::io::OwnedFd;
+use std::path::{Path, PathBuf};
+
+use crate::sys::pipe::{Receiver, Sender};
+
+/// Named pipe, or FIFO.
+///
+/// A FIFO is a pipe with a path in the file system, created with
+/// [`Fifo::create`]. Unrelated processes can communicate through it by opening
+/// the receiving end with [`open_receiver`] and the sending end with
+/// [`open_sender`], both are opened in non-blocking mode and with the
+/// close-on-exec flag set.
+///
+/// [`open_receiver`]: Fifo::open_receiver
+/// [`open_sender`]: Fifo::open_sender
+///
+/// # Opening without the other end
+///
+/// Opening the receiving end always succeeds, even if no sending end is open.
+/// Opening the sending end however fails, with an error of kind
+/// [`NotConnected`], if no receiving end is open (`ENXIO`). The sending end can
+/// be opened again once a reader opened the FIFO.
+///
+/// [`NotConnected`]: io::ErrorKind::NotConnected
+///
+/// # Waiting for writers
+///
+/// On Linux a newly opened receiving end doesn't report any readiness until a
+/// writer opened the FIFO and wrote to or closed it. This means the receiver
+/// can be registered with [`OsQueue`] to wait for a writer. Once all writers
+/// closed their ends the receiver reports hang up readiness ([`Ready::HUP`])
+/// and reading returns zero bytes. It will continue to do so, even if a new
+/// writer opens the FIFO, so to wait for the next writer the receiving end must
+/// be opened again. Other platforms may report hang up readiness for a
+/// receiving end that was opened without any writers.
+///
+/// [`OsQueue`]: crate::os::OsQueue
+/// [`Ready::HUP`]: crate::event::Ready::HUP
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::{self, Read, Write};
+///
+/// use gaea::os::{OsQueue, RegisterOption};
+/// use gaea::unix::{Fifo, Receiver};
+/// use gaea::{event, poll};
+///
+/// let mut os_queue = OsQueue::new()?;
+/// let mut events = Vec::new();
+///
+/// let path = std::env::temp_dir().join(format!("gaea_fifo_example_{}", std::process::id()));
+/// let fifo = Fifo::create(&path, 0o600)?;
+///
+/// // Open the receiving end first, otherwise opening the sending end fails.
+/// let mut receiver = fifo.open_receiver()?;
+/// os_queue.register(&mut receiver, event::Id(0), Receiver::INTERESTS, RegisterOption::LEVEL)?;
+///
+/// // Normally the sending end would be opened by another process.
+/// let mut sender = fifo.open_sender()?;
+/// sender.write_all(b"Hello world")?;
+///
+/// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+/// let mut buf = [0; 20];
+/// let n = receiver.read(&mut buf)?;
+/// assert_eq!(&buf[..n], b"Hello world");
+/// # std::fs::remove_file(&path)?;
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fifo {
+    path: PathBuf,
+}
+
+impl Fifo {
+    /// Create a new FIFO at `path`, with permissions `mode` (modified by the
+    /// process's umask), using `mkfifo(3)`.
+    ///
+    /// Returns an error of kind [`AlreadyExists`] if `path` already exists, use
+    /// [`Fifo::new`] to use an existing FIFO.
+    ///
+    /// [`AlreadyExists`]: io::ErrorKind::AlreadyExists
+    pub fn create<P>(path: P, mode: u32) -> io::Result<Fifo>
+        where P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains a null byte"))?;
+        #[allow(trivial_numeric_casts)]
+        let mode = mode as libc::mode_t;
+        if unsafe { libc::mkfifo(c_path.as_ptr(), mode) } == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Fifo { path })
+        }
+    }
+
+    /// Use an existing FIFO at `path`.
+    ///
+    /// This doesn't check the path, but opening either end fails if the path
+    /// is not a FIFO.
+    pub fn new<P>(path: P) -> Fifo
+        where P: Into<PathBuf>,
+    {
+        Fifo { path: path.into() }
+    }
+
+    /// Returns the path of the FIFO.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Open the receiving end of the FIFO.
+    ///
+    /// See [Waiting for writers] for the readiness of the receiver without any
+    /// writers.
+    ///
+    /// [Waiting for writers]: #waiting-for-writers
+    pub fn open_receiver(&self) -> io::Result<Receiver> {
+        self.open(true).map(Receiver::from)
+    }
+
+    /// Open the sending end of the FIFO.
+    ///
+    /// Returns an error of kind [`NotConnected`] if no receiving end is open.
+    ///
+    /// [`NotConnected`]: io::ErrorKind::NotConnected
+    pub fn open_sender(&self) -> io::Result<Sender> {
+        self.open(false).map(Sender::from).map_err(|err| match err.raw_os_error() {
+            Some(libc::ENXIO) => io::Error::new(io::ErrorKind::NotConnected, "no receiving end of the FIFO is open"),
+            _ => err,
+        })
+    }
+
+    /// Open the FIFO in non-blocking mode for reading (`read == true`) or
+    /// writing.
+    fn open(&self, read: bool) -> io::Result<OwnedFd> {
+        let file = OpenOptions::new()
+            .read(read)
+            .write(!read)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&self.path)?;
+        check_fifo(&file).map(|()| OwnedFd::from(file))
+    }
+}
+
+/// Make sure `file` is a FIFO.
+fn check_fifo(file: &File) -> io::Result<()> {
+    if file.metadata()?.file_type().is_fifo() {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "path is not a FIFO"))
+    }
+}
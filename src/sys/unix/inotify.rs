@@ -0,0 +1,393 @@
+use std::ffi::{CString, OsStr, OsString};
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::BitOr;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, RawFd};
+use std::path::Path;
+use std::ptr;
+
+use crate::event;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+
+/// Size of the buffer used to read events, large enough to hold at least a
+/// single event with the longest possible name.
+const BUF_SIZE: usize = 4096;
+
+/// Size of an event, excluding the name.
+const EVENT_SIZE: usize = size_of::<libc::inotify_event>();
+
+/// Filesystem watcher backed by Linux's `inotify(7)`.
+///
+/// Files and directories are watched by using [`add_watch`], after which
+/// changes can be received using [`receive`] once the `Watcher` is readable.
+///
+/// [`add_watch`]: Watcher::add_watch
+/// [`receive`]: Watcher::receive
+///
+/// # Notes
+///
+/// When watching a directory, events are generated for the directory itself
+/// and the files inside it, but not recursively for any subdirectories.
+///
+/// If the queue of events overflows, events are lost and a
+/// [`WatchEventKind::Overflow`] event is received.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::fs::{self, File};
+/// use std::io;
+///
+/// use gaea::os::{OsQueue, RegisterOption};
+/// use gaea::unix::{WatchEventKind, WatchMask, Watcher};
+/// use gaea::{event, poll};
+///
+/// let dir = std::env::temp_dir().join(format!("gaea_watcher_doc_{}", std::process::id()));
+/// fs::create_dir(&dir)?;
+///
+/// let mut os_queue = OsQueue::new()?;
+/// let mut events = Vec::new();
+///
+/// let mut watcher = Watcher::new()?;
+/// let watch = watcher.add_watch(&dir, WatchMask::CREATE | WatchMask::DELETE)?;
+/// os_queue.register(&mut watcher, event::Id(0), Watcher::INTERESTS, RegisterOption::LEVEL)?;
+///
+/// File::create(dir.join("config.toml"))?;
+///
+/// poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+/// let event = watcher.receive()?.unwrap();
+/// assert_eq!(event.watch(), Some(watch));
+/// assert_eq!(event.kind(), WatchEventKind::Create);
+/// assert_eq!(event.name().unwrap(), "config.toml");
+/// # fs::remove_dir_all(&dir)?;
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Watcher {
+    fd: File,
+    /// Buffer of read, but not yet received, events.
+    buf: Box<[u8]>,
+    /// Position of the next event in `buf`.
+    pos: usize,
+    /// Number of valid bytes in `buf`.
+    len: usize,
+}
+
+impl Watcher {
+    /// The interests to use when registering to receive filesystem events.
+    pub const INTERESTS: Interests = Interests::READABLE;
+
+    /// Create a new `Watcher`, without any watches.
+    pub fn new() -> io::Result<Watcher> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Watcher {
+                fd: unsafe { File::from_raw_fd(fd) },
+                buf: vec![0; BUF_SIZE].into_boxed_slice(),
+                pos: 0,
+                len: 0,
+            })
+        }
+    }
+
+    /// Watch `path` for the events in `mask`.
+    ///
+    /// If `path` is already watched, the mask of the existing watch is
+    /// replaced and the same `WatchId` is returned.
+    pub fn add_watch<P>(&mut self, path: P, mask: WatchMask) -> io::Result<WatchId>
+        where P: AsRef<Path>,
+    {
+        let path = CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains a null byte"))?;
+        match unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), path.as_ptr(), mask.0) } {
+            -1 => Err(io::Error::last_os_error()),
+            wd => Ok(WatchId(wd)),
+        }
+    }
+
+    /// Stop watching the watch with id `watch`.
+    ///
+    /// This generates a [`WatchEventKind::Ignored`] event for the watch.
+    pub fn remove_watch(&mut self, watch: WatchId) -> io::Result<()> {
+        if unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), watch.0) } == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Receive a filesystem event, if any.
+    ///
+    /// This should be called until it returns `Ok(None)` after receiving a
+    /// readable event, as a single read may return multiple events.
+    pub fn receive(&mut self) -> io::Result<Option<WatchEvent>> {
+        if self.pos >= self.len {
+            self.pos = 0;
+            self.len = 0;
+            self.len = loop {
+                match self.fd.read(&mut self.buf) {
+                    Ok(n) => break n,
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err),
+                }
+            };
+        }
+
+        // The kernel only returns complete events.
+        assert!(self.len - self.pos >= EVENT_SIZE, "incomplete inotify event");
+        #[allow(trivial_casts)]
+        let event: libc::inotify_event = unsafe {
+            ptr::read_unaligned(self.buf[self.pos..].as_ptr() as *const libc::inotify_event)
+        };
+        let name_start = self.pos + EVENT_SIZE;
+        self.pos = name_start + event.len as usize;
+
+        // The name is padded with null bytes.
+        let name = &self.buf[name_start..self.pos];
+        let name = match name.iter().position(|b| *b == 0) {
+            Some(0) => None,
+            Some(end) => Some(&name[..end]),
+            None if name.is_empty() => None,
+            None => Some(name),
+        };
+
+        Ok(Some(WatchEvent {
+            watch: if event.wd == -1 { None } else { Some(WatchId(event.wd)) },
+            kind: WatchEventKind::from_mask(event.mask, event.cookie),
+            is_dir: event.mask & libc::IN_ISDIR != 0,
+            name: name.map(|name| OsString::from_vec(name.to_vec())),
+        }))
+    }
+}
+
+impl Evented for Watcher {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        debug_assert!(!interests.is_writable(), "Watcher can never be written");
+        self.as_fd().register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        debug_assert!(!interests.is_writable(), "Watcher can never be written");
+        self.as_fd().reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
+    }
+}
+
+impl AsFd for Watcher {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for Watcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+/// Identifier of a watch, returned by [`Watcher::add_watch`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct WatchId(libc::c_int);
+
+/// Events to watch for, used in [`Watcher::add_watch`].
+///
+/// # Examples
+///
+/// ```
+/// use gaea::unix::WatchMask;
+///
+/// let mask = WatchMask::CREATE | WatchMask::DELETE;
+/// assert!(mask.contains(WatchMask::CREATE));
+/// assert!(!mask.contains(WatchMask::MODIFY));
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct WatchMask(u32);
+
+impl WatchMask {
+    /// File was accessed, e.g. read (`IN_ACCESS`).
+    pub const ACCESS: WatchMask = WatchMask(libc::IN_ACCESS);
+    /// File was modified, e.g. written (`IN_MODIFY`).
+    pub const MODIFY: WatchMask = WatchMask(libc::IN_MODIFY);
+    /// Metadata changed, e.g. permissions (`IN_ATTRIB`).
+    pub const ATTRIBUTE: WatchMask = WatchMask(libc::IN_ATTRIB);
+    /// File opened for writing was closed (`IN_CLOSE_WRITE`).
+    pub const CLOSE_WRITE: WatchMask = WatchMask(libc::IN_CLOSE_WRITE);
+    /// File not opened for writing was closed (`IN_CLOSE_NOWRITE`).
+    pub const CLOSE_NO_WRITE: WatchMask = WatchMask(libc::IN_CLOSE_NOWRITE);
+    /// File was opened (`IN_OPEN`).
+    pub const OPEN: WatchMask = WatchMask(libc::IN_OPEN);
+    /// File was moved out of, or into, the watched directory (`IN_MOVE`).
+    pub const MOVE: WatchMask = WatchMask(libc::IN_MOVED_FROM | libc::IN_MOVED_TO);
+    /// File was created in the watched directory (`IN_CREATE`).
+    pub const CREATE: WatchMask = WatchMask(libc::IN_CREATE);
+    /// File was deleted from the watched directory (`IN_DELETE`).
+    pub const DELETE: WatchMask = WatchMask(libc::IN_DELETE);
+    /// The watched file or directory itself was deleted (`IN_DELETE_SELF`).
+    pub const DELETE_SELF: WatchMask = WatchMask(libc::IN_DELETE_SELF);
+    /// The watched file or directory itself was moved (`IN_MOVE_SELF`).
+    pub const MOVE_SELF: WatchMask = WatchMask(libc::IN_MOVE_SELF);
+    /// All of the above events (`IN_ALL_EVENTS`).
+    pub const ALL: WatchMask = WatchMask(libc::IN_ALL_EVENTS);
+
+    /// Whether or not all events in `other` are contained within `self`.
+    pub const fn contains(self, other: WatchMask) -> bool {
+        (self.0 & other.0) == other.0
+    }
+}
+
+impl BitOr for WatchMask {
+    type Output = WatchMask;
+
+    fn bitor(self, rhs: Self) -> Self {
+        WatchMask(self.0 | rhs.0)
+    }
+}
+
+/// Filesystem event, received using [`Watcher::receive`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WatchEvent {
+    watch: Option<WatchId>,
+    kind: WatchEventKind,
+    is_dir: bool,
+    name: Option<OsString>,
+}
+
+impl WatchEvent {
+    /// Returns the watch the event is for, `None` for
+    /// [`WatchEventKind::Overflow`].
+    pub fn watch(&self) -> Option<WatchId> {
+        self.watch
+    }
+
+    /// Returns the kind of event.
+    pub fn kind(&self) -> WatchEventKind {
+        self.kind
+    }
+
+    /// Returns true if the subject of the event is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Returns the name of the file inside the watched directory the event is
+    /// for, or `None` if the event is for the watched file or directory
+    /// itself.
+    pub fn name(&self) -> Option<&OsStr> {
+        self.name.as_deref()
+    }
+}
+
+/// Kind of [`WatchEvent`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum WatchEventKind {
+    /// File was accessed, see [`WatchMask::ACCESS`].
+    Access,
+    /// File was modified, see [`WatchMask::MODIFY`].
+    Modify,
+    /// Metadata changed, see [`WatchMask::ATTRIBUTE`].
+    Attribute,
+    /// File opened for writing was closed, see [`WatchMask::CLOSE_WRITE`].
+    CloseWrite,
+    /// File not opened for writing was closed, see
+    /// [`WatchMask::CLOSE_NO_WRITE`].
+    CloseNoWrite,
+    /// File was opened, see [`WatchMask::OPEN`].
+    Open,
+    /// File was moved out of the watched directory, see [`WatchMask::MOVE`].
+    ///
+    /// The `cookie` is the same as the cookie of the related
+    /// [`WatchEventKind::MovedTo`] event, if the file was moved into a
+    /// watched directory.
+    MovedFrom {
+        /// Cookie connecting the `MovedFrom` and `MovedTo` events.
+        cookie: u32,
+    },
+    /// File was moved into the watched directory, see [`WatchMask::MOVE`].
+    MovedTo {
+        /// Cookie connecting the `MovedFrom` and `MovedTo` events.
+        cookie: u32,
+    },
+    /// File was created, see [`WatchMask::CREATE`].
+    Create,
+    /// File was deleted, see [`WatchMask::DELETE`].
+    Delete,
+    /// The watched file or directory was deleted, see
+    /// [`WatchMask::DELETE_SELF`].
+    DeleteSelf,
+    /// The watched file or directory was moved, see [`WatchMask::MOVE_SELF`].
+    MoveSelf,
+    /// The filesystem containing the watched file or directory was
+    /// unmounted.
+    Unmount,
+    /// The watch was removed, either explicitly using
+    /// [`Watcher::remove_watch`] or because the file was deleted or its
+    /// filesystem unmounted. No more events are received for the watch.
+    Ignored,
+    /// The event queue overflowed, some events were lost.
+    Overflow,
+}
+
+impl WatchEventKind {
+    /// Convert the mask of an `inotify_event` into a kind. Each event only has
+    /// a single event bit set.
+    fn from_mask(mask: u32, cookie: u32) -> WatchEventKind {
+        use self::WatchEventKind::*;
+        match mask & !(libc::IN_ISDIR) {
+            m if m & libc::IN_Q_OVERFLOW != 0 => Overflow,
+            m if m & libc::IN_IGNORED != 0 => Ignored,
+            m if m & libc::IN_UNMOUNT != 0 => Unmount,
+            m if m & libc::IN_ACCESS != 0 => Access,
+            m if m & libc::IN_MODIFY != 0 => Modify,
+            m if m & libc::IN_ATTRIB != 0 => Attribute,
+            m if m & libc::IN_CLOSE_WRITE != 0 => CloseWrite,
+            m if m & libc::IN_CLOSE_NOWRITE != 0 => CloseNoWrite,
+            m if m & libc::IN_OPEN != 0 => Open,
+            m if m & libc::IN_MOVED_FROM != 0 => MovedFrom { cookie },
+            m if m & libc::IN_MOVED_TO != 0 => MovedTo { cookie },
+            m if m & libc::IN_CREATE != 0 => Create,
+            m if m & libc::IN_DELETE != 0 => Delete,
+            m if m & libc::IN_DELETE_SELF != 0 => DeleteSelf,
+            m if m & libc::IN_MOVE_SELF != 0 => MoveSelf,
+            // All events have one of the bits above set.
+            _ => unreachable!("unknown inotify event mask: {:#x}", mask),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WatchEventKind;
+
+    // More tests can be found in `tests/unix_watcher.rs`. This is only tested
+    // here because it's not part of the public API, and an overflow is hard to
+    // trigger.
+
+    #[test]
+    fn watch_event_kind_from_mask() {
+        let tests = [
+            (libc::IN_Q_OVERFLOW, WatchEventKind::Overflow),
+            (libc::IN_IGNORED, WatchEventKind::Ignored),
+            (libc::IN_UNMOUNT, WatchEventKind::Unmount),
+            (libc::IN_CREATE, WatchEventKind::Create),
+            (libc::IN_CREATE | libc::IN_ISDIR, WatchEventKind::Create),
+            (libc::IN_DELETE, WatchEventKind::Delete),
+            (libc::IN_MODIFY, WatchEventKind::Modify),
+            (libc::IN_MOVED_FROM, WatchEventKind::MovedFrom { cookie: 123 }),
+            (libc::IN_MOVED_TO, WatchEventKind::MovedTo { cookie: 123 }),
+            (libc::IN_DELETE_SELF, WatchEventKind::DeleteSelf),
+            (libc::IN_MOVE_SELF, WatchEventKind::MoveSelf),
+        ];
+        for (mask, expected) in tests.iter() {
+            assert_eq!(WatchEventKind::from_mask(*mask, 123), *expected);
+        }
+    }
+}
@@ -9,6 +9,8 @@ use crate::event::{self, Event, Ready};
 use crate::os::{Interests, RegisterOption, SignalSet};
 use crate::sys::EVENTS_CAP;
 
+use super::{replace_fd, set_cloexec};
+
 // Of course each OS that implements kqueue has chosen to go for different types
 // in the `kevent` structure, hence the type definitions below.
 
@@ -65,12 +67,12 @@ pub struct Selector {
 
 impl Selector {
     pub fn new() -> io::Result<Selector> {
-        let kq = unsafe { libc::kqueue() };
-        if kq == -1 {
-            Err(io::Error::last_os_error())
-        } else {
-            Ok(Selector { kq })
-        }
+        new_kqueue().map(|kq| Selector { kq })
+    }
+
+    /// kqueue uses a fixed size events array, so `capacity` is ignored.
+    pub fn with_capacity(_capacity: usize) -> io::Result<Selector> {
+        Selector::new()
     }
 
     pub fn select<ES>(&self, event_sink: &mut ES, timeout: Option<Duration>) -> io::Result<()>
@@ -102,6 +104,13 @@ impl Selector {
         }
     }
 
+    /// Replace the kqueue with a new one, keeping the file descriptor number
+    /// the same. kqueues aren't inherited by child processes, so this must be
+    /// called after forking.
+    pub fn after_fork_child(&self) -> io::Result<()> {
+        new_kqueue().and_then(|kq| replace_fd(kq, self.kq))
+    }
+
     pub fn register(&self, fd: RawFd, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
         let flags = opt_to_flags(opt) | libc::EV_ADD;
         // At most we need two changes, but maybe we only need 1.
@@ -167,7 +176,7 @@ impl Selector {
     // Used by `Awakener`.
     #[cfg(any(target_os = "freebsd", target_os = "macos"))]
     pub fn try_clone(&self) -> io::Result<Selector> {
-        let new_kq = unsafe { libc::dup(self.kq) };
+        let new_kq = unsafe { libc::fcntl(self.kq, libc::F_DUPFD_CLOEXEC, 0) };
         if new_kq == -1 {
             Err(io::Error::last_os_error())
         } else {
@@ -185,17 +194,11 @@ impl Selector {
 
     // Used by `Signals`.
     pub fn register_signals(&self, id: event::Id, signals: SignalSet) -> io::Result<()> {
-        let mut changes: [libc::kevent; SignalSet::all().size()] = unsafe { mem::uninitialized() };
-        let mut n_changes = 0;
-
-        for signal in signals {
-            let kevent = new_kevent(signal.into_raw() as libc::uintptr_t,
-                libc::EVFILT_SIGNAL, libc::EV_RECEIPT | libc::EV_ADD, id);
-            unsafe { ptr::write(&mut changes[n_changes], kevent) };
-            n_changes += 1;
-        }
-
-        kevent_register(self.kq, &mut changes[0..n_changes], &[])
+        let mut changes: Vec<libc::kevent> = signals.into_iter()
+            .map(|signal| new_kevent(signal.into_raw() as libc::uintptr_t,
+                libc::EVFILT_SIGNAL, libc::EV_RECEIPT | libc::EV_ADD, id))
+            .collect();
+        kevent_register(self.kq, &mut changes, &[])
     }
 }
 
@@ -317,6 +320,21 @@ fn check_errors(events: &[libc::kevent], ignored_errors: &[kevent_data_t]) -> io
     Ok(())
 }
 
+/// Create a new kqueue with the close-on-exec flag set.
+fn new_kqueue() -> io::Result<RawFd> {
+    let kq = unsafe { libc::kqueue() };
+    if kq == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    match set_cloexec(kq, true) {
+        Ok(()) => Ok(kq),
+        Err(err) => {
+            let _ = unsafe { libc::close(kq) };
+            Err(err)
+        },
+    }
+}
+
 /// Whether or not the provided `flags` contains the provided `flag`.
 const fn contains_flag(flags: kevent_flags_t, flag: kevent_flags_t) -> bool {
     (flags & flag) != 0
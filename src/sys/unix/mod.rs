@@ -1,11 +1,24 @@
+use std::io;
+use std::os::unix::io::{AsRawFd, IntoRawFd, RawFd};
+
 mod awakener;
 mod eventedfd;
+mod fifo;
+mod pty;
 mod signals;
 mod tcp;
+mod tty;
 mod udp;
 
 pub mod pipe;
 
+#[cfg(any(target_os = "android", target_os = "linux"))]
+mod eventfd;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+mod inotify;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+mod socket;
+
 #[cfg(any(target_os = "android", target_os = "linux"))]
 mod epoll;
 
@@ -22,6 +35,69 @@ pub use self::kqueue::Selector;
 
 pub use self::awakener::Awakener;
 pub use self::eventedfd::EventedFd;
-pub use self::signals::Signals;
+pub use self::fifo::Fifo;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub use self::eventfd::EventFd;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub use self::inotify::{WatchEvent, WatchEventKind, WatchId, WatchMask, Watcher};
+pub use self::pty::Pty;
+pub use self::signals::{SignalHandler, Signals};
 pub use self::tcp::{TcpListener, TcpStream};
+pub use self::tty::{stderr, stdin, stdout, Tty, WindowSize};
 pub use self::udp::UdpSocket;
+
+/// Make the file descriptor `old` refer to the file description of `new` and
+/// close `new`. The file descriptor number `old` doesn't change, so it can be
+/// used to replace a resource shared with a parent process after forking. The
+/// close-on-exec flag is set on `old`.
+///
+/// `new` is closed even if an error is returned.
+pub(crate) fn replace_fd(new: RawFd, old: RawFd) -> io::Result<()> {
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    let res = unsafe { libc::dup3(new, old, libc::O_CLOEXEC) };
+    #[cfg(not(any(target_os = "android", target_os = "linux")))]
+    let res = unsafe {
+        match libc::dup2(new, old) {
+            -1 => -1,
+            _ => libc::fcntl(old, libc::F_SETFD, libc::FD_CLOEXEC),
+        }
+    };
+    let result = if res == -1 { Err(io::Error::last_os_error()) } else { Ok(()) };
+    let _ = unsafe { libc::close(new) };
+    result
+}
+
+/// Set (`cloexec == true`) or clear the close-on-exec flag on `fd`.
+pub(crate) fn set_cloexec(fd: RawFd, cloexec: bool) -> io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    if flags == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let new_flags = if cloexec { flags | libc::FD_CLOEXEC } else { flags & !libc::FD_CLOEXEC };
+    if new_flags != flags && unsafe { libc::fcntl(fd, libc::F_SETFD, new_flags) } == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Clear the close-on-exec flag on `handle` and convert it into a raw file
+/// descriptor, see the `unix` module docs. `handle` is dropped on error.
+pub(crate) fn into_inheritable<H>(handle: H) -> io::Result<RawFd>
+    where H: AsRawFd + IntoRawFd,
+{
+    set_cloexec(handle.as_raw_fd(), false).map(|()| handle.into_raw_fd())
+}
+
+/// Set the non-blocking flag on `fd`.
+pub(crate) fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    if flags & libc::O_NONBLOCK == 0 && unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
@@ -2,12 +2,11 @@ use std::fs::File;
 use std::io::{self, Read, Write};
 #[cfg(feature = "nightly")]
 use std::io::{IoSlice, IoSliceMut};
-use std::mem;
-use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
 
 use crate::event;
-use crate::os::{Evented, Interests, OsQueue, RegisterOption};
-use crate::sys::unix::EventedFd;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+use crate::sys::unix::{into_inheritable, set_cloexec};
 
 /// Create a new non-blocking unix pipe.
 ///
@@ -16,7 +15,14 @@ use crate::sys::unix::EventedFd;
 ///
 /// This channel may be created before forking the process and then one end used
 /// in each process, e.g. the parent process has the sending end to send command
-/// to the child process.
+/// to the child process. To communicate with unrelated processes use a named
+/// pipe, see [`Fifo`].
+///
+/// Both ends are created with the close-on-exec flag set, use
+/// [`Sender::set_inheritable`] or [`Receiver::set_inheritable`] to pass an end
+/// to a program started with `exec(3)`.
+///
+/// [`Fifo`]: crate::unix::Fifo
 ///
 /// # Deregistering
 ///
@@ -72,20 +78,28 @@ use crate::sys::unix::EventedFd;
 /// # }
 /// ```
 pub fn new_pipe() -> io::Result<(Sender, Receiver)> {
-    let mut fds: [RawFd; 2] = unsafe { mem::uninitialized() };
-
-    if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
-        Err(io::Error::last_os_error())
-    } else {
-        for fd in &fds {
-            if unsafe { libc::fcntl(*fd, libc::F_SETFL, libc::O_NONBLOCK) } == -1 {
-                return Err(io::Error::last_os_error());
-            }
+    let mut fds: [RawFd; 2] = [-1, -1];
+
+    #[cfg(not(target_os = "macos"))]
+    let res = unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC | libc::O_NONBLOCK) };
+    #[cfg(target_os = "macos")]
+    let res = unsafe { libc::pipe(fds.as_mut_ptr()) };
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let r = Receiver { inner: unsafe { File::from_raw_fd(fds[0]) } };
+    let w = Sender { inner: unsafe { File::from_raw_fd(fds[1]) } };
+
+    // macOS doesn't support `pipe2`, so we have to set the flags manually.
+    #[cfg(target_os = "macos")]
+    for fd in &fds {
+        set_cloexec(*fd, true)?;
+        if unsafe { libc::fcntl(*fd, libc::F_SETFL, libc::O_NONBLOCK) } == -1 {
+            return Err(io::Error::last_os_error());
         }
-        let r = Receiver { inner: unsafe { File::from_raw_fd(fds[0]) } };
-        let w = Sender { inner: unsafe { File::from_raw_fd(fds[1]) } };
-        Ok((w, r))
     }
+    Ok((w, r))
 }
 
 /// Receiving end of an unix pipe.
@@ -99,21 +113,43 @@ pub struct Receiver {
 impl Receiver {
     /// The interests to use when registering to receive readable events.
     pub const INTERESTS: Interests = Interests::READABLE;
+
+    /// Set whether or not the receiving end is [inherited] by programs started with
+    /// `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    pub fn set_inheritable(&mut self, inheritable: bool) -> io::Result<()> {
+        set_cloexec(self.inner.as_raw_fd(), !inheritable)
+    }
+
+    /// Converts the receiving end into a raw file descriptor that is [inherited] by
+    /// programs started with `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    pub fn into_inheritable(self) -> io::Result<RawFd> {
+        into_inheritable(self)
+    }
 }
 
 impl Evented for Receiver {
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
         debug_assert!(!interests.is_writable(), "receiving end of a pipe can never be written");
-        EventedFd(&self.inner.as_raw_fd()).register(os_queue, id, interests, opt)
+        self.as_fd().register(registrar, id, interests, opt)
     }
 
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
         debug_assert!(!interests.is_writable(), "receiving end of a pipe can never be written");
-        EventedFd(&self.inner.as_raw_fd()).reregister(os_queue, id, interests, opt)
+        self.as_fd().reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
     }
+}
 
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-        EventedFd(&self.inner.as_raw_fd()).deregister(os_queue)
+impl AsFd for Receiver {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
     }
 }
 
@@ -123,6 +159,20 @@ impl AsRawFd for Receiver {
     }
 }
 
+impl From<OwnedFd> for Receiver {
+    /// The caller must ensure that the file descriptor is the receiving end of a
+    /// pipe in non-blocking mode.
+    fn from(fd: OwnedFd) -> Receiver {
+        Receiver { inner: File::from(fd) }
+    }
+}
+
+impl From<Receiver> for OwnedFd {
+    fn from(receiver: Receiver) -> OwnedFd {
+        OwnedFd::from(receiver.inner)
+    }
+}
+
 impl IntoRawFd for Receiver {
     fn into_raw_fd(self) -> RawFd {
         self.inner.into_raw_fd()
@@ -151,21 +201,43 @@ pub struct Sender {
 impl Sender {
     /// The interests to use when registering to receive writable events.
     pub const INTERESTS: Interests = Interests::WRITABLE;
+
+    /// Set whether or not the sending end is [inherited] by programs started with
+    /// `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    pub fn set_inheritable(&mut self, inheritable: bool) -> io::Result<()> {
+        set_cloexec(self.inner.as_raw_fd(), !inheritable)
+    }
+
+    /// Converts the sending end into a raw file descriptor that is [inherited] by
+    /// programs started with `exec(3)`.
+    ///
+    /// [inherited]: crate::unix#inheriting-file-descriptors
+    pub fn into_inheritable(self) -> io::Result<RawFd> {
+        into_inheritable(self)
+    }
 }
 
 impl Evented for Sender {
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
         debug_assert!(!interests.is_readable(), "sending end of a pipe can never be read");
-        EventedFd(&self.inner.as_raw_fd()).register(os_queue, id, interests, opt)
+        self.as_fd().register(registrar, id, interests, opt)
     }
 
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
         debug_assert!(!interests.is_readable(), "sending end of a pipe can never be read");
-        EventedFd(&self.inner.as_raw_fd()).reregister(os_queue, id, interests, opt)
+        self.as_fd().reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
     }
+}
 
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-        EventedFd(&self.inner.as_raw_fd()).deregister(os_queue)
+impl AsFd for Sender {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
     }
 }
 
@@ -175,6 +247,20 @@ impl AsRawFd for Sender {
     }
 }
 
+impl From<OwnedFd> for Sender {
+    /// The caller must ensure that the file descriptor is the sending end of a
+    /// pipe in non-blocking mode.
+    fn from(fd: OwnedFd) -> Sender {
+        Sender { inner: File::from(fd) }
+    }
+}
+
+impl From<Sender> for OwnedFd {
+    fn from(sender: Sender) -> OwnedFd {
+        OwnedFd::from(sender.inner)
+    }
+}
+
 impl IntoRawFd for Sender {
     fn into_raw_fd(self) -> RawFd {
         self.inner.into_raw_fd()
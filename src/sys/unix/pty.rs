@@ -0,0 +1,280 @@
+use std::ffi::{CStr, OsStr};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+#[cfg(feature = "nightly")]
+use std::io::{IoSlice, IoSliceMut};
+use std::mem::MaybeUninit;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::path::{Path, PathBuf};
+
+use crate::event;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+use crate::sys::unix::set_nonblocking;
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+use crate::sys::unix::set_cloexec;
+use crate::sys::unix::tty::WindowSize;
+
+/// Non-blocking pseudo-terminal (pty) master.
+///
+/// A pseudo-terminal is a pair of a master and a slave device. The slave
+/// behaves like a regular terminal and is used as controlling terminal and
+/// standard streams of a child process, e.g. a shell. Anything written to the
+/// master is input to the slave, and anything written to the slave can be read
+/// from the master.
+///
+/// [`Pty::open`] opens a new master, in non-blocking mode. The slave can be
+/// opened using [`open_slave`] (or [`slave_path`]) and is opened in blocking
+/// mode, as expected by most programs.
+///
+/// [`open_slave`]: Pty::open_slave
+/// [`slave_path`]: Pty::slave_path
+///
+/// # Hang up
+///
+/// Once all file descriptors of the slave are closed, e.g. because the child
+/// process exited, the master reports hang up readiness ([`Ready::HUP`]) and
+/// reading returns zero bytes, like at the end of a pipe. This also means that
+/// the master reports hang up *before* the slave is opened for the first time,
+/// so open the slave before registering the master. Linux reports an `EIO`
+/// error in this case, which the [`Read`] implementation converts into a read
+/// of zero bytes.
+///
+/// [`Ready::HUP`]: crate::event::Ready::HUP
+///
+/// # Controlling terminal
+///
+/// To make the slave the controlling terminal of the child process, the child
+/// must call `setsid(2)` and `ioctl(fd, TIOCSCTTY, 0)` before executing the
+/// program, e.g. using [`std::os::unix::process::CommandExt::pre_exec`].
+/// Without a controlling terminal the child will not receive signals such as
+/// [`WindowChange`], or [`Interrupt`] when `^C` is written to the master.
+///
+/// [`WindowChange`]: crate::os::Signal::WindowChange
+/// [`Interrupt`]: crate::os::Signal::Interrupt
+///
+/// # Deregistering
+///
+/// `Pty` will deregister itself when dropped, **iff** the file descriptor is
+/// not duplicated (via `dup(2)`).
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::{self, Read};
+///
+/// use gaea::os::{OsQueue, RegisterOption};
+/// use gaea::process::{Command, Stdio};
+/// use gaea::unix::{Pty, WindowSize};
+/// use gaea::{event, poll};
+///
+/// let mut os_queue = OsQueue::new()?;
+/// let mut events = Vec::new();
+///
+/// let mut pty = Pty::open()?;
+/// pty.set_window_size(WindowSize::new(24, 80))?;
+///
+/// // Use the slave as standard streams of the child process.
+/// let slave = pty.open_slave()?;
+/// let mut child = Command::new("stty")
+///     .arg("size")
+///     .stdin(Stdio::from(slave.try_clone()?))
+///     .stdout(Stdio::from(slave.try_clone()?))
+///     .stderr(Stdio::from(slave))
+///     .spawn()?;
+///
+/// os_queue.register(&mut pty, event::Id(0), Pty::INTERESTS, RegisterOption::LEVEL)?;
+///
+/// // Read until the child process exits, closing the slave.
+/// let mut output = Vec::new();
+/// loop {
+///     poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+///     match pty.read_to_end(&mut output) {
+///         Ok(_) => break,
+///         Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+///         Err(err) => return Err(err.into()),
+///     }
+/// }
+///
+/// assert!(child.wait()?.success());
+/// assert_eq!(output, b"24 80\r\n");
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Pty {
+    master: File,
+    slave_path: PathBuf,
+}
+
+impl Pty {
+    /// The interests to use when registering to receive both readable and
+    /// writable events.
+    pub const INTERESTS: Interests = Interests::BOTH;
+
+    /// Open a new pseudo-terminal master, in non-blocking mode.
+    ///
+    /// The file descriptor is created with the close-on-exec flag set.
+    pub fn open() -> io::Result<Pty> {
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        let flags = libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC;
+        #[cfg(not(any(target_os = "android", target_os = "linux")))]
+        let flags = libc::O_RDWR | libc::O_NOCTTY;
+        let fd = unsafe { libc::posix_openpt(flags) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let master = unsafe { File::from_raw_fd(fd) };
+
+        // Not all platforms support `O_CLOEXEC` in `posix_openpt`, so we have
+        // to set the flag manually.
+        #[cfg(not(any(target_os = "android", target_os = "linux")))]
+        set_cloexec(fd, true)?;
+        set_nonblocking(fd)?;
+        if unsafe { libc::grantpt(fd) } == -1 || unsafe { libc::unlockpt(fd) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let slave_path = slave_path(fd)?;
+        Ok(Pty { master, slave_path })
+    }
+
+    /// Returns the path of the slave device, e.g. `/dev/pts/3`.
+    pub fn slave_path(&self) -> &Path {
+        &self.slave_path
+    }
+
+    /// Open the slave device, in blocking mode.
+    ///
+    /// The slave is opened without making it the controlling terminal of the
+    /// calling process and with the close-on-exec flag set. The close-on-exec
+    /// flag is cleared when using it as a standard stream of a child process,
+    /// see [`Stdio`].
+    ///
+    /// [`Stdio`]: crate::process::Stdio
+    pub fn open_slave(&self) -> io::Result<OwnedFd> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(&self.slave_path)
+            .map(OwnedFd::from)
+    }
+
+    /// Set the size of the terminal window.
+    ///
+    /// If the terminal is the controlling terminal of a process group, the
+    /// foreground process group receives the [`WindowChange`] signal.
+    ///
+    /// [`WindowChange`]: crate::os::Signal::WindowChange
+    pub fn set_window_size(&mut self, size: WindowSize) -> io::Result<()> {
+        let size = size.into_raw();
+        if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &size) } == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the size of the terminal window.
+    pub fn window_size(&self) -> io::Result<WindowSize> {
+        let mut size = MaybeUninit::<libc::winsize>::uninit();
+        if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCGWINSZ, size.as_mut_ptr()) } == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(WindowSize::from_raw(unsafe { size.assume_init() }))
+        }
+    }
+}
+
+/// Returns the path of the slave device of master `fd`.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn slave_path(fd: RawFd) -> io::Result<PathBuf> {
+    let mut buf = [0u8; 64];
+    match unsafe { libc::ptsname_r(fd, buf.as_mut_ptr().cast(), buf.len()) } {
+        0 => Ok(path_from_cstr(unsafe { CStr::from_ptr(buf.as_ptr().cast()) })),
+        err => Err(io::Error::from_raw_os_error(err)),
+    }
+}
+
+/// Returns the path of the slave device of master `fd`.
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+fn slave_path(fd: RawFd) -> io::Result<PathBuf> {
+    use std::sync::Mutex;
+
+    // `ptsname` returns a pointer to a static buffer.
+    static LOCK: Mutex<()> = Mutex::new(());
+    let _guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let name = unsafe { libc::ptsname(fd) };
+    if name.is_null() {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(path_from_cstr(unsafe { CStr::from_ptr(name) }))
+    }
+}
+
+fn path_from_cstr(path: &CStr) -> PathBuf {
+    PathBuf::from(OsStr::from_bytes(path.to_bytes()))
+}
+
+impl Evented for Pty {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
+    }
+}
+
+impl AsFd for Pty {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.master.as_fd()
+    }
+}
+
+impl AsRawFd for Pty {
+    fn as_raw_fd(&self) -> RawFd {
+        self.master.as_raw_fd()
+    }
+}
+
+impl Read for Pty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        hang_up_to_eof(self.master.read(buf))
+    }
+
+    #[cfg(feature = "nightly")]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        hang_up_to_eof(self.master.read_vectored(bufs))
+    }
+}
+
+/// Linux returns `EIO` when reading from a master of which the slave is
+/// closed, convert this into an end of file.
+fn hang_up_to_eof(result: io::Result<usize>) -> io::Result<usize> {
+    match result {
+        Err(ref err) if err.raw_os_error() == Some(libc::EIO) => Ok(0),
+        result => result,
+    }
+}
+
+impl Write for Pty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.master.write(buf)
+    }
+
+    #[cfg(feature = "nightly")]
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.master.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.master.flush()
+    }
+}
@@ -6,19 +6,21 @@ use crate::os::signals::SignalSet;
 mod signalfd {
     use std::fs::File;
     use std::io::{self, Read};
-    use std::os::unix::io::FromRawFd;
+    use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
     use std::{mem, slice};
 
     use super::{block_signals, create_sigset};
     use crate::event;
-    use crate::os::signals::{Signal, SignalSet};
-    use crate::os::{Interests, RegisterOption};
-    use crate::sys::Selector;
+    use crate::os::signals::{Signal, SignalInfo, SignalSet};
+    use crate::os::{Interests, RegisterOption, Selector};
+    use crate::sys::replace_fd;
 
     /// Signaler backed by `signalfd`.
     #[derive(Debug)]
     pub struct Signals {
         fd: File,
+        signals: SignalSet,
+        id: event::Id,
     }
 
     impl Signals {
@@ -27,22 +29,36 @@ mod signalfd {
             let set = create_sigset(signals)?;
 
             // Create a new signal file descriptor.
-            let fd = unsafe { libc::signalfd(-1, &set, libc::SFD_CLOEXEC | libc::SFD_NONBLOCK) };
-            if fd == -1 {
-                return Err(io::Error::last_os_error());
-            }
+            let fd = new_signalfd(&set)?;
 
             // Register the signalfd, only then block the signals and return our
             // struct.
             selector.register(fd, id, Interests::READABLE, RegisterOption::LEVEL)
                 .and_then(|()| block_signals(set))
-                .map(|()| Signals { fd: unsafe { File::from_raw_fd(fd) } })
+                .map(|()| Signals { fd: unsafe { File::from_raw_fd(fd) }, signals, id })
+        }
+
+        /// Replace the signalfd shared with the parent process with a new one,
+        /// keeping the file descriptor number the same. The signal mask is
+        /// inherited by the child process, so the signals are still blocked.
+        pub fn after_fork_child(&mut self, selector: &Selector) -> io::Result<()> {
+            let fd = self.fd.as_raw_fd();
+            // The registration is copied to the new selector, but it refers
+            // to the file shared with the parent process.
+            match selector.deregister(fd) {
+                Ok(()) => {},
+                Err(ref err) if err.raw_os_error() == Some(libc::ENOENT) => {},
+                Err(err) => return Err(err),
+            }
+            let set = create_sigset(self.signals)?;
+            replace_fd(new_signalfd(&set)?, fd)?;
+            selector.register(fd, self.id, Interests::READABLE, RegisterOption::LEVEL)
         }
 
-        pub fn receive(&mut self) -> io::Result<Option<Signal>> {
-            let mut info: libc::signalfd_siginfo = unsafe { mem::uninitialized() };
+        pub fn receive(&mut self) -> io::Result<Option<SignalInfo>> {
+            let mut info: libc::signalfd_siginfo = unsafe { mem::zeroed() };
             #[allow(trivial_casts)]
-            let info_ref: &mut [u8] = unsafe { slice::from_raw_parts_mut(&mut info as *mut _ as *mut u8, mem::size_of::<libc::signalfd_siginfo>()) };
+            let info_ref: &mut [u8] = unsafe { slice::from_raw_parts_mut(&mut info as *mut _ as *mut u8, size_of::<libc::signalfd_siginfo>()) };
             let n = loop {
                 match self.fd.read(info_ref) {
                     Ok(n) => break n,
@@ -51,8 +67,18 @@ mod signalfd {
                     Err(err) => return Err(err),
                 }
             };
-            assert_eq!(n, mem::size_of::<libc::signalfd_siginfo>());
-            Ok(Signal::from_raw(info.ssi_signo as libc::c_int))
+            assert_eq!(n, size_of::<libc::signalfd_siginfo>());
+            Ok(Signal::from_raw(info.ssi_signo as libc::c_int)
+                .map(|signal| SignalInfo::from_signalfd(signal, &info)))
+        }
+    }
+
+    fn new_signalfd(set: &libc::sigset_t) -> io::Result<RawFd> {
+        let fd = unsafe { libc::signalfd(-1, set, libc::SFD_CLOEXEC | libc::SFD_NONBLOCK) };
+        if fd == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(fd)
         }
     }
 }
@@ -63,27 +89,30 @@ pub use self::signalfd::Signals;
 #[cfg(any(target_os = "freebsd", target_os = "macos",
           target_os = "netbsd", target_os = "openbsd"))]
 mod kqueue {
+    use std::mem::MaybeUninit;
     use std::os::unix::io::AsRawFd;
     use std::{io, mem, ptr};
 
     use super::{block_signals, create_sigset};
     use crate::event;
-    use crate::os::signals::{Signal, SignalSet};
-    use crate::os::{Interests, RegisterOption};
-    use crate::sys::Selector;
+    use crate::os::signals::{Signal, SignalInfo, SignalSet};
+    use crate::os::{Interests, RegisterOption, Selector};
+    use crate::sys;
 
     /// Signaler backed by kqueue (`EVFILT_SIGNAL`).
     #[derive(Debug)]
     pub struct Signals {
         // Separate from the associated kqueue.
-        kq: Selector,
+        kq: sys::Selector,
+        signals: SignalSet,
+        id: event::Id,
     }
 
     impl Signals {
         pub fn new(selector: &Selector, signals: SignalSet, id: event::Id) -> io::Result<Signals> {
             // Create a new kqueue.
             let set = create_sigset(signals)?;
-            let kq = Selector::new()?;
+            let kq = sys::Selector::new()?;
 
             // Next register signals with our new kqueue.
             kq.register_signals(id, signals)
@@ -93,24 +122,35 @@ mod kqueue {
                     Interests::READABLE, RegisterOption::LEVEL))
                 // Once all setup is done block the signals.
                 .and_then(|()| block_signals(set))
-                .map(|()| Signals { kq })
+                .map(|()| Signals { kq, signals, id })
+        }
+
+        /// Create a new kqueue and register it with the new kqueue of
+        /// `selector`.
+        pub fn after_fork_child(&mut self, selector: &Selector) -> io::Result<()> {
+            let signals = Signals::new(selector, self.signals, self.id)?;
+            // kqueues aren't inherited by child processes, so the file
+            // descriptor is already closed (and might be reused).
+            mem::forget(mem::replace(self, signals));
+            Ok(())
         }
 
-        pub fn receive(&mut self) -> io::Result<Option<Signal>> {
-            let mut kevent: libc::kevent = unsafe { mem::uninitialized() };
+        pub fn receive(&mut self) -> io::Result<Option<SignalInfo>> {
+            let mut kevent = MaybeUninit::<libc::kevent>::uninit();
             let timeout = libc::timespec { tv_sec: 0, tv_nsec: 0 };
 
             let n_events = unsafe {
                 libc::kevent(self.kq.as_raw_fd(), ptr::null(), 0,
-                    &mut kevent, 1, &timeout)
+                    kevent.as_mut_ptr(), 1, &timeout)
             };
             match n_events {
                 -1 => Err(io::Error::last_os_error()),
                 0 => Ok(None), // No signals.
                 1 => {
+                    let kevent = unsafe { kevent.assume_init() };
                     let filter = kevent.filter;
                     assert_eq!(filter, libc::EVFILT_SIGNAL);
-                    Ok(Signal::from_raw(kevent.ident as libc::c_int))
+                    Ok(Signal::from_raw(kevent.ident as libc::c_int).map(SignalInfo::new))
                 },
                 _ => unreachable!(),
             }
@@ -122,9 +162,253 @@ mod kqueue {
           target_os = "netbsd", target_os = "openbsd"))]
 pub use self::kqueue::Signals;
 
+mod handler {
+    use std::io::{self, Read};
+    use std::os::unix::io::{AsRawFd, IntoRawFd};
+    use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+    use std::{mem, ptr, thread};
+
+    use log::error;
+
+    use super::create_sigset;
+    use crate::event;
+    use crate::os::signals::{Signal, SignalInfo, SignalSet};
+    use crate::os::{Interests, RegisterOption, Selector};
+    use crate::sys::pipe::{new_pipe, Receiver, Sender};
+    use crate::sys::replace_fd;
+
+    /// Maximum number of signals, the size of `SignalSet`.
+    const MAX_SIGNALS: usize = 128;
+
+    /// Sending end of the pipe for each signal, or -1 if no handler is
+    /// installed. Indexed by raw signal number minus one.
+    static PIPES: [AtomicI32; MAX_SIGNALS] = [const { AtomicI32::new(-1) }; MAX_SIGNALS];
+
+    /// Whether or not a signal was received since the last call to `receive`.
+    /// Indexed by raw signal number minus one.
+    static PENDING: [AtomicBool; MAX_SIGNALS] = [const { AtomicBool::new(false) }; MAX_SIGNALS];
+
+    /// Number of signal handlers currently using a pipe from `PIPES`. Used to
+    /// not close a pipe while a handler could still write to it.
+    static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+    /// Signaler backed by a signal handler writing to a pipe.
+    #[derive(Debug)]
+    pub struct SignalHandler {
+        receiver: Receiver,
+        sender: Sender,
+        signals: SignalSet,
+        id: event::Id,
+        /// Signal dispositions before the handler was installed.
+        old_actions: Vec<(libc::c_int, SigAction)>,
+        /// Signal mask of the thread that installed the handler, before the
+        /// signals were unblocked.
+        old_mask: SigSet,
+        /// Thread that installed the handler, only that thread's signal mask
+        /// is restored.
+        thread: Thread,
+    }
+
+    impl SignalHandler {
+        pub fn new(selector: &Selector, signals: SignalSet, id: event::Id) -> io::Result<SignalHandler> {
+            let (sender, receiver) = new_pipe()?;
+            selector.register(receiver.as_raw_fd(), id, Interests::READABLE, RegisterOption::LEVEL)?;
+
+            // Unblock the signals in this thread, if they're blocked the
+            // handler is never called when all other threads block them as
+            // well.
+            let set = create_sigset(signals)?;
+            let mut old_mask: libc::sigset_t = unsafe { mem::zeroed() };
+            match unsafe { libc::pthread_sigmask(libc::SIG_UNBLOCK, &set, &mut old_mask) } {
+                0 => {},
+                err => return Err(io::Error::from_raw_os_error(err)),
+            }
+
+            let mut handler = SignalHandler {
+                receiver, sender, signals, id,
+                old_actions: Vec::with_capacity(signals.size()),
+                old_mask: SigSet(old_mask),
+                thread: Thread(unsafe { libc::pthread_self() }),
+            };
+
+            // On error `Drop` will uninstall the handlers installed so far and
+            // restore the signal mask.
+            let fd = handler.sender.as_raw_fd();
+            for signal in signals {
+                let raw_signal = signal.into_raw();
+                let pipe = &PIPES[raw_signal as usize - 1];
+                if pipe.compare_exchange(-1, fd, Ordering::AcqRel, Ordering::Acquire).is_err() {
+                    return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                        format!("signal handler for {:?} already installed", signal)));
+                }
+                PENDING[raw_signal as usize - 1].store(false, Ordering::Relaxed);
+
+                let mut action: libc::sigaction = unsafe { mem::zeroed() };
+                let handle: extern "C" fn(libc::c_int) = handle_signal;
+                action.sa_sigaction = handle as libc::sighandler_t;
+                action.sa_flags = libc::SA_RESTART;
+                // Don't nest signal handlers.
+                let _ = unsafe { libc::sigfillset(&mut action.sa_mask) };
+                let mut old_action: libc::sigaction = unsafe { mem::zeroed() };
+                if unsafe { libc::sigaction(raw_signal, &action, &mut old_action) } == -1 {
+                    pipe.store(-1, Ordering::Release);
+                    return Err(io::Error::last_os_error());
+                }
+                handler.old_actions.push((raw_signal, SigAction(old_action)));
+            }
+            Ok(handler)
+        }
+
+        /// Replace the pipe shared with the parent process with a new one,
+        /// keeping the file descriptor numbers the same. The signal handlers
+        /// are inherited by the child process.
+        pub fn after_fork_child(&mut self, selector: &Selector) -> io::Result<()> {
+            let fd = self.receiver.as_raw_fd();
+            match selector.deregister(fd) {
+                Ok(()) => {},
+                Err(ref err) if err.raw_os_error() == Some(libc::ENOENT) => {},
+                Err(err) => return Err(err),
+            }
+            // The signal handler uses the same file descriptor number, so it
+            // doesn't need to be updated.
+            let (sender, receiver) = new_pipe()?;
+            replace_fd(receiver.into_raw_fd(), fd)?;
+            replace_fd(sender.into_raw_fd(), self.sender.as_raw_fd())?;
+            selector.register(fd, self.id, Interests::READABLE, RegisterOption::LEVEL)
+        }
+
+        pub fn receive(&mut self) -> io::Result<Option<SignalInfo>> {
+            if let Some(signal) = self.take_pending() {
+                return Ok(Some(SignalInfo::new(signal)));
+            }
+
+            // Only empty the pipe once all pending signals are received, to
+            // keep receiving (level triggered) events until then.
+            let mut buf = [0; 64];
+            loop {
+                match self.receiver.read(&mut buf) {
+                    Ok(n) if n == buf.len() => continue,
+                    Ok(_) => break,
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err),
+                }
+            }
+
+            // A signal could have been received while emptying the pipe.
+            Ok(self.take_pending().map(SignalInfo::new))
+        }
+
+        /// Returns a pending signal, if any, marking it as no longer pending.
+        fn take_pending(&self) -> Option<Signal> {
+            self.signals.into_iter()
+                .find(|signal| PENDING[signal.into_raw() as usize - 1].swap(false, Ordering::AcqRel))
+        }
+    }
+
+    impl Drop for SignalHandler {
+        fn drop(&mut self) {
+            for (raw_signal, old_action) in self.old_actions.drain(..) {
+                if unsafe { libc::sigaction(raw_signal, &old_action.0, ptr::null_mut()) } == -1 {
+                    error!("unable to restore signal disposition: {}", io::Error::last_os_error());
+                }
+                PIPES[raw_signal as usize - 1].store(-1, Ordering::SeqCst);
+            }
+            // A handler running on another thread could have loaded the file
+            // descriptor before it was removed above, wait for it to finish
+            // before the pipe is closed (when the fields are dropped).
+            while ACTIVE.load(Ordering::SeqCst) != 0 {
+                thread::yield_now();
+            }
+
+            // The signal mask is per thread, restoring it on another thread
+            // would change that thread's mask.
+            if unsafe { libc::pthread_equal(self.thread.0, libc::pthread_self()) } != 0 {
+                match unsafe { libc::pthread_sigmask(libc::SIG_SETMASK, &self.old_mask.0, ptr::null_mut()) } {
+                    0 => {},
+                    err => error!("unable to restore signal mask: {}", io::Error::from_raw_os_error(err)),
+                }
+            }
+        }
+    }
+
+    /// Signal handler, must be async-signal-safe.
+    extern "C" fn handle_signal(raw_signal: libc::c_int) {
+        // Don't overwrite `errno` for the interrupted code.
+        let errno = unsafe { *errno_location() };
+        if let Some(index) = (raw_signal as usize).checked_sub(1).filter(|index| *index < MAX_SIGNALS) {
+            PENDING[index].store(true, Ordering::Release);
+            // Mark the handler as active before loading the pipe, see the
+            // `Drop` implementation of `SignalHandler`.
+            let _ = ACTIVE.fetch_add(1, Ordering::SeqCst);
+            let fd = PIPES[index].load(Ordering::SeqCst);
+            if fd != -1 {
+                // If the pipe is full it's already readable, so we can ignore
+                // the error.
+                let byte = raw_signal as u8;
+                #[allow(trivial_casts)]
+                let _ = unsafe { libc::write(fd, &byte as *const u8 as *const libc::c_void, 1) };
+            }
+            let _ = ACTIVE.fetch_sub(1, Ordering::SeqCst);
+        }
+        unsafe { *errno_location() = errno };
+    }
+
+    #[cfg(target_os = "linux")]
+    unsafe fn errno_location() -> *mut libc::c_int {
+        libc::__errno_location()
+    }
+
+    #[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+    unsafe fn errno_location() -> *mut libc::c_int {
+        libc::__errno()
+    }
+
+    #[cfg(any(target_os = "freebsd", target_os = "macos"))]
+    unsafe fn errno_location() -> *mut libc::c_int {
+        libc::__error()
+    }
+
+    /// Wrapper around `libc::sigaction` to implement `Debug`.
+    struct SigAction(libc::sigaction);
+
+    impl std::fmt::Debug for SigAction {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.debug_struct("sigaction").field("sa_sigaction", &self.0.sa_sigaction)
+                .field("sa_flags", &self.0.sa_flags).finish()
+        }
+    }
+
+    /// Wrapper around `libc::pthread_t` to implement `Debug`, `Send` and
+    /// `Sync`.
+    struct Thread(libc::pthread_t);
+
+    // `pthread_t` is a pointer on some platforms, but it's only compared to
+    // the current thread, never dereferenced.
+    unsafe impl Send for Thread {}
+    unsafe impl Sync for Thread {}
+
+    impl std::fmt::Debug for Thread {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("pthread_t")
+        }
+    }
+
+    /// Wrapper around `libc::sigset_t` to implement `Debug`.
+    struct SigSet(libc::sigset_t);
+
+    impl std::fmt::Debug for SigSet {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("sigset_t")
+        }
+    }
+}
+
+pub use self::handler::SignalHandler;
+
 /// Create a `libc::sigset_t` from `SignalSet`.
 fn create_sigset(signals: SignalSet) -> io::Result<libc::sigset_t> {
-    let mut set: libc::sigset_t = unsafe { mem::uninitialized() };
+    let mut set: libc::sigset_t = unsafe { mem::zeroed() };
     if unsafe { libc::sigemptyset(&mut set) } == -1 {
         return Err(io::Error::last_os_error());
     }
@@ -136,6 +420,24 @@ fn create_sigset(signals: SignalSet) -> io::Result<libc::sigset_t> {
     Ok(set)
 }
 
+/// Returns the signal mask of the current thread, without the signals in
+/// `signals`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn sigmask_without(signals: SignalSet) -> io::Result<libc::sigset_t> {
+    let mut set: libc::sigset_t = unsafe { mem::zeroed() };
+    // `pthread_sigmask` returns the error, rather than setting `errno`.
+    match unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), &mut set) } {
+        0 => {},
+        err => return Err(io::Error::from_raw_os_error(err)),
+    }
+    for signal in signals {
+        if unsafe { libc::sigdelset(&mut set, signal.into_raw()) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(set)
+}
+
 /// Block all signals in `set`.
 fn block_signals(set: libc::sigset_t) -> io::Result<()> {
     if unsafe { libc::sigprocmask(libc::SIG_BLOCK, &set, ptr::null_mut()) } == -1 {
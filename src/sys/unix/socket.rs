@@ -0,0 +1,43 @@
+//! Socket options not supported by the standard library.
+
+use std::io;
+use std::mem::size_of;
+use std::os::unix::io::RawFd;
+
+/// Set the `SO_BUSY_POLL` option, in microseconds.
+pub fn set_busy_poll(fd: RawFd, usecs: u32) -> io::Result<()> {
+    let usecs = usecs.min(libc::c_int::MAX as u32) as libc::c_int;
+    setsockopt(fd, libc::SOL_SOCKET, libc::SO_BUSY_POLL, usecs)
+}
+
+/// Get the `SO_BUSY_POLL` option, in microseconds.
+pub fn busy_poll(fd: RawFd) -> io::Result<u32> {
+    getsockopt(fd, libc::SOL_SOCKET, libc::SO_BUSY_POLL).map(|usecs| usecs as u32)
+}
+
+#[allow(trivial_casts)]
+fn setsockopt(fd: RawFd, level: libc::c_int, name: libc::c_int, value: libc::c_int) -> io::Result<()> {
+    let err = unsafe {
+        libc::setsockopt(fd, level, name, (&value as *const libc::c_int) as *const libc::c_void,
+            size_of::<libc::c_int>() as libc::socklen_t)
+    };
+    if err == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[allow(trivial_casts)]
+fn getsockopt(fd: RawFd, level: libc::c_int, name: libc::c_int) -> io::Result<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = size_of::<libc::c_int>() as libc::socklen_t;
+    let err = unsafe {
+        libc::getsockopt(fd, level, name, (&mut value as *mut libc::c_int) as *mut libc::c_void, &mut len)
+    };
+    if err == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(value)
+    }
+}
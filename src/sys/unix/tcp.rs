@@ -3,11 +3,15 @@ use std::io::{self, Read, Write};
 use std::io::{IoSlice, IoSliceMut};
 use std::mem::size_of_val;
 use std::net::{self, SocketAddr};
-use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
 
 use crate::event;
-use crate::os::{Evented, Interests, OsQueue, RegisterOption};
-use crate::sys::unix::eventedfd::EventedFd;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+
+#[cfg(target_os = "macos")]
+use crate::sys::unix::set_cloexec;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use crate::sys::unix::socket;
 
 #[derive(Debug)]
 pub struct TcpStream {
@@ -21,15 +25,8 @@ impl TcpStream {
             SocketAddr::V4(..) => libc::AF_INET,
             SocketAddr::V6(..) => libc::AF_INET6,
         };
-        let socket_fd = unsafe { libc::socket(socket_family, libc::SOCK_STREAM, 0) };
-        if socket_fd == -1 {
-            return Err(io::Error::last_os_error());
-        }
-
-        // Set non blocking mode.
-        if unsafe { libc::fcntl(socket_fd, libc::F_SETFL, libc::O_NONBLOCK) } == -1 {
-            return Err(io::Error::last_os_error());
-        }
+        let socket_fd = new_socket(socket_family)?;
+        let stream = unsafe { net::TcpStream::from_raw_fd(socket_fd) };
 
         // Connect to the provided address. If this would block it will return
         // `EINPROGRESS`, which we don't consider an error here.
@@ -41,10 +38,18 @@ impl TcpStream {
             }
         }
 
-        let stream = unsafe { net::TcpStream::from_raw_fd(socket_fd) };
         Ok(TcpStream { stream })
     }
 
+    pub fn from_std(stream: net::TcpStream) -> io::Result<TcpStream> {
+        stream.set_nonblocking(true)?;
+        Ok(TcpStream { stream })
+    }
+
+    pub fn into_std(self) -> net::TcpStream {
+        self.stream
+    }
+
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
         self.stream.peer_addr()
     }
@@ -61,6 +66,16 @@ impl TcpStream {
         self.stream.ttl()
     }
 
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn set_busy_poll(&mut self, usecs: u32) -> io::Result<()> {
+        socket::set_busy_poll(self.stream.as_raw_fd(), usecs)
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn busy_poll(&mut self) -> io::Result<u32> {
+        socket::busy_poll(self.stream.as_raw_fd())
+    }
+
     pub fn set_nodelay(&mut self, nodelay: bool) -> io::Result<()> {
         self.stream.set_nodelay(nodelay)
     }
@@ -82,6 +97,36 @@ impl TcpStream {
     }
 }
 
+/// Create a new non-blocking TCP socket, with the close-on-exec flag set.
+fn new_socket(socket_family: libc::c_int) -> io::Result<RawFd> {
+    #[cfg(not(target_os = "macos"))]
+    let socket_type = libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC;
+    // macOS doesn't support setting the flags when creating the socket.
+    #[cfg(target_os = "macos")]
+    let socket_type = libc::SOCK_STREAM;
+
+    let socket_fd = unsafe { libc::socket(socket_family, socket_type, 0) };
+    if socket_fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let result = set_cloexec(socket_fd, true).and_then(|()| {
+            if unsafe { libc::fcntl(socket_fd, libc::F_SETFL, libc::O_NONBLOCK) } == -1 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(err) = result {
+            let _ = unsafe { libc::close(socket_fd) };
+            return Err(err);
+        }
+    }
+    Ok(socket_fd)
+}
+
 // Implementation taken from the Rust standard library.
 // Copyright 2015 The Rust Project Developers.
 #[allow(trivial_casts)]
@@ -123,22 +168,22 @@ impl Write for TcpStream {
 }
 
 impl Evented for TcpStream {
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        EventedFd(&self.as_raw_fd()).register(os_queue, id, interests, opt)
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().register(registrar, id, interests, opt)
     }
 
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        EventedFd(&self.as_raw_fd()).reregister(os_queue, id, interests, opt)
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().reregister(registrar, id, interests, opt)
     }
 
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-        EventedFd(&self.as_raw_fd()).deregister(os_queue)
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
     }
 }
 
-impl Into<net::TcpStream> for TcpStream {
-    fn into(self) -> net::TcpStream {
-        self.stream
+impl AsFd for TcpStream {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.stream.as_fd()
     }
 }
 
@@ -174,10 +219,8 @@ impl TcpListener {
             SocketAddr::V4(..) => libc::AF_INET,
             SocketAddr::V6(..) => libc::AF_INET6,
         };
-        let socket_fd = unsafe { libc::socket(socket_family, libc::SOCK_STREAM, 0) };
-        if socket_fd == -1 {
-            return Err(io::Error::last_os_error());
-        }
+        let socket_fd = new_socket(socket_family)?;
+        let listener = unsafe { net::TcpListener::from_raw_fd(socket_fd) };
 
         // Set the `SO_REUSEPORT` and `SO_REUSEADDR` options.
         unsafe {
@@ -185,11 +228,6 @@ impl TcpListener {
             enable_socket_option(socket_fd, libc::SOL_SOCKET, libc::SO_REUSEADDR)?;
         }
 
-        // Set non blocking mode.
-        if unsafe { libc::fcntl(socket_fd, libc::F_SETFL, libc::O_NONBLOCK) } == -1 {
-            return Err(io::Error::last_os_error());
-        }
-
         // Bind to the address
         let (raw_address, raw_address_length) = raw_address(&address);
         if unsafe { libc::bind(socket_fd, raw_address, raw_address_length) } == -1 {
@@ -201,10 +239,18 @@ impl TcpListener {
             return Err(io::Error::last_os_error());
         }
 
-        let listener = unsafe { net::TcpListener::from_raw_fd(socket_fd) };
         Ok(TcpListener { listener })
     }
 
+    pub fn from_std(listener: net::TcpListener) -> io::Result<TcpListener> {
+        listener.set_nonblocking(true)?;
+        Ok(TcpListener { listener })
+    }
+
+    pub fn into_std(self) -> net::TcpListener {
+        self.listener
+    }
+
     pub fn try_clone(&self) -> io::Result<TcpListener> {
         self.listener.try_clone().map(|listener| TcpListener { listener })
     }
@@ -247,16 +293,22 @@ unsafe fn enable_socket_option(fd: RawFd, level: libc::c_int, name: libc::c_int)
 }
 
 impl Evented for TcpListener {
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        EventedFd(&self.as_raw_fd()).register(os_queue, id, interests, opt)
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().register(registrar, id, interests, opt)
     }
 
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        EventedFd(&self.as_raw_fd()).reregister(os_queue, id, interests, opt)
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().reregister(registrar, id, interests, opt)
     }
 
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-        EventedFd(&self.as_raw_fd()).deregister(os_queue)
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
+    }
+}
+
+impl AsFd for TcpListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.listener.as_fd()
     }
 }
 
@@ -0,0 +1,363 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+#[cfg(feature = "nightly")]
+use std::io::{IoSlice, IoSliceMut};
+use std::mem::MaybeUninit;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::path::PathBuf;
+
+use log::error;
+
+use crate::event;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+use crate::sys::pipe::{Receiver, Sender};
+
+/// Returns a non-blocking handle to the standard input (stdin) of the process.
+///
+/// Setting the non-blocking flag on file descriptor 0 itself would also affect
+/// all other processes sharing the file description, e.g. the parent shell.
+/// Instead the standard input is reopened, which creates a new file
+/// description with its own flags. On Linux all file types except sockets can
+/// be reopened (using `/proc/self/fd`), on other platforms only terminals.
+/// Regular files are not reopened but duplicated, as the non-blocking flag has
+/// no effect on them.
+///
+/// The returned `Receiver` has the close-on-exec flag set.
+pub fn stdin() -> io::Result<Receiver> {
+    reopen(libc::STDIN_FILENO, true).map(Receiver::from)
+}
+
+/// Returns a non-blocking handle to the standard output (stdout) of the
+/// process.
+///
+/// See [`stdin`] for details about how the handle is created.
+///
+/// # Notes
+///
+/// The returned `Sender` doesn't share the buffer of [`std::io::stdout`], make
+/// sure to flush it before writing to the `Sender`.
+pub fn stdout() -> io::Result<Sender> {
+    reopen(libc::STDOUT_FILENO, false).map(Sender::from)
+}
+
+/// Returns a non-blocking handle to the standard error (stderr) of the
+/// process.
+///
+/// See [`stdin`] for details about how the handle is created.
+pub fn stderr() -> io::Result<Sender> {
+    reopen(libc::STDERR_FILENO, false).map(Sender::from)
+}
+
+/// Reopen `fd` in non-blocking mode for reading (`read == true`) or writing.
+fn reopen(fd: RawFd, read: bool) -> io::Result<OwnedFd> {
+    let mut stat = MaybeUninit::<libc::stat>::uninit();
+    if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    if unsafe { stat.assume_init() }.st_mode & libc::S_IFMT == libc::S_IFREG {
+        // Reopening a regular file would reset the file offset and drop flags
+        // such as `O_APPEND`.
+        match unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) } {
+            -1 => Err(io::Error::last_os_error()),
+            fd => Ok(unsafe { OwnedFd::from_raw_fd(fd) }),
+        }
+    } else {
+        OpenOptions::new()
+            .read(read)
+            .write(!read)
+            .custom_flags(libc::O_NONBLOCK | libc::O_NOCTTY)
+            .open(fd_path(fd)?)
+            .map(OwnedFd::from)
+    }
+}
+
+/// Returns the path to reopen `fd`.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn fd_path(fd: RawFd) -> io::Result<PathBuf> {
+    Ok(PathBuf::from(format!("/proc/self/fd/{}", fd)))
+}
+
+/// Returns the path to reopen `fd`, only supported for terminals.
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+fn fd_path(fd: RawFd) -> io::Result<PathBuf> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut buf = [0u8; 256];
+    match unsafe { libc::ttyname_r(fd, buf.as_mut_ptr().cast(), buf.len()) } {
+        0 => {
+            let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+            Ok(PathBuf::from(OsStr::from_bytes(&buf[..len])))
+        },
+        err => Err(io::Error::from_raw_os_error(err)),
+    }
+}
+
+/// Non-blocking handle to a terminal.
+///
+/// [`Tty::open`] opens the controlling terminal of the process (`/dev/tty`).
+/// Because this creates a new file description, putting it in non-blocking
+/// mode doesn't affect the standard streams of the process or of the parent
+/// shell.
+///
+/// The terminal can be switched to raw mode using [`set_raw_mode`], in which
+/// input is available byte by byte, without echoing or line editing. The
+/// original mode is restored when [`restore_mode`] is called or the `Tty` is
+/// dropped.
+///
+/// # Window size
+///
+/// The size of the terminal window is returned by [`window_size`]. When the
+/// window is resized the process receives the [`WindowChange`] signal, which
+/// can be received using [`Signals`], after which `window_size` returns the new
+/// size.
+///
+/// [`set_raw_mode`]: Tty::set_raw_mode
+/// [`restore_mode`]: Tty::restore_mode
+/// [`window_size`]: Tty::window_size
+/// [`WindowChange`]: crate::os::Signal::WindowChange
+/// [`Signals`]: crate::os::Signals
+///
+/// # Deregistering
+///
+/// `Tty` will deregister itself when dropped, **iff** the file descriptor is
+/// not duplicated (via `dup(2)`).
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::{self, Read};
+///
+/// use gaea::os::{OsQueue, RegisterOption, Signal, Signals};
+/// use gaea::unix::Tty;
+/// use gaea::{event, poll};
+///
+/// const TTY_ID: event::Id = event::Id(0);
+/// const SIGNAL_ID: event::Id = event::Id(1);
+///
+/// let mut os_queue = OsQueue::new()?;
+/// let mut events = Vec::new();
+///
+/// let mut tty = Tty::open()?;
+/// tty.set_raw_mode()?;
+/// os_queue.register(&mut tty, TTY_ID, Tty::INTERESTS, RegisterOption::LEVEL)?;
+/// let mut signals = Signals::new(&mut os_queue, Signal::WindowChange.into(), SIGNAL_ID)?;
+///
+/// loop {
+///     poll::<_, io::Error>(&mut [&mut os_queue], &mut events, None)?;
+///     for event in events.drain(..) {
+///         match event.id() {
+///             TTY_ID if event.readiness().is_readable() => {
+///                 let mut buf = [0; 32];
+///                 let n = tty.read(&mut buf)?;
+///                 // Stop when 'q' is pressed.
+///                 if buf[..n].contains(&b'q') {
+///                     // Raw mode is disabled once `tty` is dropped.
+///                     return Ok(());
+///                 }
+///             },
+///             TTY_ID => {},
+///             SIGNAL_ID => while let Some(_) = signals.receive()? {
+///                 let size = tty.window_size()?;
+///                 println!("resized to {}x{}\r", size.columns, size.rows);
+///             },
+///             _ => unreachable!(),
+///         }
+///     }
+/// }
+/// # }
+/// ```
+pub struct Tty {
+    inner: File,
+    /// Terminal attributes to restore, if in raw mode.
+    original: Option<libc::termios>,
+}
+
+impl Tty {
+    /// The interests to use when registering to receive both readable and
+    /// writable events.
+    pub const INTERESTS: Interests = Interests::BOTH;
+
+    /// Open the controlling terminal of the process, in non-blocking mode.
+    ///
+    /// The file descriptor is created with the close-on-exec flag set.
+    pub fn open() -> io::Result<Tty> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/tty")
+            .map(|inner| Tty { inner, original: None })
+    }
+
+    /// Switch the terminal to raw mode (see `cfmakeraw(3)`).
+    ///
+    /// In raw mode input is available byte by byte, characters are not echoed
+    /// and special characters, e.g. Ctrl+C, are not processed. Calling this if
+    /// the terminal is already in raw mode does nothing.
+    pub fn set_raw_mode(&mut self) -> io::Result<()> {
+        if self.original.is_some() {
+            return Ok(());
+        }
+
+        let original = get_attributes(self.inner.as_raw_fd())?;
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        set_attributes(self.inner.as_raw_fd(), &raw)
+            .map(|()| self.original = Some(original))
+    }
+
+    /// Restore the mode of the terminal from before [`set_raw_mode`] was
+    /// called. Does nothing if the terminal is not in raw mode.
+    ///
+    /// [`set_raw_mode`]: Tty::set_raw_mode
+    pub fn restore_mode(&mut self) -> io::Result<()> {
+        match self.original {
+            Some(ref original) => set_attributes(self.inner.as_raw_fd(), original)
+                .map(|()| self.original = None),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `true` if the terminal was switched to raw mode using
+    /// [`set_raw_mode`].
+    ///
+    /// [`set_raw_mode`]: Tty::set_raw_mode
+    pub fn is_raw_mode(&self) -> bool {
+        self.original.is_some()
+    }
+
+    /// Returns the current size of the terminal window.
+    pub fn window_size(&self) -> io::Result<WindowSize> {
+        let mut size = MaybeUninit::<libc::winsize>::uninit();
+        if unsafe { libc::ioctl(self.inner.as_raw_fd(), libc::TIOCGWINSZ, size.as_mut_ptr()) } == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(WindowSize::from_raw(unsafe { size.assume_init() }))
+        }
+    }
+}
+
+/// Get the terminal attributes of `fd`.
+fn get_attributes(fd: RawFd) -> io::Result<libc::termios> {
+    let mut attributes = MaybeUninit::<libc::termios>::uninit();
+    if unsafe { libc::tcgetattr(fd, attributes.as_mut_ptr()) } == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(unsafe { attributes.assume_init() })
+    }
+}
+
+/// Set the terminal attributes of `fd`, effective immediately.
+fn set_attributes(fd: RawFd, attributes: &libc::termios) -> io::Result<()> {
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, attributes) } == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+impl Evented for Tty {
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().register(registrar, id, interests, opt)
+    }
+
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().reregister(registrar, id, interests, opt)
+    }
+
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
+    }
+}
+
+impl AsFd for Tty {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl AsRawFd for Tty {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl From<OwnedFd> for Tty {
+    /// The caller must ensure that the file descriptor is a terminal in
+    /// non-blocking mode.
+    fn from(fd: OwnedFd) -> Tty {
+        Tty { inner: File::from(fd), original: None }
+    }
+}
+
+impl Read for Tty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+
+    #[cfg(feature = "nightly")]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        self.inner.read_vectored(bufs)
+    }
+}
+
+impl Write for Tty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    #[cfg(feature = "nightly")]
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.inner.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl fmt::Debug for Tty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tty")
+            .field("inner", &self.inner)
+            .field("raw_mode", &self.is_raw_mode())
+            .finish()
+    }
+}
+
+impl Drop for Tty {
+    fn drop(&mut self) {
+        if let Err(err) = self.restore_mode() {
+            error!("unable to restore terminal mode: {}", err);
+        }
+    }
+}
+
+/// Size of a terminal window, see [`Tty::window_size`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct WindowSize {
+    /// Number of rows, in characters.
+    pub rows: u16,
+    /// Number of columns, in characters.
+    pub columns: u16,
+}
+
+impl WindowSize {
+    /// Create a new `WindowSize`.
+    pub const fn new(rows: u16, columns: u16) -> WindowSize {
+        WindowSize { rows, columns }
+    }
+
+    pub(crate) const fn from_raw(size: libc::winsize) -> WindowSize {
+        WindowSize { rows: size.ws_row, columns: size.ws_col }
+    }
+
+    pub(crate) const fn into_raw(self) -> libc::winsize {
+        libc::winsize { ws_row: self.rows, ws_col: self.columns, ws_xpixel: 0, ws_ypixel: 0 }
+    }
+}
@@ -1,10 +1,12 @@
 use std::io;
 use std::net::{self, SocketAddr};
-use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
 
 use crate::event;
-use crate::os::{Evented, Interests, OsQueue, RegisterOption};
-use crate::sys::unix::EventedFd;
+use crate::os::{Evented, Interests, RegisterOption, Registrar};
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use crate::sys::unix::socket;
 
 #[derive(Debug)]
 pub struct UdpSocket {
@@ -18,6 +20,15 @@ impl UdpSocket {
         Ok(UdpSocket { socket })
     }
 
+    pub fn from_std(socket: net::UdpSocket) -> io::Result<UdpSocket> {
+        socket.set_nonblocking(true)?;
+        Ok(UdpSocket { socket })
+    }
+
+    pub fn into_std(self) -> net::UdpSocket {
+        self.socket
+    }
+
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         self.socket.local_addr()
     }
@@ -53,19 +64,35 @@ impl UdpSocket {
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
         self.socket.take_error()
     }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn set_busy_poll(&mut self, usecs: u32) -> io::Result<()> {
+        socket::set_busy_poll(self.socket.as_raw_fd(), usecs)
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub fn busy_poll(&mut self) -> io::Result<u32> {
+        socket::busy_poll(self.socket.as_raw_fd())
+    }
 }
 
 impl Evented for UdpSocket {
-    fn register(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        EventedFd(&self.as_raw_fd()).register(os_queue, id, interests, opt)
+    fn register(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().register(registrar, id, interests, opt)
     }
 
-    fn reregister(&mut self, os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
-        EventedFd(&self.as_raw_fd()).reregister(os_queue, id, interests, opt)
+    fn reregister(&mut self, registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+        self.as_fd().reregister(registrar, id, interests, opt)
     }
 
-    fn deregister(&mut self, os_queue: &mut OsQueue) -> io::Result<()> {
-        EventedFd(&self.as_raw_fd()).deregister(os_queue)
+    fn deregister(&mut self, registrar: &Registrar) -> io::Result<()> {
+        self.as_fd().deregister(registrar)
+    }
+}
+
+impl AsFd for UdpSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
     }
 }
 
@@ -2,7 +2,7 @@
 
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
-use std::mem::replace;
+use std::mem::{replace, take};
 use std::time::{Duration, Instant};
 
 use log::trace;
@@ -92,7 +92,7 @@ impl Timers {
             .position(|deadline| deadline.0.id == id);
 
         if let Some(index) = index {
-            let deadlines = replace(&mut self.deadlines, BinaryHeap::new());
+            let deadlines = take(&mut self.deadlines);
             let mut deadlines_vec = deadlines.into_vec();
             let removed_deadline = deadlines_vec.swap_remove(index);
             debug_assert_eq!(removed_deadline.0.id, id, "remove_deadline: removed incorrect deadline");
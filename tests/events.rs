@@ -146,7 +146,7 @@ fn id() {
     let id1 = id.clone();
     assert_eq!(id, id1);
 
-    let max_value = usize::max_value();
+    let max_value = usize::MAX;
     let id = event::Id(max_value);
     assert_eq!(event::Id::from(max_value), event::Id(max_value));
     assert_eq!(usize::from(id), max_value);
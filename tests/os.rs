@@ -1,15 +1,19 @@
-use std::io::{self, Write};
-use std::sync::{Arc, Barrier};
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::{mem, panic, ptr};
+use std::sync::{Arc, Barrier, OnceLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use gaea::event::{self, Capacity, Event, Ready};
-use gaea::os::{Awakener, Evented, Interests, OsQueue, RegisterOption};
-use gaea::unix::new_pipe;
+use gaea::os::{Awakener, DrainChecked, Evented, Interests, OsQueue, PollOutcome, ReadinessCache, RegisterOption, Registrar, RegistrationMismatch, Signal};
+use gaea::poll;
+use gaea::unix::{new_pipe, EventedFd, Receiver, Sender};
 
 mod util;
 
-use self::util::{assert_error, max_timeout, expect_no_events, expect_events, init, init_with_os_queue, EventsCapacity, TIMEOUT_MARGIN};
+use self::util::{assert_error, max_timeout, expect_no_events, expect_events, in_child_process, init, init_with_os_queue, EventsCapacity, TIMEOUT_MARGIN};
 
 struct TestEvented {
     registrations: Vec<(event::Id, Interests, RegisterOption)>,
@@ -28,17 +32,17 @@ impl TestEvented {
 }
 
 impl Evented for TestEvented {
-    fn register(&mut self, _os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+    fn register(&mut self, _registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
         self.registrations.push((id, interests, opt));
         Ok(())
     }
 
-    fn reregister(&mut self, _os_queue: &mut OsQueue, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
+    fn reregister(&mut self, _registrar: &Registrar, id: event::Id, interests: Interests, opt: RegisterOption) -> io::Result<()> {
         self.reregistrations.push((id, interests, opt));
         Ok(())
     }
 
-    fn deregister(&mut self, _os_queue: &mut OsQueue) -> io::Result<()> {
+    fn deregister(&mut self, _registrar: &Registrar) -> io::Result<()> {
         self.deregister_count += 1;
         Ok(())
     }
@@ -56,7 +60,7 @@ fn os_queue_registration() {
     os_queue.register(&mut handle, id, interests, opt)
         .expect("unable to register evented handle");
     assert_eq!(handle.registrations.len(), 1);
-    assert_eq!(handle.registrations.get(0), Some(&(id, interests, opt)));
+    assert_eq!(handle.registrations.first(), Some(&(id, interests, opt)));
     assert!(handle.reregistrations.is_empty());
     assert_eq!(handle.deregister_count, 0);
 
@@ -67,7 +71,7 @@ fn os_queue_registration() {
         .expect("unable to reregister evented handle");
     assert_eq!(handle.registrations.len(), 1);
     assert_eq!(handle.reregistrations.len(), 1);
-    assert_eq!(handle.reregistrations.get(0), Some(&(re_id, re_interests, re_opt)));
+    assert_eq!(handle.reregistrations.first(), Some(&(re_id, re_interests, re_opt)));
     assert_eq!(handle.deregister_count, 0);
 
     os_queue.deregister(&mut handle).expect("unable to reregister evented handle");
@@ -79,16 +83,16 @@ fn os_queue_registration() {
 struct ErroneousTestEvented;
 
 impl Evented for ErroneousTestEvented {
-    fn register(&mut self, _os_queue: &mut OsQueue, _id: event::Id, _interests: Interests, _opt: RegisterOption) -> io::Result<()> {
-        Err(io::Error::new(io::ErrorKind::Other, "register"))
+    fn register(&mut self, _registrar: &Registrar, _id: event::Id, _interests: Interests, _opt: RegisterOption) -> io::Result<()> {
+        Err(io::Error::other("register"))
     }
 
-    fn reregister(&mut self, _os_queue: &mut OsQueue, _id: event::Id, _interests: Interests, _opt: RegisterOption) -> io::Result<()> {
-        Err(io::Error::new(io::ErrorKind::Other, "reregister"))
+    fn reregister(&mut self, _registrar: &Registrar, _id: event::Id, _interests: Interests, _opt: RegisterOption) -> io::Result<()> {
+        Err(io::Error::other("reregister"))
     }
 
-    fn deregister(&mut self, _os_queue: &mut OsQueue) -> io::Result<()> {
-        Err(io::Error::new(io::ErrorKind::Other, "deregister"))
+    fn deregister(&mut self, _registrar: &Registrar) -> io::Result<()> {
+        Err(io::Error::other("deregister"))
     }
 }
 
@@ -106,6 +110,21 @@ fn os_queue_erroneous_registration() {
     assert_error(os_queue.deregister(&mut handle), "deregister");
 }
 
+#[test]
+fn os_queue_max_id() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let (mut sender, _receiver) = new_pipe().expect("unable to create pipe");
+
+    // All ids can be used, including when polling with a timeout that isn't a
+    // whole number of milliseconds.
+    let id = event::Id(usize::MAX);
+    os_queue.register(&mut sender, id, Interests::WRITABLE, RegisterOption::EDGE)
+        .expect("unable to register");
+    poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_micros(1500)))
+        .expect("unable to poll");
+    assert_eq!(events, vec![Event::new(id, Ready::WRITABLE)]);
+}
+
 // NOTE: the `event::Source` implementation is tested more thoroughly in the TCP
 // and UDP tests.
 
@@ -155,6 +174,7 @@ fn queue_events_capacity() {
 
     // Add two more events.
     os_queue.reregister(&mut sender, event::Id(1), Interests::WRITABLE, opt).unwrap();
+    assert_eq!(awakener.drain().unwrap(), 1);
     awakener.wake().unwrap();
 
     let mut events = EventsCapacity(Capacity::Limited(100), 0);
@@ -167,6 +187,7 @@ fn queue_events_capacity() {
     const DATA: &[u8] = b"Hello";
     let n = sender.write(DATA).unwrap();
     assert_eq!(n, DATA.len());
+    assert_eq!(awakener.drain().unwrap(), 1);
     awakener.wake().unwrap();
 
     let mut events = EventsCapacity(Capacity::Growable, 0);
@@ -189,6 +210,7 @@ fn awakener() {
     expect_events(&mut os_queue, &mut events, vec![
         Event::new(event_id, Ready::READABLE),
     ]);
+    assert_eq!(awakener.drain().unwrap(), 1);
 
     // Multiple wakes between polls.
     for _ in 0..3 {
@@ -197,6 +219,7 @@ fn awakener() {
     expect_events(&mut os_queue, &mut events, vec![
         Event::new(event_id, Ready::READABLE),
     ]);
+    assert_eq!(awakener.drain().unwrap(), 3);
 
     // Waking on another thread.
     let awakener1 = awakener.try_clone()
@@ -236,6 +259,7 @@ fn awakener_try_clone() {
     expect_events(&mut os_queue, &mut events, vec![
         Event::new(event_id, Ready::READABLE),
     ]);
+    assert_eq!(awakener.drain().unwrap(), 1);
 
     let handle2 = thread::spawn(move || {
         thread::sleep(Duration::from_millis(500));
@@ -277,6 +301,7 @@ fn awakener_multiple_wakeups() {
     expect_events(&mut os_queue, &mut events, vec![
         Event::new(event_id, Ready::READABLE),
     ]);
+    assert_eq!(awakener.drain().unwrap(), 1);
 
     // Unblock thread 2.
     barrier.wait();
@@ -291,3 +316,1043 @@ fn awakener_multiple_wakeups() {
     handle1.join().unwrap();
     handle2.join().unwrap();
 }
+
+#[test]
+fn awakener_drain() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let event_id = event::Id(10);
+    let awakener = Awakener::new(&mut os_queue, event_id)
+        .expect("unable to create awakener");
+    assert_eq!(awakener.drain().unwrap(), 0);
+
+    // Wakes from all clones are coalesced.
+    let awakener1 = awakener.try_clone()
+        .expect("unable to clone awakener");
+    awakener.wake().expect("unable to wake");
+    awakener1.wake().expect("unable to wake");
+    awakener.wake().expect("unable to wake");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event_id, Ready::READABLE),
+    ]);
+    assert_eq!(awakener.drain().unwrap(), 3);
+    assert_eq!(awakener1.drain().unwrap(), 0);
+    expect_no_events(&mut os_queue);
+
+    // Without draining the next wake doesn't wake the queue again.
+    awakener1.wake().expect("unable to wake");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event_id, Ready::READABLE),
+    ]);
+    awakener1.wake().expect("unable to wake");
+    expect_no_events(&mut os_queue);
+    assert_eq!(awakener.drain().unwrap(), 2);
+
+    // After draining it does.
+    awakener1.wake().expect("unable to wake");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event_id, Ready::READABLE),
+    ]);
+    assert_eq!(awakener1.drain().unwrap(), 1);
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn awakener_multiple_awakeners() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    // Separately created `Awakener`s don't share their state.
+    let awakener1 = Awakener::new(&mut os_queue, event::Id(1))
+        .expect("unable to create awakener");
+    let awakener2 = Awakener::new(&mut os_queue, event::Id(2))
+        .expect("unable to create awakener");
+
+    awakener1.wake().expect("unable to wake");
+    awakener1.wake().expect("unable to wake");
+    awakener2.wake().expect("unable to wake");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(1), Ready::READABLE),
+        Event::new(event::Id(2), Ready::READABLE),
+    ]);
+    assert_eq!(awakener1.drain().unwrap(), 2);
+    assert_eq!(awakener2.drain().unwrap(), 1);
+
+    // Waking one doesn't affect the other.
+    awakener2.wake().expect("unable to wake");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(2), Ready::READABLE),
+    ]);
+    assert_eq!(awakener1.drain().unwrap(), 0);
+    assert_eq!(awakener2.drain().unwrap(), 1);
+}
+
+#[test]
+fn awakener_wake_racing_poll() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    const WAKES: usize = 1000;
+    let event_id = event::Id(10);
+    let awakener = Awakener::new(&mut os_queue, event_id)
+        .expect("unable to create awakener");
+    let awakener1 = awakener.try_clone()
+        .expect("unable to clone awakener");
+    let handle = thread::spawn(move || {
+        for _ in 0..WAKES {
+            awakener1.wake().expect("unable to wake");
+            thread::yield_now();
+        }
+    });
+
+    // No wake up may be lost, no matter how it's interleaved with polling and
+    // draining: every wake is counted once and the queue is always woken
+    // while there are wakes left to drain.
+    let mut count = 0;
+    while count < WAKES {
+        events.clear();
+        poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_secs(1)))
+            .expect("unable to poll");
+        assert!(!events.is_empty(), "lost wake up, only drained {} wakes", count);
+        count += awakener.drain().unwrap();
+    }
+    assert_eq!(count, WAKES);
+
+    handle.join().unwrap();
+}
+
+/// `Awakener` used in `wake_from_signal_handler`.
+static SIGNAL_AWAKENER: OnceLock<Awakener> = OnceLock::new();
+
+#[test]
+fn awakener_wake_from_signal_handler() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    // Must be async-signal-safe, so it can't panic. Any error shows up as a
+    // missing event or wrong wake count below.
+    extern "C" fn wake_from_signal_handler(_: libc::c_int) {
+        if let Some(awakener) = SIGNAL_AWAKENER.get() {
+            let _ = awakener.wake();
+        }
+    }
+
+    /// Restores the previous signal disposition when dropped.
+    struct RestoreAction(libc::c_int, libc::sigaction);
+
+    impl Drop for RestoreAction {
+        fn drop(&mut self) {
+            let res = unsafe { libc::sigaction(self.0, &self.1, ptr::null_mut()) };
+            // Don't panic while panicking.
+            if !thread::panicking() {
+                assert_eq!(res, 0, "unable to restore signal disposition");
+            }
+        }
+    }
+
+    let event_id = event::Id(10);
+    let awakener = Awakener::new(&mut os_queue, event_id)
+        .expect("unable to create awakener");
+    SIGNAL_AWAKENER.set(awakener.try_clone().expect("unable to clone awakener"))
+        .expect("awakener already set");
+
+    let mut action: libc::sigaction = unsafe { mem::zeroed() };
+    let handler: extern "C" fn(libc::c_int) = wake_from_signal_handler;
+    action.sa_sigaction = handler as libc::sighandler_t;
+    let mut old_action: libc::sigaction = unsafe { mem::zeroed() };
+    assert_eq!(unsafe { libc::sigaction(libc::SIGUSR1, &action, &mut old_action) }, 0);
+    let _restore = RestoreAction(libc::SIGUSR1, old_action);
+
+    for _ in 0..2 {
+        assert_eq!(unsafe { libc::pthread_kill(libc::pthread_self(), libc::SIGUSR1) }, 0);
+    }
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event_id, Ready::READABLE),
+    ]);
+    assert_eq!(awakener.drain().unwrap(), 2);
+}
+
+#[test]
+fn registrar_is_send_and_sync() {
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    assert_send::<Registrar>();
+    assert_sync::<Registrar>();
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn registrar_registration() {
+    init();
+    let os_queue = OsQueue::new().expect("unable to create OsQueue");
+    let registrar = os_queue.registrar();
+
+    let (_sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    let fd = receiver.as_raw_fd();
+    registrar.register(&mut receiver, event::Id(0), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register handle");
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(0), Some(Interests::READABLE), RegisterOption::EDGE)));
+
+    registrar.reregister(&mut receiver, event::Id(1), Interests::READABLE, RegisterOption::LEVEL)
+        .expect("unable to reregister handle");
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(1), Some(Interests::READABLE), RegisterOption::LEVEL)));
+
+    registrar.deregister(&mut receiver).expect("unable to deregister handle");
+    assert_eq!(registration_of(&os_queue, fd), None);
+}
+
+#[test]
+fn registrar_evented() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let registrar = os_queue.registrar();
+
+    // Any `Evented` handle can be registered from another thread.
+    let handle = thread::spawn(move || {
+        let mut handle = TestEvented::new();
+        let id = event::Id(0);
+        let interests = Interests::READABLE;
+        let opt = RegisterOption::EDGE;
+        registrar.register(&mut handle, id, interests, opt).expect("unable to register evented handle");
+        registrar.reregister(&mut handle, id, interests, opt).expect("unable to reregister evented handle");
+        registrar.deregister(&mut handle).expect("unable to deregister evented handle");
+        assert_eq!(handle.registrations, vec![(id, interests, opt)]);
+        assert_eq!(handle.reregistrations, vec![(id, interests, opt)]);
+        assert_eq!(handle.deregister_count, 1);
+
+        let (mut sender, receiver) = new_pipe().expect("unable to create pipe");
+        let mut receiver = DrainChecked::new(receiver);
+        registrar.register(&mut receiver, event::Id(1), Interests::READABLE, RegisterOption::EDGE)
+            .expect("unable to register pipe");
+        sender.write_all(b"Hello world").expect("unable to write");
+        (sender, receiver)
+    });
+
+    let (_sender, mut receiver) = handle.join().unwrap();
+    expect_events(&mut os_queue, &mut events, vec![Event::new(event::Id(1), Ready::READABLE)]);
+    drain(&mut receiver);
+    os_queue.deregister(&mut receiver).expect("unable to deregister pipe");
+}
+
+#[test]
+fn registrar_register_while_polling() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let registrar = os_queue.registrar();
+
+    const ID: event::Id = event::Id(0);
+    let barrier = Arc::new(Barrier::new(2));
+    let barrier2 = Arc::clone(&barrier);
+
+    let handle = thread::spawn(move || {
+        let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+        barrier2.wait();
+        // Give the main thread some time to start polling.
+        thread::sleep(Duration::from_millis(50));
+
+        registrar.register(&mut receiver, ID, Interests::READABLE, RegisterOption::EDGE)
+            .expect("unable to register pipe");
+        sender.write_all(b"Hello world").expect("unable to write");
+        (sender, receiver)
+    });
+
+    barrier.wait();
+    // Blocks until the other thread registered and wrote to the pipe.
+    poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_secs(5)))
+        .expect("unable to poll");
+    assert!(events.contains(&Event::new(ID, Ready::READABLE)), "missing event: {:?}", events);
+
+    let (_sender, mut receiver) = handle.join().unwrap();
+    os_queue.registrar().deregister(&mut receiver).expect("unable to deregister pipe");
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn exclusive_register_option_oneshot() {
+    let (mut os_queue, _) = init_with_os_queue();
+    let (_sender, mut receiver) = new_pipe().expect("unable to create pipe");
+
+    let opt = RegisterOption::EXCLUSIVE | RegisterOption::ONESHOT;
+    let err = os_queue.register(&mut receiver, event::Id(0), Interests::READABLE, opt)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn exclusive_register_option_reregister() {
+    let (mut os_queue, _) = init_with_os_queue();
+    let (_sender, mut receiver) = new_pipe().expect("unable to create pipe");
+
+    os_queue.register(&mut receiver, event::Id(0), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register pipe");
+    let opt = RegisterOption::EDGE | RegisterOption::EXCLUSIVE;
+    let err = os_queue.reregister(&mut receiver, event::Id(0), Interests::READABLE, opt)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn exclusive_register_option_wake_up() {
+    init();
+
+    const N: usize = 4;
+    const ID: event::Id = event::Id(0);
+
+    let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    let barrier = Arc::new(Barrier::new(N + 1));
+
+    // Register the same pipe with multiple queues, each polled on its own
+    // thread.
+    let handles: Vec<_> = (0..N).map(|_| {
+        let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+        os_queue.register(&mut receiver, ID, Interests::READABLE, RegisterOption::EDGE | RegisterOption::EXCLUSIVE)
+            .expect("unable to register pipe");
+        let barrier = Arc::clone(&barrier);
+        thread::spawn(move || {
+            let mut events = Vec::new();
+            barrier.wait();
+            poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(500)))
+                .expect("unable to poll");
+            events.len()
+        })
+    }).collect();
+
+    barrier.wait();
+    // Make sure all threads are blocked polling.
+    thread::sleep(Duration::from_millis(100));
+    sender.write_all(b"Hello world").expect("unable to write");
+
+    let woken = handles.into_iter()
+        .map(|handle| handle.join().unwrap())
+        .filter(|n_events| *n_events != 0)
+        .count();
+    // `EPOLLEXCLUSIVE` only guarantees that one or more, not all, queues are
+    // woken.
+    assert!(woken >= 1, "expected at least one queue to be woken");
+    assert!(woken < N, "expected not all queues to be woken, woken: {}", woken);
+}
+
+#[test]
+fn os_queue_with_tracking() {
+    init();
+    let mut os_queue = OsQueue::with_tracking().expect("unable to create OsQueue");
+    let mut events = Vec::new();
+
+    let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    os_queue.register(&mut receiver, event::Id(0), Interests::READABLE, RegisterOption::LEVEL)
+        .expect("unable to register pipe");
+
+    // Double registration is still reported as an error by the OS.
+    assert!(os_queue.register(&mut receiver, event::Id(1), Interests::READABLE, RegisterOption::LEVEL).is_err());
+
+    sender.write_all(b"Hello world").expect("unable to write");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(0), Ready::READABLE),
+    ]);
+
+    os_queue.reregister(&mut receiver, event::Id(2), Interests::READABLE, RegisterOption::LEVEL)
+        .expect("unable to reregister pipe");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(2), Ready::READABLE),
+    ]);
+
+    os_queue.deregister(&mut receiver).expect("unable to deregister pipe");
+    expect_no_events(&mut os_queue);
+}
+
+/// Returns the single registration of `fd` in `os_queue`, as `(id, interests,
+/// opt)`, or `None` if `fd` isn't registered.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn registration_of(os_queue: &OsQueue, fd: RawFd) -> Option<(event::Id, Option<Interests>, RegisterOption)> {
+    let registrations = os_queue.registrations().expect("unable to get registrations");
+    let mut registrations = registrations.iter().filter(|registration| registration.fd() == fd);
+    let registration = registrations.next()?;
+    assert!(registrations.next().is_none(), "multiple registrations for fd");
+    Some((registration.id(), registration.interests(), registration.opt()))
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn registered() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let (stream, mut peer) = UnixStream::pair().expect("unable to create socket pair");
+    stream.set_nonblocking(true).unwrap();
+    let stream = EventedFd::new(OwnedFd::from(stream));
+    let fd = stream.as_raw_fd();
+    let mut registered = os_queue.register_owned(stream, event::Id(0), Interests::READABLE, RegisterOption::ONESHOT)
+        .expect("unable to register handle");
+    assert_eq!(registered.id(), event::Id(0));
+    assert_eq!(registered.interests(), Interests::READABLE);
+    assert_eq!(registered.opt(), RegisterOption::ONESHOT);
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(0), Some(Interests::READABLE), RegisterOption::ONESHOT)));
+
+    // After an event the oneshot registration is disabled.
+    peer.write_all(b"Hello").unwrap();
+    expect_events(&mut os_queue, &mut events, vec![Event::new(event::Id(0), Ready::READABLE)]);
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(0), None, RegisterOption::ONESHOT)));
+
+    // Same interests, no need to reregister, so it stays disabled.
+    registered.set_interests(Interests::READABLE).unwrap();
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(0), None, RegisterOption::ONESHOT)));
+
+    // Rearming a oneshot registration requires a reregistration.
+    registered.rearm().unwrap();
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(0), Some(Interests::READABLE), RegisterOption::ONESHOT)));
+
+    registered.set_interests(Interests::WRITABLE).unwrap();
+    assert_eq!(registered.interests(), Interests::WRITABLE);
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(0), Some(Interests::WRITABLE), RegisterOption::ONESHOT)));
+
+    registered.reregister(event::Id(1), Interests::WRITABLE, RegisterOption::EDGE).unwrap();
+    assert_eq!(registered.id(), event::Id(1));
+    assert_eq!(registered.opt(), RegisterOption::EDGE);
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(1), Some(Interests::WRITABLE), RegisterOption::EDGE)));
+
+    // Rearming a non-oneshot registration does nothing.
+    registered.rearm().unwrap();
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(1), Some(Interests::WRITABLE), RegisterOption::EDGE)));
+
+    drop(registered);
+    assert_eq!(registration_of(&os_queue, fd), None);
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn registered_deregister() {
+    let (mut os_queue, _) = init_with_os_queue();
+
+    let (stream, _peer) = UnixStream::pair().expect("unable to create socket pair");
+    let stream = EventedFd::new(OwnedFd::from(stream));
+    let fd = stream.as_raw_fd();
+    let registered = os_queue.register_owned(stream, event::Id(0), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register handle");
+    let mut stream = registered.deregister().map_err(|(_, err)| err).expect("unable to deregister handle");
+    assert_eq!(registration_of(&os_queue, fd), None);
+
+    // Not registered anymore, so it can be registered again.
+    os_queue.register(&mut stream, event::Id(1), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register handle again");
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(1), Some(Interests::READABLE), RegisterOption::EDGE)));
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn registered_exclusive() {
+    let (mut os_queue, _) = init_with_os_queue();
+
+    let (stream, _peer) = UnixStream::pair().expect("unable to create socket pair");
+    let stream = EventedFd::new(OwnedFd::from(stream));
+    let fd = stream.as_raw_fd();
+    let opt = RegisterOption::EDGE | RegisterOption::EXCLUSIVE;
+    let mut registered = os_queue.register_owned(stream, event::Id(0), Interests::READABLE, opt)
+        .expect("unable to register handle");
+
+    // Exclusive registrations can't be modified by the kernel, so these are
+    // deregistered and registered again.
+    registered.set_interests(Interests::WRITABLE).expect("unable to set interests");
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(0), Some(Interests::WRITABLE), opt)));
+    registered.reregister(event::Id(1), Interests::READABLE, RegisterOption::LEVEL)
+        .expect("unable to reregister handle");
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(1), Some(Interests::READABLE), RegisterOption::LEVEL)));
+    registered.reregister(event::Id(2), Interests::READABLE, RegisterOption::EXCLUSIVE)
+        .expect("unable to reregister handle");
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(2), Some(Interests::READABLE), RegisterOption::EXCLUSIVE)));
+
+    // Invalid option, the original registration should be restored.
+    let err = registered.reregister(event::Id(3), Interests::READABLE, RegisterOption::EXCLUSIVE | RegisterOption::ONESHOT)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(registered.id(), event::Id(2));
+    assert_eq!(registration_of(&os_queue, fd), Some((event::Id(2), Some(Interests::READABLE), RegisterOption::EXCLUSIVE)));
+}
+
+#[test]
+fn registered_evented() {
+    let (mut os_queue, _) = init_with_os_queue();
+
+    let mut registered = os_queue.register_owned(TestEvented::new(), event::Id(0), Interests::READABLE, RegisterOption::ONESHOT)
+        .expect("unable to register handle");
+    assert_eq!(registered.registrations, vec![(event::Id(0), Interests::READABLE, RegisterOption::ONESHOT)]);
+
+    registered.rearm().unwrap();
+    assert_eq!(registered.reregistrations, vec![(event::Id(0), Interests::READABLE, RegisterOption::ONESHOT)]);
+
+    let handle = registered.deregister().map_err(|(_, err)| err).expect("unable to deregister handle");
+    assert_eq!(handle.deregister_count, 1);
+}
+
+#[test]
+fn registered_deregister_error() {
+    struct FailingDeregister(usize);
+
+    impl Evented for FailingDeregister {
+        fn register(&mut self, _registrar: &Registrar, _id: event::Id, _interests: Interests, _opt: RegisterOption) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn reregister(&mut self, _registrar: &Registrar, _id: event::Id, _interests: Interests, _opt: RegisterOption) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn deregister(&mut self, _registrar: &Registrar) -> io::Result<()> {
+            self.0 += 1;
+            Err(io::Error::other("deregister"))
+        }
+    }
+
+    let (mut os_queue, _) = init_with_os_queue();
+
+    let registered = os_queue.register_owned(FailingDeregister(0), event::Id(0), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register handle");
+    // The handle is returned along with the error.
+    let (handle, err) = registered.deregister().err().expect("deregistering should fail");
+    assert_eq!(err.to_string(), "deregister");
+    assert_eq!(handle.0, 1);
+}
+
+#[test]
+fn registered_pipe() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let (mut sender, receiver) = new_pipe().expect("unable to create pipe");
+    let mut receiver = os_queue.register_owned(receiver, event::Id(0), Interests::READABLE, RegisterOption::ONESHOT)
+        .expect("unable to register pipe");
+
+    sender.write_all(b"Hello world").expect("unable to write");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(0), Ready::READABLE),
+    ]);
+    // Oneshot, so no more events until rearmed.
+    expect_no_events(&mut os_queue);
+
+    receiver.rearm().expect("unable to rearm");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(0), Ready::READABLE),
+    ]);
+
+    let mut buf = [0; 20];
+    assert_eq!(receiver.read(&mut buf).unwrap(), 11);
+}
+
+#[test]
+fn os_queue_registrations() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    assert!(os_queue.registrations().expect("unable to get registrations").is_empty());
+
+    let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    os_queue.register(&mut receiver, event::Id(0), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register receiver");
+    os_queue.register(&mut sender, event::Id(1), Interests::WRITABLE, RegisterOption::ONESHOT)
+        .expect("unable to register sender");
+
+    let mut registrations = os_queue.registrations().expect("unable to get registrations");
+    registrations.sort_by_key(|registration| registration.id());
+    assert_eq!(registrations.len(), 2);
+    assert_eq!(registrations[0].fd(), receiver.as_raw_fd());
+    assert_eq!(registrations[0].id(), event::Id(0));
+    assert_eq!(registrations[0].interests(), Some(Interests::READABLE));
+    assert_eq!(registrations[0].opt(), RegisterOption::EDGE);
+    assert_eq!(registrations[1].fd(), sender.as_raw_fd());
+    assert_eq!(registrations[1].id(), event::Id(1));
+    assert_eq!(registrations[1].interests(), Some(Interests::WRITABLE));
+    assert_eq!(registrations[1].opt(), RegisterOption::ONESHOT);
+
+    // After the oneshot registration triggers it's disabled.
+    let mut events = Vec::new();
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(1), Ready::WRITABLE),
+    ]);
+    let registrations = os_queue.registrations().expect("unable to get registrations");
+    let registration = registrations.iter().find(|r| r.id() == event::Id(1)).unwrap();
+    assert_eq!(registration.interests(), None);
+    assert_eq!(registration.opt(), RegisterOption::ONESHOT);
+
+    os_queue.deregister(&mut receiver).expect("unable to deregister receiver");
+    os_queue.deregister(&mut sender).expect("unable to deregister sender");
+    assert!(os_queue.registrations().expect("unable to get registrations").is_empty());
+}
+
+#[test]
+fn os_queue_check_registrations() {
+    init();
+    let os_queue = OsQueue::new().expect("unable to create OsQueue");
+    assert_error(os_queue.check_registrations(), "registration tracking not enabled");
+
+    let mut os_queue = OsQueue::with_tracking().expect("unable to create OsQueue");
+    let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    os_queue.register(&mut receiver, event::Id(0), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register receiver");
+    os_queue.register(&mut sender, event::Id(1), Interests::WRITABLE, RegisterOption::ONESHOT)
+        .expect("unable to register sender");
+    assert_eq!(os_queue.check_registrations().unwrap(), vec![]);
+
+    // Disabled oneshot registrations are not a mismatch.
+    let mut events = Vec::new();
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(1), Ready::WRITABLE),
+    ]);
+    assert_eq!(os_queue.check_registrations().unwrap(), vec![]);
+
+    // Dropping the receiver without deregistering it leaves a stale
+    // registration.
+    let receiver_fd = receiver.as_raw_fd();
+    drop(receiver);
+    match os_queue.check_registrations().unwrap().as_slice() {
+        [RegistrationMismatch::Stale(registration)] => {
+            assert_eq!(registration.fd(), receiver_fd);
+            assert_eq!(registration.id(), event::Id(0));
+        },
+        mismatches => panic!("unexpected mismatches: {:?}", mismatches),
+    }
+}
+
+#[test]
+fn os_queue_sub_millisecond_timeout() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    let mut events = Vec::new();
+
+    for timeout in &[Duration::from_micros(1), Duration::from_micros(500), Duration::from_micros(1500)] {
+        let start = Instant::now();
+        poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(*timeout))
+            .expect("unable to poll");
+        // Timeouts must be rounded up, never down.
+        assert!(start.elapsed() >= *timeout, "poll returned before the timeout: {:?}", timeout);
+        assert!(events.is_empty());
+    }
+}
+
+#[test]
+fn os_queue_blocking_poll_with_sigmask() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    let mut events = Vec::new();
+
+    extern "C" fn noop_handler(_: libc::c_int) {}
+
+    // Install a signal handler and block the signal for this thread.
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    action.sa_sigaction = noop_handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    let mut old_action: libc::sigaction = unsafe { std::mem::zeroed() };
+    assert_eq!(unsafe { libc::sigaction(libc::SIGQUIT, &action, &mut old_action) }, 0);
+    let mut set: libc::sigset_t = unsafe { std::mem::zeroed() };
+    let mut old_set: libc::sigset_t = unsafe { std::mem::zeroed() };
+    unsafe {
+        assert_eq!(libc::sigemptyset(&mut set), 0);
+        assert_eq!(libc::sigaddset(&mut set, libc::SIGQUIT), 0);
+        assert_eq!(libc::pthread_sigmask(libc::SIG_BLOCK, &set, &mut old_set), 0);
+        // Signal is pending until unblocked.
+        assert_eq!(libc::pthread_kill(libc::pthread_self(), libc::SIGQUIT), 0);
+    }
+
+    let timeout = Some(Duration::from_millis(10));
+    // Signal isn't unblocked, so it stays pending.
+    let outcome = os_queue.blocking_poll_with_sigmask(&mut events, timeout, Signal::Interrupt.into())
+        .expect("unable to poll");
+    assert_eq!(outcome, PollOutcome::Completed);
+    // Unblocking it interrupts the poll, also using a sub-millisecond timeout.
+    let outcome = os_queue.blocking_poll_with_sigmask(&mut events, Some(Duration::from_micros(10_500)), Signal::Quit.into())
+        .expect("unable to poll");
+    assert_eq!(outcome, PollOutcome::Interrupted);
+    // The signal is handled.
+    let outcome = os_queue.blocking_poll_with_sigmask(&mut events, timeout, Signal::Quit.into())
+        .expect("unable to poll");
+    assert_eq!(outcome, PollOutcome::Completed);
+    assert!(events.is_empty());
+
+    // The signal should be blocked again.
+    let mut current_set: libc::sigset_t = unsafe { std::mem::zeroed() };
+    unsafe {
+        assert_eq!(libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), &mut current_set), 0);
+        assert_eq!(libc::sigismember(&current_set, libc::SIGQUIT), 1);
+        assert_eq!(libc::pthread_sigmask(libc::SIG_SETMASK, &old_set, ptr::null_mut()), 0);
+        assert_eq!(libc::sigaction(libc::SIGQUIT, &old_action, ptr::null_mut()), 0);
+    }
+}
+
+#[test]
+fn os_queue_busy_poll() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    let mut events = Vec::new();
+    assert_eq!(os_queue.busy_poll_stats(), Default::default());
+
+    let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    os_queue.register(&mut receiver, event::Id(0), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register receiver");
+
+    os_queue.set_busy_poll(Some(Duration::from_millis(100)));
+
+    // Events are found while spinning.
+    sender.write_all(b"Hello").expect("unable to write");
+    poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_secs(1)))
+        .expect("unable to poll");
+    assert_eq!(events, vec![Event::new(event::Id(0), Ready::READABLE)]);
+    let stats = os_queue.busy_poll_stats();
+    assert_eq!(stats.hits(), 1);
+    assert_eq!(stats.misses(), 0);
+    assert!(stats.spins() >= 1);
+
+    // Spinning is limited by the timeout.
+    events.clear();
+    let timeout = Duration::from_millis(10);
+    let start = Instant::now();
+    poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(timeout))
+        .expect("unable to poll");
+    assert!(start.elapsed() >= timeout);
+    assert!(events.is_empty());
+    let stats = os_queue.busy_poll_stats();
+    assert_eq!(stats.hits(), 1);
+    assert_eq!(stats.misses(), 1);
+
+    // Events are found after spinning, while blocking.
+    os_queue.set_busy_poll(Some(Duration::from_millis(1)));
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        sender.write_all(b"Hello").expect("unable to write");
+        // Return the sender to not close it.
+        sender
+    });
+    poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_secs(1)))
+        .expect("unable to poll");
+    assert_eq!(events, vec![Event::new(event::Id(0), Ready::READABLE)]);
+    let stats = os_queue.busy_poll_stats();
+    assert_eq!(stats.hits(), 1);
+    assert_eq!(stats.misses(), 2);
+    let _sender = handle.join().unwrap();
+
+    // Disabling busy polling doesn't spin.
+    os_queue.set_busy_poll(None);
+    poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(1)))
+        .expect("unable to poll");
+    assert_eq!(os_queue.busy_poll_stats(), stats);
+}
+
+#[test]
+fn os_queue_set_busy_poll_params() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    match os_queue.set_busy_poll_params(10, 8, true) {
+        Ok(()) => {},
+        // Not supported by kernels before 6.9.
+        Err(ref err) if err.raw_os_error() == Some(libc::ENOTTY) => {},
+        Err(err) => panic!("unexpected error setting busy poll params: {}", err),
+    }
+}
+
+/// Create `n` pipes, registering the sending ends with `os_queue` using
+/// edge-triggered writable interests, so each returns a single event.
+fn register_writable_pipes(os_queue: &mut OsQueue, n: usize) -> Vec<(Sender, Receiver)> {
+    (0..n).map(|id| {
+        let (mut sender, receiver) = new_pipe().expect("unable to create pipe");
+        os_queue.register(&mut sender, event::Id(id), Interests::WRITABLE, RegisterOption::EDGE)
+            .expect("unable to register pipe");
+        (sender, receiver)
+    }).collect()
+}
+
+#[test]
+fn os_queue_with_capacity() {
+    init();
+    let mut os_queue = OsQueue::with_capacity(4).expect("unable to create OsQueue");
+    let _pipes = register_writable_pipes(&mut os_queue, 20);
+
+    // The buffer is filled, so it grows: 4, 8, 16.
+    let mut events = Vec::new();
+    for expected in &[4, 8, 8] {
+        events.clear();
+        poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(0)))
+            .expect("unable to poll");
+        assert_eq!(events.len(), *expected);
+    }
+    expect_no_events(&mut os_queue);
+}
+
+/// Event sink with limited capacity.
+struct LimitedSink(Vec<Event>, usize);
+
+impl event::Sink for LimitedSink {
+    fn capacity_left(&self) -> Capacity {
+        Capacity::Limited(self.1 - self.0.len())
+    }
+
+    fn add(&mut self, event: Event) {
+        assert!(self.0.len() < self.1, "added too many events");
+        self.0.push(event);
+    }
+}
+
+#[test]
+fn os_queue_fill_sink() {
+    init();
+    let mut os_queue = OsQueue::with_capacity(4).expect("unable to create OsQueue");
+    os_queue.set_fill_sink(true);
+    let _pipes = register_writable_pipes(&mut os_queue, 20);
+
+    // Limited by the capacity of the sink.
+    let mut events = LimitedSink(Vec::new(), 6);
+    event::Source::<_, io::Error>::poll(&mut os_queue, &mut events).unwrap();
+    assert_eq!(events.0.len(), 6);
+
+    // Retrieves all events.
+    let mut events = Vec::new();
+    poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(0)))
+        .expect("unable to poll");
+    assert_eq!(events.len(), 14);
+    events.sort_by_key(|event| event.id());
+    events.dedup();
+    assert_eq!(events.len(), 14);
+    expect_no_events(&mut os_queue);
+}
+
+/// Read from `receiver` until it returns `WouldBlock`.
+fn drain<R: Read>(receiver: &mut R) {
+    let mut buf = [0; 64];
+    loop {
+        match receiver.read(&mut buf) {
+            Ok(0) => panic!("unexpected end of stream"),
+            Ok(_) => continue,
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+            Err(err) => panic!("unexpected error reading: {}", err),
+        }
+    }
+}
+
+#[test]
+fn drain_checked() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    let mut events = Vec::new();
+
+    let (mut sender, receiver) = new_pipe().expect("unable to create pipe");
+    let mut receiver = DrainChecked::panicking(receiver);
+    os_queue.register(&mut receiver, event::Id(0), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register receiver");
+
+    sender.write_all(b"Hello").expect("unable to write");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(0), Ready::READABLE),
+    ]);
+
+    // Reading, but not until `WouldBlock`, isn't enough.
+    let mut buf = [0; 2];
+    assert_eq!(receiver.read(&mut buf).unwrap(), 2);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _ = poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(0)));
+    }));
+    let err = result.expect_err("expected a panic");
+    let msg = err.downcast_ref::<String>().expect("unexpected panic message");
+    assert!(msg.contains("id 0 received readable readiness"), "unexpected panic message: {}", msg);
+
+    // Only reported once.
+    expect_no_events(&mut os_queue);
+
+    // Draining the receiver is fine.
+    sender.write_all(b"Hello").expect("unable to write");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(0), Ready::READABLE),
+    ]);
+    drain(&mut receiver);
+    expect_no_events(&mut os_queue);
+
+    // Deregistered handles aren't checked.
+    sender.write_all(b"Hello").expect("unable to write");
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(event::Id(0), Ready::READABLE),
+    ]);
+    os_queue.deregister(&mut receiver).expect("unable to deregister receiver");
+    expect_no_events(&mut os_queue);
+}
+
+#[test]
+fn drain_checked_level_triggered() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    let mut events = Vec::new();
+
+    let (sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    let mut sender = DrainChecked::panicking(sender);
+    os_queue.register(&mut sender, event::Id(0), Interests::WRITABLE, RegisterOption::LEVEL)
+        .expect("unable to register sender");
+
+    // Level-triggered handles don't have to be drained.
+    for _ in 0..2 {
+        expect_events(&mut os_queue, &mut events, vec![
+            Event::new(event::Id(0), Ready::WRITABLE),
+        ]);
+        sender.write_all(b"Hello").expect("unable to write");
+    }
+    drain(&mut receiver);
+}
+
+#[test]
+fn readiness_cache() {
+    init();
+    let mut cache = ReadinessCache::new();
+    let id = event::Id(0);
+    assert!(!cache.is_ready(id, Ready::READABLE));
+    assert_eq!(cache.readiness(id), Ready::EMPTY);
+
+    cache.set_ready(id, Ready::READABLE);
+    cache.set_ready(id, Ready::WRITABLE);
+    assert!(cache.is_ready(id, Ready::READABLE | Ready::WRITABLE));
+    cache.clear(id, Ready::READABLE);
+    assert!(!cache.is_ready(id, Ready::READABLE));
+    assert!(cache.is_ready(id, Ready::WRITABLE));
+    cache.remove(id);
+    assert_eq!(cache.readiness(id), Ready::EMPTY);
+}
+
+#[test]
+fn readiness_cache_sink() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    let mut cache = ReadinessCache::new();
+    let mut events = Vec::new();
+
+    let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    os_queue.register(&mut receiver, event::Id(0), Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register receiver");
+    os_queue.register(&mut sender, event::Id(1), Interests::WRITABLE, RegisterOption::EDGE)
+        .expect("unable to register sender");
+
+    sender.write_all(b"Hello").expect("unable to write");
+    poll::<_, io::Error>(&mut [&mut os_queue], &mut cache.sink(&mut events), Some(Duration::from_millis(100)))
+        .expect("unable to poll");
+    events.sort_by_key(|event| event.id());
+    assert_eq!(events, vec![
+        Event::new(event::Id(0), Ready::READABLE),
+        Event::new(event::Id(1), Ready::WRITABLE),
+    ]);
+    assert!(cache.is_ready(event::Id(0), Ready::READABLE));
+    assert!(cache.is_ready(event::Id(1), Ready::WRITABLE));
+
+    // Polling again returns no events, but the cache remembers the readiness.
+    expect_no_events(&mut os_queue);
+    assert!(cache.is_ready(event::Id(0), Ready::READABLE));
+
+    // Reading until `WouldBlock` clears the readable readiness.
+    drain(&mut cache.io(event::Id(0), &mut receiver));
+    assert!(!cache.is_ready(event::Id(0), Ready::READABLE));
+
+    // Writing until `WouldBlock` clears the writable readiness.
+    loop {
+        match cache.io(event::Id(1), &mut sender).write(&[0; 1024]) {
+            Ok(_) => assert!(cache.is_ready(event::Id(1), Ready::WRITABLE)),
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+            Err(err) => panic!("unexpected error writing: {}", err),
+        }
+    }
+    assert!(!cache.is_ready(event::Id(1), Ready::WRITABLE));
+}
+
+#[test]
+fn os_queue_nested() {
+    init();
+    let (mut parent, mut events) = init_with_os_queue();
+    let mut child = OsQueue::new().expect("unable to create OsQueue");
+    assert_ne!(parent.as_raw_fd(), child.as_raw_fd());
+
+    parent.register(&mut child, event::Id(0), OsQueue::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register child queue");
+    expect_no_events(&mut parent);
+
+    let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    child.register(&mut receiver, event::Id(1), Receiver::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register receiver");
+    sender.write_all(b"Hello").expect("unable to write");
+
+    // The parent queue returns a single event for the child queue.
+    expect_events(&mut parent, &mut events, vec![Event::new(event::Id(0), Ready::READABLE)]);
+    expect_events(&mut child, &mut events, vec![Event::new(event::Id(1), Ready::READABLE)]);
+
+    // After draining the child, the parent no longer returns events.
+    drain(&mut receiver);
+    expect_no_events(&mut parent);
+
+    parent.deregister(&mut child).expect("unable to deregister child queue");
+    sender.write_all(b"Hello").expect("unable to write");
+    expect_no_events(&mut parent);
+}
+
+#[test]
+fn os_queue_register_self() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    // A queue can't be registered with itself.
+    let mut fd = unsafe { BorrowedFd::borrow_raw(os_queue.as_raw_fd()) };
+    assert!(os_queue.register(&mut fd, event::Id(0), OsQueue::INTERESTS, RegisterOption::LEVEL).is_err());
+}
+
+#[test]
+fn os_queue_after_fork_child() {
+    init();
+    let mut os_queue = OsQueue::with_tracking().expect("unable to create OsQueue");
+    let mut events = Vec::new();
+    let registrar = os_queue.registrar();
+    let mut awakener = Awakener::new(&mut os_queue, event::Id(0)).expect("unable to create awakener");
+
+    let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    os_queue.register(&mut receiver, event::Id(1), Receiver::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register receiver");
+
+    let ok = in_child_process(|| {
+        // Everything returns an error before the queue is re-initialised.
+        let (_sender2, mut receiver2) = new_pipe()?;
+        assert!(registrar.register(&mut receiver2, event::Id(2), Receiver::INTERESTS, RegisterOption::LEVEL).is_err());
+        assert!(poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(0))).is_err());
+        assert!(awakener.wake().is_err());
+
+        os_queue.after_fork_child()?;
+        awakener.after_fork_child(&mut os_queue)?;
+        assert!(os_queue.check_registrations()?.is_empty());
+
+        // The registrar uses the new queue.
+        registrar.register(&mut receiver2, event::Id(2), Receiver::INTERESTS, RegisterOption::LEVEL)?;
+
+        // Tracked registrations are copied.
+        sender.write_all(b"Hello")?;
+        awakener.wake()?;
+        expect_events(&mut os_queue, &mut events, vec![
+            Event::new(event::Id(0), Ready::READABLE),
+            Event::new(event::Id(1), Ready::READABLE),
+        ]);
+        Ok(())
+    });
+    assert!(ok, "child process failed");
+
+    // The parent isn't affected by the child, except for the data in the
+    // shared pipe.
+    expect_events(&mut os_queue, &mut events, vec![Event::new(event::Id(1), Ready::READABLE)]);
+    drain(&mut receiver);
+    awakener.wake().expect("unable to wake");
+    expect_events(&mut os_queue, &mut events, vec![Event::new(event::Id(0), Ready::READABLE)]);
+}
+
+#[test]
+fn os_queue_after_fork_child_without_tracking() {
+    init();
+    let mut os_queue = OsQueue::new().expect("unable to create OsQueue");
+    let mut events = Vec::new();
+
+    let (mut sender, mut receiver) = new_pipe().expect("unable to create pipe");
+    os_queue.register(&mut receiver, event::Id(1), Receiver::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register receiver");
+    let (_sender2, mut receiver2) = new_pipe().expect("unable to create pipe");
+    os_queue.register(&mut receiver2, event::Id(2), Receiver::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register receiver");
+    os_queue.deregister(&mut receiver2).expect("unable to deregister receiver");
+
+    let ok = in_child_process(|| {
+        os_queue.after_fork_child()?;
+
+        // Registrations are copied, even without tracking. Deregistered
+        // handles are not.
+        sender.write_all(b"Hello")?;
+        expect_events(&mut os_queue, &mut events, vec![Event::new(event::Id(1), Ready::READABLE)]);
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        assert_eq!(os_queue.registrations()?.len(), 1);
+        Ok(())
+    });
+    assert!(ok, "child process failed");
+}
@@ -0,0 +1,120 @@
+use std::io::{self, Read, Write};
+use std::os::unix::io::AsRawFd;
+
+use gaea::event::{self, Event, Ready};
+use gaea::poll;
+use gaea::os::{OsQueue, RegisterOption};
+use gaea::process::{Command, Stdio};
+use gaea::unix::{Receiver, Sender};
+
+mod util;
+
+use self::util::{assert_would_block, expect_events, init_with_os_queue, is_cloexec};
+
+const STDIN_ID: event::Id = event::Id(0);
+const STDOUT_ID: event::Id = event::Id(1);
+const STDERR_ID: event::Id = event::Id(2);
+#[cfg(any(target_os = "android", target_os = "linux"))]
+const CHILD_ID: event::Id = event::Id(3);
+
+/// Poll `os_queue` until the `Receiver` registered with `id` is closed,
+/// returning all data read.
+fn read_all(os_queue: &mut OsQueue, events: &mut Vec<Event>, receiver: &mut Receiver, id: event::Id) -> Vec<u8> {
+    let mut output = Vec::new();
+    loop {
+        events.clear();
+        poll::<_, io::Error>(&mut [&mut *os_queue], events, None).expect("unable to poll");
+        assert!(events.iter().all(|event| event.id() == id));
+        match receiver.read_to_end(&mut output) {
+            Ok(_) => return output,
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+            Err(err) => panic!("unable to read: {}", err),
+        }
+    }
+}
+
+#[test]
+fn process_stdio() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let mut child = Command::new("sh")
+        .args(["-c", "cat; echo error >&2"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("unable to spawn child process");
+
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+    assert!(is_cloexec(stdout.as_raw_fd()));
+
+    os_queue.register(&mut stdin, STDIN_ID, Sender::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register stdin");
+    expect_events(&mut os_queue, &mut events, vec![Event::new(STDIN_ID, Ready::WRITABLE)]);
+    os_queue.deregister(&mut stdin).expect("unable to deregister stdin");
+    stdin.write_all(b"Hello world").expect("unable to write to stdin");
+    drop(stdin);
+
+    os_queue.register(&mut stdout, STDOUT_ID, Receiver::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register stdout");
+    assert_eq!(read_all(&mut os_queue, &mut events, &mut stdout, STDOUT_ID), b"Hello world");
+    os_queue.deregister(&mut stdout).expect("unable to deregister stdout");
+
+    os_queue.register(&mut stderr, STDERR_ID, Receiver::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register stderr");
+    assert_eq!(read_all(&mut os_queue, &mut events, &mut stderr, STDERR_ID), b"error\n");
+
+    assert!(child.wait().expect("unable to wait on child").success());
+}
+
+#[test]
+fn process_non_blocking() {
+    init_with_os_queue();
+
+    let mut child = Command::new("cat")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("unable to spawn child process");
+
+    // No output yet, and the process is still running.
+    let mut buf = [0; 10];
+    assert_would_block(child.stdout.as_mut().unwrap().read(&mut buf));
+    assert!(child.try_wait().expect("unable to wait on child").is_none());
+
+    child.kill().expect("unable to kill child");
+    assert!(!child.wait().expect("unable to wait on child").success());
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn process_exit_notification() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let mut child = Command::new("sh")
+        .args(["-c", "read line; exit 3"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .expect("unable to spawn child process");
+    os_queue.register(&mut child, CHILD_ID, gaea::process::Child::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register child");
+
+    util::expect_no_events(&mut os_queue);
+    assert!(child.try_wait().expect("unable to wait on child").is_none());
+
+    // Closing stdin makes the child process exit.
+    drop(child.stdin.take());
+    expect_events(&mut os_queue, &mut events, vec![Event::new(CHILD_ID, Ready::READABLE)]);
+    let status = child.try_wait().expect("unable to wait on child").expect("child not exited");
+    assert_eq!(status.code(), Some(3));
+}
+
+#[test]
+fn process_spawn_error() {
+    init_with_os_queue();
+
+    let err = Command::new("/non-existing/program").spawn().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
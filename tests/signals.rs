@@ -1,16 +1,24 @@
 use std::io::{self, Read};
 use std::ops::{Deref, DerefMut};
-use std::panic;
+use std::{panic, ptr};
 use std::process::{Child, Command, Stdio};
-use std::thread::sleep;
+use std::thread::{self, sleep};
 use std::time::Duration;
 
-use gaea::event;
-use gaea::os::{Signal, Signals, SignalSet};
+use gaea::event::{self, Event, Ready};
+use gaea::os::{Signal, SignalInfo, Signals, SignalSet};
 
 mod util;
 
-use self::util::init_with_os_queue;
+use self::util::{expect_events, init_with_os_queue};
+
+/// All signals in `SignalSet::all_standard`.
+const STANDARD_SIGNALS: [Signal; 21] = [Signal::Interrupt, Signal::Terminate,
+    Signal::Quit, Signal::Hangup, Signal::Abort, Signal::Alarm, Signal::User1,
+    Signal::User2, Signal::Child, Signal::Continue, Signal::TerminalStop,
+    Signal::TerminalInput, Signal::TerminalOutput, Signal::Pipe, Signal::Urgent,
+    Signal::CpuTimeLimit, Signal::FileSizeLimit, Signal::VirtualAlarm,
+    Signal::Profile, Signal::WindowChange, Signal::Io];
 
 #[test]
 fn signal_bit_or() {
@@ -38,6 +46,7 @@ fn signal_set() {
     let tests = vec![
         (SignalSet::empty(), 0, vec![]),
         (SignalSet::all(), 3, vec![Signal::Interrupt, Signal::Terminate, Signal::Quit]),
+        (SignalSet::all_standard(), STANDARD_SIGNALS.len(), STANDARD_SIGNALS.to_vec()),
         (Signal::Interrupt.into(), 1, vec![Signal::Interrupt]),
         (Signal::Terminate.into(), 1, vec![Signal::Terminate]),
         (Signal::Quit.into(), 1, vec![Signal::Quit]),
@@ -53,7 +62,7 @@ fn signal_set() {
         assert_eq!(set.size(), size);
 
         // Test `contains`.
-        let mut contains_iter = (&expected).iter().cloned();
+        let mut contains_iter = expected.iter().cloned();
         while let Some(signal) = contains_iter.next() {
             assert!(set.contains(signal));
             assert!(set.contains::<SignalSet>(signal.into()));
@@ -78,6 +87,43 @@ fn signal_set() {
     }
 }
 
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn signal_set_realtime() {
+    let set = Signal::Realtime(0) | Signal::Realtime(2) | Signal::Hangup;
+    assert_eq!(set.size(), 3);
+    assert!(set.contains(Signal::Realtime(0)));
+    assert!(set.contains(Signal::Realtime(2)));
+    assert!(!set.contains(Signal::Realtime(1)));
+    assert!(!SignalSet::all_standard().contains(Signal::Realtime(0)));
+
+    let signals: Vec<Signal> = set.into_iter().collect();
+    assert_eq!(signals.len(), 3);
+    assert!(signals.contains(&Signal::Realtime(0)));
+    assert!(signals.contains(&Signal::Realtime(2)));
+    assert!(signals.contains(&Signal::Hangup));
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn signal_realtime() {
+    assert_eq!(Signal::realtime(0), Some(Signal::Realtime(0)));
+    let max = (libc::SIGRTMAX() - libc::SIGRTMIN()) as u8;
+    assert_eq!(Signal::realtime(max), Some(Signal::Realtime(max)));
+    assert_eq!(Signal::realtime(max + 1), None);
+    assert_eq!(Signal::realtime(u8::MAX), None);
+
+    // Creating `Signals` with a signal larger than `SIGRTMAX` fails.
+    let (mut os_queue, _) = init_with_os_queue();
+    for n in [max + 1, u8::MAX] {
+        let set = Signal::Interrupt | Signal::Realtime(n);
+        let err = Signals::new(&mut os_queue, set, event::Id(0)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = Signals::with_handler(&mut os_queue, set, event::Id(0)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
+
 #[test]
 fn signal_set_iter_length() {
     let set = Signal::Interrupt | Signal::Terminate | Signal::Quit;
@@ -98,6 +144,262 @@ fn receive_no_signal() {
     assert_eq!(signals.receive().expect("unable to receive signal"), None);
 }
 
+#[test]
+fn receive_signals() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let id = event::Id(0);
+    let mut signals = Signals::new(&mut os_queue, Signal::User1 | Signal::Hangup | Signal::WindowChange, id)
+        .expect("unable to create Signals");
+
+    // The signals are blocked in this thread only, so the signals must be
+    // send to this thread, rather then the process.
+    raise(libc::SIGUSR1);
+    expect_events(&mut os_queue, &mut events, vec![Event::new(id, Ready::READABLE)]);
+    assert_eq!(signals.receive().unwrap(), Some(Signal::User1));
+    assert_eq!(signals.receive().unwrap(), None);
+
+    raise(libc::SIGHUP);
+    raise(libc::SIGWINCH);
+    let mut received = vec![signals.receive().unwrap().unwrap(), signals.receive().unwrap().unwrap()];
+    received.sort();
+    assert_eq!(received, vec![Signal::Hangup, Signal::WindowChange]);
+    assert_eq!(signals.receive().unwrap(), None);
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn receive_realtime_signals() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let id = event::Id(0);
+    let mut signals = Signals::new(&mut os_queue, Signal::Realtime(1) | Signal::Realtime(3), id)
+        .expect("unable to create Signals");
+
+    raise(libc::SIGRTMIN() + 3);
+    expect_events(&mut os_queue, &mut events, vec![Event::new(id, Ready::READABLE)]);
+    assert_eq!(signals.receive().unwrap(), Some(Signal::Realtime(3)));
+
+    // Real-time signals are queued, rather than merged.
+    raise(libc::SIGRTMIN() + 1);
+    raise(libc::SIGRTMIN() + 1);
+    assert_eq!(signals.receive().unwrap(), Some(Signal::Realtime(1)));
+    assert_eq!(signals.receive().unwrap(), Some(Signal::Realtime(1)));
+    assert_eq!(signals.receive().unwrap(), None);
+}
+
+#[test]
+fn receive_info() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let id = event::Id(0);
+    let mut signals = Signals::new(&mut os_queue, Signal::User2.into(), id)
+        .expect("unable to create Signals");
+    assert_eq!(signals.receive_info().unwrap(), None);
+
+    raise(libc::SIGUSR2);
+    expect_events(&mut os_queue, &mut events, vec![Event::new(id, Ready::READABLE)]);
+    let info: SignalInfo = signals.receive_info().unwrap().expect("no signal received");
+    assert_eq!(info.signal(), Signal::User2);
+    #[cfg(any(target_os = "android", target_os = "linux"))] {
+        assert_eq!(info.pid(), Some(std::process::id()));
+        assert_eq!(info.uid(), Some(unsafe { libc::getuid() }));
+    }
+    assert_eq!(info.status(), None);
+    assert_eq!(info.value_int(), None);
+    assert_eq!(signals.receive_info().unwrap(), None);
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn receive_info_sigqueue() {
+    util::init();
+
+    // `sigqueue` sends a signal to the process, which could be handled by
+    // any thread. In the forked child process there is only a single thread.
+    let ok = util::in_child_process(|| {
+        let mut os_queue = gaea::os::OsQueue::new()?;
+        let mut signals = Signals::new(&mut os_queue, Signal::Realtime(2).into(), event::Id(0))?;
+        let value = libc::sigval { sival_ptr: 123 as *mut libc::c_void };
+        if unsafe { libc::sigqueue(libc::getpid(), libc::SIGRTMIN() + 2, value) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let info = signals.receive_info()?.expect("no signal received");
+        assert_eq!(info.signal(), Signal::Realtime(2));
+        assert_eq!(info.pid(), Some(std::process::id()));
+        assert_eq!(info.value_ptr(), Some(123));
+        assert_eq!(info.status(), None);
+        Ok(())
+    });
+    assert!(ok, "child process failed");
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn receive_info_child() {
+    util::init();
+
+    // `SIGCHLD` is send to the process, so this is also done in a child
+    // process with a single thread.
+    let ok = util::in_child_process(|| {
+        let mut os_queue = gaea::os::OsQueue::new()?;
+        let mut signals = Signals::new(&mut os_queue, Signal::Child.into(), event::Id(0))?;
+        let pid = match unsafe { libc::fork() } {
+            -1 => return Err(io::Error::last_os_error()),
+            0 => unsafe { libc::_exit(3) },
+            pid => pid,
+        };
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+
+        let info = signals.receive_info()?.expect("no signal received");
+        assert_eq!(info.signal(), Signal::Child);
+        assert_eq!(info.pid(), Some(pid as u32));
+        assert_eq!(info.status(), Some(3));
+        assert_eq!(info.value_int(), None);
+        Ok(())
+    });
+    assert!(ok, "child process failed");
+}
+
+#[test]
+fn signal_handler() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let id = event::Id(0);
+    let mut signals = Signals::with_handler(&mut os_queue, Signal::Profile | Signal::VirtualAlarm, id)
+        .expect("unable to create Signals");
+    assert_eq!(signals.receive().unwrap(), None);
+
+    // Signals are send to the process, any thread may run the signal handler.
+    send_signal(std::process::id() as libc::pid_t, libc::SIGPROF);
+    expect_events(&mut os_queue, &mut events, vec![Event::new(id, Ready::READABLE)]);
+    assert_eq!(signals.receive().unwrap(), Some(Signal::Profile));
+    assert_eq!(signals.receive().unwrap(), None);
+
+    send_signal(std::process::id() as libc::pid_t, libc::SIGVTALRM);
+    send_signal(std::process::id() as libc::pid_t, libc::SIGPROF);
+    expect_events(&mut os_queue, &mut events, vec![Event::new(id, Ready::READABLE)]);
+    let mut received = vec![signals.receive().unwrap().unwrap(), signals.receive().unwrap().unwrap()];
+    received.sort();
+    assert_eq!(received, vec![Signal::VirtualAlarm, Signal::Profile]);
+    assert_eq!(signals.receive().unwrap(), None);
+}
+
+#[test]
+fn signal_handler_already_installed() {
+    let (mut os_queue, _) = init_with_os_queue();
+
+    let signals = Signals::with_handler(&mut os_queue, Signal::Urgent.into(), event::Id(0))
+        .expect("unable to create Signals");
+    let err = Signals::with_handler(&mut os_queue, Signal::Io | Signal::Urgent, event::Id(1))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+    // The handler for `Io` should be uninstalled again.
+    drop(Signals::with_handler(&mut os_queue, Signal::Io.into(), event::Id(1))
+        .expect("unable to create Signals"));
+
+    drop(signals);
+    drop(Signals::with_handler(&mut os_queue, Signal::Urgent.into(), event::Id(0))
+        .expect("unable to create Signals"));
+}
+
+#[test]
+fn signal_handler_restore() {
+    let (mut os_queue, _) = init_with_os_queue();
+
+    let mut set: libc::sigset_t = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, libc::SIGTTIN);
+    }
+    assert_eq!(unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, ptr::null_mut()) }, 0);
+    let old_handler = signal_disposition(libc::SIGTTIN);
+
+    let signals = Signals::with_handler(&mut os_queue, Signal::TerminalInput.into(), event::Id(0))
+        .expect("unable to create Signals");
+    assert_ne!(signal_disposition(libc::SIGTTIN), old_handler);
+    assert!(!is_blocked(libc::SIGTTIN));
+
+    drop(signals);
+    assert_eq!(signal_disposition(libc::SIGTTIN), old_handler);
+    assert!(is_blocked(libc::SIGTTIN));
+    assert_eq!(unsafe { libc::pthread_sigmask(libc::SIG_UNBLOCK, &set, ptr::null_mut()) }, 0);
+}
+
+#[test]
+fn signal_handler_drop_on_other_thread() {
+    let (mut os_queue, _) = init_with_os_queue();
+
+    let signals = Signals::with_handler(&mut os_queue, Signal::FileSizeLimit.into(), event::Id(0))
+        .expect("unable to create Signals");
+    assert!(!is_blocked(libc::SIGXFSZ));
+
+    thread::spawn(move || {
+        let mut set: libc::sigset_t = unsafe { std::mem::zeroed() };
+        unsafe {
+            libc::sigemptyset(&mut set);
+            libc::sigaddset(&mut set, libc::SIGXFSZ);
+        }
+        assert_eq!(unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, ptr::null_mut()) }, 0);
+
+        // Only the signal mask of the thread that created `Signals` is
+        // restored.
+        drop(signals);
+        assert!(is_blocked(libc::SIGXFSZ));
+    }).join().expect("thread panicked");
+}
+
+#[test]
+fn signal_handler_after_fork_child() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let id = event::Id(0);
+    let mut signals = Signals::with_handler(&mut os_queue, Signal::CpuTimeLimit.into(), id)
+        .expect("unable to create Signals");
+
+    let ok = util::in_child_process(|| {
+        assert!(signals.receive().is_err());
+        os_queue.after_fork_child()?;
+        signals.after_fork_child(&mut os_queue)?;
+
+        if unsafe { libc::kill(libc::getpid(), libc::SIGXCPU) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        expect_events(&mut os_queue, &mut events, vec![Event::new(id, Ready::READABLE)]);
+        assert_eq!(signals.receive()?, Some(Signal::CpuTimeLimit));
+        Ok(())
+    });
+    assert!(ok, "child process failed");
+
+    // The parent shouldn't have received the signal send in the child.
+    assert_eq!(signals.receive().unwrap(), None);
+}
+
+/// Returns the current disposition of `signal`.
+fn signal_disposition(signal: libc::c_int) -> libc::sighandler_t {
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    assert_eq!(unsafe { libc::sigaction(signal, ptr::null(), &mut action) }, 0);
+    action.sa_sigaction
+}
+
+/// Returns true if `signal` is blocked in the current thread.
+fn is_blocked(signal: libc::c_int) -> bool {
+    let mut set: libc::sigset_t = unsafe { std::mem::zeroed() };
+    assert_eq!(unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), &mut set) }, 0);
+    unsafe { libc::sigismember(&set, signal) == 1 }
+}
+
+/// Send `signal` to the current thread.
+fn raise(signal: libc::c_int) {
+    match unsafe { libc::pthread_kill(libc::pthread_self(), signal) } {
+        0 => {},
+        err => panic!("error sending signal: {}", io::Error::from_raw_os_error(err)),
+    }
+}
+
 #[test]
 fn signals_example() {
     let child = run_example("signals");
@@ -154,7 +456,7 @@ fn run_example(name: &'static str) -> ChildCommand {
 /// Build the example with the given name.
 fn build_example(name: &'static str) {
     let output = Command::new("cargo")
-        .args(&["build", "--example", name])
+        .args(["build", "--example", name])
         .output()
         .expect("unable to build example");
 
@@ -11,7 +11,7 @@ use gaea::{event, poll};
 
 mod util;
 
-use self::util::{any_local_address, any_local_ipv6_address, assert_would_block, expect_events, init, init_with_os_queue};
+use self::util::{any_local_address, any_local_ipv6_address, assert_would_block, expect_events, init, init_with_os_queue, is_cloexec};
 
 const ID1: event::Id = event::Id(0);
 const ID2: event::Id = event::Id(1);
@@ -128,6 +128,50 @@ fn tcp_listener_try_clone_same_os_queue() {
     thread_handle.join().expect("unable to join thread");
 }
 
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn tcp_listener_try_clone_exclusive() {
+    init();
+
+    const N: usize = 4;
+
+    let mut listener = TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+    let barrier = Arc::new(Barrier::new(N + 1));
+
+    // Each worker thread has its own queue and clone of the listener.
+    let handles: Vec<_> = (0..N).map(|_| {
+        let mut listener = listener.try_clone().expect("unable to clone TCP listener");
+        let mut os_queue = OsQueue::new().unwrap();
+        os_queue.register(&mut listener, ID1, TcpListener::INTERESTS, RegisterOption::EDGE | RegisterOption::EXCLUSIVE)
+            .expect("unable to register listener");
+        let barrier = Arc::clone(&barrier);
+        thread::spawn(move || {
+            let mut events = Vec::new();
+            barrier.wait();
+            poll::<_, io::Error>(&mut [&mut os_queue], &mut events, Some(Duration::from_millis(500)))
+                .expect("unable to poll");
+            drop(listener);
+            events.len()
+        })
+    }).collect();
+
+    barrier.wait();
+    // Make sure all threads are blocked polling.
+    sleep(Duration::from_millis(100));
+    let stream = net::TcpStream::connect(address).unwrap();
+
+    let woken = handles.into_iter()
+        .map(|handle| handle.join().unwrap())
+        .filter(|n_events| *n_events != 0)
+        .count();
+    // `EPOLLEXCLUSIVE` only guarantees that one or more, not all, workers are
+    // woken.
+    assert!(woken >= 1, "expected at least one worker to be woken");
+    assert!(woken < N, "expected not all workers to be woken, woken: {}", woken);
+    drop(stream);
+}
+
 #[test]
 fn tcp_listener_try_clone_different_os_queue() {
     let (mut os_queue1, mut events) = init_with_os_queue();
@@ -511,3 +555,18 @@ fn start_connections(listener: &mut TcpListener, n_connections: usize, barrier:
         }
     })
 }
+
+#[test]
+fn tcp_listener_inheritable() {
+    init();
+    let mut socket = TcpListener::bind(any_local_address()).unwrap();
+    assert!(is_cloexec(socket.as_raw_fd()));
+    socket.set_inheritable(true).unwrap();
+    assert!(!is_cloexec(socket.as_raw_fd()));
+    socket.set_inheritable(false).unwrap();
+    assert!(is_cloexec(socket.as_raw_fd()));
+
+    let fd = socket.into_inheritable().unwrap();
+    assert!(!is_cloexec(fd));
+    drop(unsafe { net::TcpListener::from_raw_fd(fd) });
+}
@@ -13,7 +13,7 @@ use gaea::{event, poll};
 
 mod util;
 
-use self::util::{any_local_address, any_local_ipv6_address, assert_would_block, expect_events, init, init_with_os_queue};
+use self::util::{any_local_address, any_local_ipv6_address, assert_would_block, expect_events, init, init_with_os_queue, is_cloexec};
 
 /// Data used in reading and writing tests.
 const DATA: &[u8] = b"Hello world!";
@@ -134,6 +134,23 @@ fn tcp_stream_nodelay() {
     thread_handle.join().expect("unable to join thread");
 }
 
+#[test]
+fn tcp_stream_busy_poll() {
+    init();
+
+    let (thread_handle, address) = start_listener(1, None);
+
+    let mut stream = TcpStream::connect(address).unwrap();
+
+    // Increasing the value requires `CAP_NET_ADMIN`, so we can only test
+    // setting it to zero.
+    stream.set_busy_poll(0).unwrap();
+    assert_eq!(stream.busy_poll().unwrap(), 0);
+    assert!(stream.take_error().unwrap().is_none());
+
+    thread_handle.join().expect("unable to join thread");
+}
+
 #[test]
 fn tcp_stream_peek() {
     let (mut os_queue, mut events) = init_with_os_queue();
@@ -669,3 +686,36 @@ fn start_listener(n_connections: usize, barrier: Option<Arc<Barrier>>) -> (threa
     });
     (thread_handle, receiver.recv().unwrap())
 }
+
+#[test]
+fn tcp_stream_inheritable() {
+    init();
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    assert!(is_cloexec(stream.as_raw_fd()));
+    stream.set_inheritable(true).unwrap();
+    assert!(!is_cloexec(stream.as_raw_fd()));
+    stream.set_inheritable(false).unwrap();
+    assert!(is_cloexec(stream.as_raw_fd()));
+
+    let fd = stream.into_inheritable().unwrap();
+    assert!(!is_cloexec(fd));
+    drop(unsafe { net::TcpStream::from_raw_fd(fd) });
+}
+
+#[test]
+fn tcp_stream_std() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let stream = net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let mut stream = TcpStream::from_std(stream).unwrap();
+    os_queue.register(&mut stream, ID1, TcpStream::INTERESTS, RegisterOption::LEVEL).unwrap();
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID1, Ready::WRITABLE)]);
+
+    // The stream is in non-blocking mode.
+    let mut buf = [0; 20];
+    assert_would_block(stream.read(&mut buf));
+    let mut stream = stream.into_std();
+    assert_would_block(stream.read(&mut buf));
+}
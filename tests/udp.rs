@@ -12,7 +12,7 @@ use gaea::{event, poll};
 
 mod util;
 
-use self::util::{any_local_address, any_local_ipv6_address, assert_error, assert_would_block, expect_events, init, init_with_os_queue};
+use self::util::{any_local_address, any_local_ipv6_address, assert_error, assert_would_block, expect_events, init, init_with_os_queue, is_cloexec};
 
 const DATA1: &[u8] = b"Hello world!";
 const DATA2: &[u8] = b"Hello mars!";
@@ -80,6 +80,19 @@ fn udp_socket() {
     assert!(socket2.take_error().unwrap().is_none());
 }
 
+#[test]
+fn udp_socket_busy_poll() {
+    init();
+
+    let mut socket = UdpSocket::bind(any_local_address()).unwrap();
+
+    // Increasing the value requires `CAP_NET_ADMIN`, so we can only test
+    // setting it to zero.
+    socket.set_busy_poll(0).unwrap();
+    assert_eq!(socket.busy_poll().unwrap(), 0);
+    assert!(socket.take_error().unwrap().is_none());
+}
+
 #[test]
 fn udp_socket_ipv6() {
     let (mut os_queue, mut events) = init_with_os_queue();
@@ -641,3 +654,37 @@ fn send_packets(address: SocketAddr, n_packets: usize, barrier: Arc<Barrier>) ->
         }
     })
 }
+
+#[test]
+fn udp_socket_inheritable() {
+    init();
+    let mut socket = UdpSocket::bind(any_local_address()).unwrap();
+    assert!(is_cloexec(socket.as_raw_fd()));
+    socket.set_inheritable(true).unwrap();
+    assert!(!is_cloexec(socket.as_raw_fd()));
+    socket.set_inheritable(false).unwrap();
+    assert!(is_cloexec(socket.as_raw_fd()));
+
+    let fd = socket.into_inheritable().unwrap();
+    assert!(!is_cloexec(fd));
+    drop(unsafe { net::UdpSocket::from_raw_fd(fd) });
+}
+
+#[test]
+fn udp_socket_std() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let socket = net::UdpSocket::bind(any_local_address()).unwrap();
+    let mut socket = UdpSocket::from_std(socket).unwrap();
+    let address = socket.local_addr().unwrap();
+    os_queue.register(&mut socket, ID1, UdpSocket::INTERESTS, RegisterOption::LEVEL).unwrap();
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID1, Ready::WRITABLE)]);
+
+    // The socket is in non-blocking mode.
+    let mut buf = [0; 20];
+    assert_would_block(socket.recv_from(&mut buf));
+
+    let socket = socket.into_std();
+    assert_eq!(socket.local_addr().unwrap(), address);
+    assert_would_block(socket.recv_from(&mut buf));
+}
@@ -0,0 +1,112 @@
+#![cfg(any(target_os = "android", target_os = "linux"))]
+
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::thread;
+
+use gaea::event::{self, Event, Ready};
+use gaea::os::{Interests, RegisterOption};
+use gaea::unix::EventFd;
+
+mod util;
+
+use self::util::{assert_would_block, expect_events, expect_no_events, init, init_with_os_queue, is_cloexec};
+
+const ID: event::Id = event::Id(0);
+
+#[test]
+fn eventfd_counter() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let mut counter = EventFd::new(0).expect("unable to create eventfd");
+    os_queue.register(&mut counter, ID, EventFd::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register eventfd");
+
+    // Empty counter is only writable.
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::WRITABLE)]);
+    assert_would_block(counter.read_count());
+
+    counter.add(1).expect("unable to add");
+    counter.add(10).expect("unable to add");
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE | Ready::WRITABLE)]);
+    assert_eq!(counter.read_count().unwrap(), 11);
+    assert_would_block(counter.read_count());
+
+    // Initial value.
+    let mut counter = EventFd::new(5).expect("unable to create eventfd");
+    assert_eq!(counter.read_count().unwrap(), 5);
+}
+
+#[test]
+fn eventfd_overflow() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let mut counter = EventFd::new(0).expect("unable to create eventfd");
+    os_queue.register(&mut counter, ID, EventFd::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register eventfd");
+
+    // The maximum value of the counter is `u64::MAX - 1`.
+    counter.add(u64::MAX - 1).expect("unable to add");
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    assert_would_block(counter.add(1));
+    assert_eq!(counter.add(u64::MAX).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+    assert_eq!(counter.read_count().unwrap(), u64::MAX - 1);
+    counter.add(1).expect("unable to add");
+}
+
+#[test]
+fn eventfd_semaphore() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let mut semaphore = EventFd::new_semaphore(2).expect("unable to create eventfd");
+    os_queue.register(&mut semaphore, ID, EventFd::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register eventfd");
+
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE | Ready::WRITABLE)]);
+    assert_eq!(semaphore.read_count().unwrap(), 1);
+    assert_eq!(semaphore.read_count().unwrap(), 1);
+    assert_would_block(semaphore.read_count());
+
+    semaphore.add(3).expect("unable to add");
+    for _ in 0..3 {
+        assert_eq!(semaphore.read_count().unwrap(), 1);
+    }
+    assert_would_block(semaphore.read_count());
+}
+
+#[test]
+fn eventfd_try_clone() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let mut counter = EventFd::new(0).expect("unable to create eventfd");
+    os_queue.register(&mut counter, ID, Interests::READABLE, RegisterOption::EDGE)
+        .expect("unable to register eventfd");
+
+    let handles: Vec<_> = (0..4).map(|_| {
+        let mut counter = counter.try_clone().expect("unable to clone eventfd");
+        thread::spawn(move || counter.add(2).expect("unable to add"))
+    }).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    assert_eq!(counter.read_count().unwrap(), 8);
+    expect_no_events(&mut os_queue);
+}
+
+#[test]
+fn eventfd_owned_fd() {
+    init();
+
+    let counter = EventFd::new(3).expect("unable to create eventfd");
+    assert!(is_cloexec(counter.as_raw_fd()));
+    let fd = OwnedFd::from(counter);
+    let mut counter = EventFd::from(fd);
+    assert_eq!(counter.read_count().unwrap(), 3);
+
+    let fd = counter.into_inheritable().expect("unable to make eventfd inheritable");
+    assert!(!is_cloexec(fd));
+    drop(unsafe { EventFd::from_raw_fd(fd) });
+}
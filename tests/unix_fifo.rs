@@ -0,0 +1,112 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+
+use gaea::event::{self, Event, Ready};
+use gaea::os::RegisterOption;
+use gaea::unix::{Fifo, Receiver};
+
+mod util;
+
+use self::util::{assert_would_block, expect_events, expect_no_events, init, init_with_os_queue, is_cloexec};
+
+const ID: event::Id = event::Id(0);
+
+/// Returns a unique path for `name` in the temporary directory.
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("gaea_{}_{}", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn fifo_create() {
+    init();
+    let path = temp_path("fifo_create");
+
+    let fifo = Fifo::create(&path, 0o600).expect("unable to create FIFO");
+    assert_eq!(fifo.path(), path);
+    assert!(fs::metadata(&path).unwrap().file_type().is_fifo());
+
+    let err = Fifo::create(&path, 0o600).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn fifo_not_a_fifo() {
+    init();
+    let path = temp_path("fifo_not_a_fifo");
+    drop(File::create(&path).unwrap());
+
+    let fifo = Fifo::new(&path);
+    assert_eq!(fifo.open_receiver().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(fifo.open_sender().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn fifo_sender_without_receiver() {
+    init();
+    let path = temp_path("fifo_sender_without_receiver");
+    let fifo = Fifo::create(&path, 0o600).expect("unable to create FIFO");
+
+    assert_eq!(fifo.open_sender().unwrap_err().kind(), io::ErrorKind::NotConnected);
+
+    // Once a receiver is open it should work.
+    let receiver = fifo.open_receiver().expect("unable to open receiver");
+    let sender = fifo.open_sender().expect("unable to open sender");
+    assert!(is_cloexec(receiver.as_raw_fd()));
+    assert!(is_cloexec(sender.as_raw_fd()));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn fifo_wait_for_writer() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let path = temp_path("fifo_wait_for_writer");
+    let fifo = Fifo::create(&path, 0o600).expect("unable to create FIFO");
+
+    let mut receiver = fifo.open_receiver().expect("unable to open receiver");
+    os_queue.register(&mut receiver, ID, Receiver::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register receiver");
+    // No writer yet, so no events.
+    expect_no_events(&mut os_queue);
+    let mut buf = [0; 20];
+    assert_eq!(receiver.read(&mut buf).unwrap(), 0);
+
+    let mut sender = fifo.open_sender().expect("unable to open sender");
+    expect_no_events(&mut os_queue);
+    assert_would_block(receiver.read(&mut buf));
+
+    sender.write_all(b"Hello world").unwrap();
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    let n = receiver.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"Hello world");
+
+    // Writer is done.
+    drop(sender);
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::HUP)]);
+    assert_eq!(receiver.read(&mut buf).unwrap(), 0);
+
+    // Reopen to wait for the next writer.
+    os_queue.deregister(&mut receiver).expect("unable to deregister receiver");
+    let mut receiver = fifo.open_receiver().expect("unable to open receiver");
+    os_queue.register(&mut receiver, ID, Receiver::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register receiver");
+    expect_no_events(&mut os_queue);
+
+    let mut sender = fifo.open_sender().expect("unable to open sender");
+    sender.write_all(b"Hello again").unwrap();
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    let n = receiver.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"Hello again");
+
+    fs::remove_file(&path).unwrap();
+}
@@ -1,15 +1,16 @@
 use std::io::{Read, Write};
+use std::os::unix::io::{AsFd, AsRawFd, OwnedFd};
 use std::thread::sleep;
 use std::time::Duration;
 
 use gaea::event;
 use gaea::event::{Event, Ready};
 use gaea::os::{Interests, OsQueue, RegisterOption};
-use gaea::unix::{new_pipe, Receiver, Sender};
+use gaea::unix::{new_pipe, EventedFd, Receiver, Sender};
 
 mod util;
 
-use self::util::{expect_events, init, init_with_os_queue};
+use self::util::{expect_events, init, init_with_os_queue, is_cloexec};
 
 const SENDER_ID: event::Id = event::Id(0);
 const RECEIVER_ID: event::Id = event::Id(1);
@@ -69,3 +70,49 @@ fn sender_readable_interests() {
     os_queue.register(&mut sender, SENDER_ID, Interests::READABLE, RegisterOption::LEVEL)
         .unwrap();
 }
+
+#[test]
+fn unix_pipe_inheritable() {
+    init();
+    let (mut sender, receiver) = new_pipe().expect("can't create pipe");
+    assert!(is_cloexec(sender.as_raw_fd()));
+    assert!(is_cloexec(receiver.as_raw_fd()));
+
+    sender.set_inheritable(true).expect("unable to set inheritable");
+    assert!(!is_cloexec(sender.as_raw_fd()));
+    sender.set_inheritable(false).expect("unable to set inheritable");
+    assert!(is_cloexec(sender.as_raw_fd()));
+
+    let fd = receiver.into_inheritable().expect("unable to convert into inheritable");
+    assert!(!is_cloexec(fd));
+    assert_eq!(unsafe { libc::close(fd) }, 0);
+}
+
+#[test]
+fn unix_pipe_owned_fd() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let (sender, receiver) = new_pipe().expect("can't create pipe");
+    let mut sender = Sender::from(OwnedFd::from(sender));
+    // Register using the blanket implementation for `AsFd` types.
+    let mut receiver = EventedFd::new(OwnedFd::from(receiver));
+    os_queue.register(&mut receiver, RECEIVER_ID, Interests::READABLE, RegisterOption::LEVEL)
+        .expect("can't register receiver");
+    os_queue.register(&mut sender.as_fd(), SENDER_ID, Interests::WRITABLE, RegisterOption::LEVEL)
+        .expect("can't register sender");
+
+    assert_eq!(sender.write(DATA).unwrap(), DATA.len());
+    sleep(Duration::from_millis(10));
+    expect_events(&mut os_queue, &mut events, vec![
+        Event::new(SENDER_ID, Ready::WRITABLE),
+        Event::new(RECEIVER_ID, Ready::READABLE),
+    ]);
+
+    let mut buf = [0; 20];
+    assert_eq!(receiver.read(&mut buf).unwrap(), DATA.len());
+    assert_eq!(&buf[..DATA.len()], DATA);
+
+    os_queue.deregister(&mut receiver).expect("can't deregister receiver");
+    let receiver = Receiver::from(receiver.into_inner());
+    assert!(receiver.as_fd().as_raw_fd() >= 0);
+}
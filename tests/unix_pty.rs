@@ -0,0 +1,100 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::AsRawFd;
+
+use gaea::event::{self, Event, Ready};
+use gaea::os::RegisterOption;
+use gaea::unix::{Pty, Tty, WindowSize};
+
+mod util;
+
+use self::util::{assert_would_block, expect_events, init, init_with_os_queue, is_cloexec};
+
+const ID: event::Id = event::Id(0);
+
+#[test]
+fn pty_open() {
+    init();
+
+    let pty = Pty::open().expect("unable to open pty");
+    assert!(is_cloexec(pty.as_raw_fd()));
+    let flags = unsafe { libc::fcntl(pty.as_raw_fd(), libc::F_GETFL) };
+    assert_ne!(flags & libc::O_NONBLOCK, 0);
+    assert!(pty.slave_path().starts_with("/dev"));
+
+    let slave = pty.open_slave().expect("unable to open slave");
+    assert!(is_cloexec(slave.as_raw_fd()));
+    assert_eq!(unsafe { libc::isatty(slave.as_raw_fd()) }, 1);
+    let flags = unsafe { libc::fcntl(slave.as_raw_fd(), libc::F_GETFL) };
+    assert_eq!(flags & libc::O_NONBLOCK, 0);
+
+    // Each call opens a new pair.
+    let pty2 = Pty::open().expect("unable to open pty");
+    assert_ne!(pty.slave_path(), pty2.slave_path());
+}
+
+#[test]
+fn pty_read_write() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let mut pty = Pty::open().expect("unable to open pty");
+    let mut slave = File::from(pty.open_slave().expect("unable to open slave"));
+    os_queue.register(&mut pty, ID, Pty::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register pty");
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::WRITABLE)]);
+    let mut buf = [0; 32];
+    assert_would_block(pty.read(&mut buf));
+
+    // Input for the slave.
+    pty.write_all(b"Hello world\n").expect("unable to write");
+    let n = slave.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"Hello world\n");
+
+    // The input is echoed back.
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    let n = pty.read(&mut buf).expect("unable to read");
+    assert_eq!(&buf[..n], b"Hello world\r\n");
+
+    // Output of the slave.
+    slave.write_all(b"Hello back").unwrap();
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    let n = pty.read(&mut buf).expect("unable to read");
+    assert_eq!(&buf[..n], b"Hello back");
+    assert_would_block(pty.read(&mut buf));
+}
+
+#[test]
+fn pty_hang_up() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+
+    let mut pty = Pty::open().expect("unable to open pty");
+    let mut slave = File::from(pty.open_slave().expect("unable to open slave"));
+    os_queue.register(&mut pty, ID, Pty::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register pty");
+
+    // Data written before closing the slave can still be read.
+    slave.write_all(b"Bye").unwrap();
+    drop(slave);
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE | Ready::HUP)]);
+    let mut output = Vec::new();
+    assert_eq!(pty.read_to_end(&mut output).expect("unable to read"), 3);
+    assert_eq!(output, b"Bye");
+}
+
+#[test]
+fn pty_window_size() {
+    init();
+
+    let mut pty = Pty::open().expect("unable to open pty");
+    let slave = Tty::from(pty.open_slave().expect("unable to open slave"));
+
+    let size = WindowSize::new(24, 80);
+    pty.set_window_size(size).expect("unable to set window size");
+    assert_eq!(pty.window_size().unwrap(), size);
+    assert_eq!(slave.window_size().unwrap(), size);
+
+    let size = WindowSize::new(50, 132);
+    pty.set_window_size(size).expect("unable to set window size");
+    assert_eq!(pty.window_size().unwrap(), size);
+    assert_eq!(slave.window_size().unwrap(), size);
+}
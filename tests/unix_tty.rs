@@ -0,0 +1,190 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+use std::ptr;
+
+use gaea::event::{self, Event, Ready};
+use gaea::os::{Interests, OsQueue, RegisterOption};
+use gaea::unix::{self, new_pipe, Receiver, Tty, WindowSize};
+
+mod util;
+
+use self::util::{assert_would_block, expect_events, in_child_process, init, init_with_os_queue, is_cloexec};
+
+const ID: event::Id = event::Id(0);
+
+/// Open a pseudo-terminal pair, returning the (blocking) master and the slave
+/// as `Tty`.
+fn open_tty() -> (File, Tty) {
+    let mut master = -1;
+    let mut slave = -1;
+    let res = unsafe { libc::openpty(&mut master, &mut slave, ptr::null_mut(), ptr::null(), ptr::null()) };
+    assert_ne!(res, -1, "unable to open pseudo-terminal: {}", io::Error::last_os_error());
+    let master = unsafe { File::from_raw_fd(master) };
+    let slave = unsafe { OwnedFd::from_raw_fd(slave) };
+    set_nonblocking(slave.as_raw_fd());
+    (master, Tty::from(slave))
+}
+
+fn set_nonblocking(fd: RawFd) {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    assert_ne!(flags, -1);
+    assert_ne!(unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) }, -1);
+}
+
+fn is_nonblocking(fd: RawFd) -> bool {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    assert_ne!(flags, -1, "unable to get file status flags: {}", io::Error::last_os_error());
+    flags & libc::O_NONBLOCK != 0
+}
+
+fn is_canonical(fd: RawFd) -> bool {
+    let mut attributes = unsafe { std::mem::zeroed() };
+    assert_ne!(unsafe { libc::tcgetattr(fd, &mut attributes) }, -1);
+    attributes.c_lflag & libc::ICANON != 0
+}
+
+#[test]
+fn tty_read_write() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let (mut master, mut tty) = open_tty();
+
+    os_queue.register(&mut tty, ID, Tty::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register tty");
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::WRITABLE)]);
+    let mut buf = [0; 32];
+    assert_would_block(tty.read(&mut buf));
+
+    tty.write_all(b"Hello world").expect("unable to write");
+    let n = master.read(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"Hello world");
+
+    // In canonical mode input is available per line.
+    master.write_all(b"Hello").unwrap();
+    master.write_all(b" back\n").unwrap();
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE | Ready::WRITABLE)]);
+    let n = tty.read(&mut buf).expect("unable to read");
+    assert_eq!(&buf[..n], b"Hello back\n");
+}
+
+#[test]
+fn tty_raw_mode() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let (mut master, mut tty) = open_tty();
+    let fd = tty.as_raw_fd();
+    assert!(is_canonical(fd));
+    assert!(!tty.is_raw_mode());
+
+    tty.set_raw_mode().expect("unable to set raw mode");
+    assert!(tty.is_raw_mode());
+    assert!(!is_canonical(fd));
+    // Setting it twice should be fine.
+    tty.set_raw_mode().expect("unable to set raw mode");
+
+    // In raw mode input is available without a new line.
+    os_queue.register(&mut tty, ID, Interests::READABLE, RegisterOption::LEVEL)
+        .expect("unable to register tty");
+    master.write_all(b"a").unwrap();
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    let mut buf = [0; 32];
+    let n = tty.read(&mut buf).expect("unable to read");
+    assert_eq!(&buf[..n], b"a");
+
+    tty.restore_mode().expect("unable to restore mode");
+    assert!(!tty.is_raw_mode());
+    assert!(is_canonical(fd));
+    tty.restore_mode().expect("unable to restore mode");
+
+    // Dropping the `Tty` should restore the mode.
+    tty.set_raw_mode().expect("unable to set raw mode");
+    let fd = unsafe { libc::dup(fd) };
+    assert_ne!(fd, -1);
+    drop(tty);
+    assert!(is_canonical(fd));
+    unsafe { libc::close(fd) };
+}
+
+#[test]
+fn tty_window_size() {
+    init();
+    let (master, tty) = open_tty();
+
+    let size = libc::winsize { ws_row: 40, ws_col: 120, ws_xpixel: 0, ws_ypixel: 0 };
+    assert_ne!(unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &size) }, -1);
+    assert_eq!(tty.window_size().unwrap(), WindowSize::new(40, 120));
+}
+
+#[test]
+fn stdin_pipe() {
+    init();
+
+    // Replace stdin in the child process, to not mess with the test harness.
+    assert!(in_child_process(|| {
+        let mut os_queue = OsQueue::new()?;
+        let mut events = Vec::new();
+
+        let mut fds = [-1; 2];
+        assert_ne!(unsafe { libc::pipe(fds.as_mut_ptr()) }, -1);
+        assert_ne!(unsafe { libc::dup2(fds[0], libc::STDIN_FILENO) }, -1);
+        let mut writer = unsafe { File::from_raw_fd(fds[1]) };
+
+        let mut stdin = unix::stdin()?;
+        assert!(is_nonblocking(stdin.as_raw_fd()));
+        assert!(is_cloexec(stdin.as_raw_fd()));
+        // Flags of the original file description are unchanged.
+        assert!(!is_nonblocking(libc::STDIN_FILENO));
+
+        os_queue.register(&mut stdin, ID, Receiver::INTERESTS, RegisterOption::LEVEL)?;
+        let mut buf = [0; 32];
+        assert_would_block(stdin.read(&mut buf));
+        writer.write_all(b"Hello world")?;
+        expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+        let n = stdin.read(&mut buf)?;
+        assert_eq!(&buf[..n], b"Hello world");
+        Ok(())
+    }));
+}
+
+#[test]
+fn stdout_regular_file() {
+    init();
+    let path = std::env::temp_dir().join(format!("gaea_stdout_regular_file_{}", std::process::id()));
+
+    assert!(in_child_process(|| {
+        let mut file = OpenOptions::new().create(true).truncate(true).write(true).open(&path)?;
+        file.write_all(b"Hello")?;
+        let file = OpenOptions::new().append(true).open(&path)?;
+        assert_ne!(unsafe { libc::dup2(file.into_raw_fd(), libc::STDOUT_FILENO) }, -1);
+
+        // Must keep appending, not overwrite the file.
+        let mut stdout = unix::stdout()?;
+        assert!(is_cloexec(stdout.as_raw_fd()));
+        stdout.write_all(b" world")?;
+        Ok(())
+    }));
+
+    assert_eq!(fs::read(&path).unwrap(), b"Hello world");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn stdout_pipe() {
+    init();
+
+    assert!(in_child_process(|| {
+        let (sender, mut receiver) = new_pipe()?;
+        assert_ne!(unsafe { libc::dup2(sender.into_raw_fd(), libc::STDOUT_FILENO) }, -1);
+        // Clear the non-blocking flag, as if inherited from a shell.
+        let flags = unsafe { libc::fcntl(libc::STDOUT_FILENO, libc::F_GETFL) };
+        assert_ne!(unsafe { libc::fcntl(libc::STDOUT_FILENO, libc::F_SETFL, flags & !libc::O_NONBLOCK) }, -1);
+
+        let mut stdout = unix::stdout()?;
+        assert!(is_nonblocking(stdout.as_raw_fd()));
+        assert!(!is_nonblocking(libc::STDOUT_FILENO));
+        stdout.write_all(b"Hello world")?;
+        let mut buf = [0; 32];
+        let n = receiver.read(&mut buf)?;
+        assert_eq!(&buf[..n], b"Hello world");
+        Ok(())
+    }));
+}
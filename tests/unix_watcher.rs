@@ -0,0 +1,157 @@
+#![cfg(any(target_os = "android", target_os = "linux"))]
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+use gaea::event::{self, Event, Ready};
+use gaea::os::RegisterOption;
+use gaea::unix::{WatchEvent, WatchEventKind, WatchMask, Watcher};
+
+mod util;
+
+use self::util::{expect_events, expect_no_events, init_with_os_queue};
+
+const ID: event::Id = event::Id(0);
+
+/// Create a new empty temporary directory, unique for `name`.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("gaea_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).expect("unable to create temporary directory");
+    dir
+}
+
+/// Receive all available events from `watcher`.
+fn receive_all(watcher: &mut Watcher) -> Vec<WatchEvent> {
+    let mut events = Vec::new();
+    while let Some(event) = watcher.receive().expect("unable to receive event") {
+        events.push(event);
+    }
+    events
+}
+
+#[test]
+fn watcher() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let dir = temp_dir("watcher");
+
+    let mut watcher = Watcher::new().expect("unable to create watcher");
+    let watch = watcher.add_watch(&dir, WatchMask::CREATE | WatchMask::MODIFY | WatchMask::DELETE)
+        .expect("unable to add watch");
+    os_queue.register(&mut watcher, ID, Watcher::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register watcher");
+    expect_no_events(&mut os_queue);
+    assert_eq!(watcher.receive().unwrap(), None);
+
+    let path = dir.join("file.txt");
+    let mut file = File::create(&path).expect("unable to create file");
+    file.write_all(b"Hello world").expect("unable to write");
+    drop(file);
+    fs::create_dir(dir.join("sub")).expect("unable to create directory");
+    fs::remove_file(&path).expect("unable to remove file");
+
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    let received = receive_all(&mut watcher);
+    let received: Vec<_> = received.iter()
+        .map(|event| {
+            assert_eq!(event.watch(), Some(watch));
+            (event.kind(), event.name().unwrap().to_str().unwrap(), event.is_dir())
+        })
+        .collect();
+    assert_eq!(received, vec![
+        (WatchEventKind::Create, "file.txt", false),
+        (WatchEventKind::Modify, "file.txt", false),
+        (WatchEventKind::Create, "sub", true),
+        (WatchEventKind::Delete, "file.txt", false),
+    ]);
+    expect_no_events(&mut os_queue);
+
+    fs::remove_dir_all(&dir).expect("unable to remove temporary directory");
+}
+
+#[test]
+fn watcher_move() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let dir = temp_dir("watcher_move");
+    let from = dir.join("from");
+    let to = dir.join("to");
+    fs::create_dir(&from).unwrap();
+    fs::create_dir(&to).unwrap();
+    drop(File::create(from.join("file")).unwrap());
+
+    let mut watcher = Watcher::new().expect("unable to create watcher");
+    let from_watch = watcher.add_watch(&from, WatchMask::MOVE).expect("unable to add watch");
+    let to_watch = watcher.add_watch(&to, WatchMask::MOVE).expect("unable to add watch");
+    assert_ne!(from_watch, to_watch);
+    os_queue.register(&mut watcher, ID, Watcher::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register watcher");
+
+    fs::rename(from.join("file"), to.join("renamed")).expect("unable to move file");
+
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    let received = receive_all(&mut watcher);
+    assert_eq!(received.len(), 2);
+    let (from_cookie, to_cookie) = match (received[0].kind(), received[1].kind()) {
+        (WatchEventKind::MovedFrom { cookie: from }, WatchEventKind::MovedTo { cookie: to }) => (from, to),
+        kinds => panic!("unexpected events: {:?}", kinds),
+    };
+    assert_eq!(from_cookie, to_cookie);
+    assert_eq!(received[0].watch(), Some(from_watch));
+    assert_eq!(received[0].name().unwrap(), "file");
+    assert_eq!(received[1].watch(), Some(to_watch));
+    assert_eq!(received[1].name().unwrap(), "renamed");
+
+    fs::remove_dir_all(&dir).expect("unable to remove temporary directory");
+}
+
+#[test]
+fn watcher_remove_watch() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let dir = temp_dir("watcher_remove_watch");
+    let path = dir.join("file");
+    drop(File::create(&path).unwrap());
+
+    let mut watcher = Watcher::new().expect("unable to create watcher");
+    let watch = watcher.add_watch(&path, WatchMask::ALL).expect("unable to add watch");
+    // Adding the same path again returns the same watch.
+    assert_eq!(watcher.add_watch(&path, WatchMask::MODIFY).unwrap(), watch);
+    os_queue.register(&mut watcher, ID, Watcher::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register watcher");
+
+    watcher.remove_watch(watch).expect("unable to remove watch");
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    let received = receive_all(&mut watcher);
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].kind(), WatchEventKind::Ignored);
+    assert_eq!(received[0].watch(), Some(watch));
+    assert_eq!(received[0].name(), None);
+
+    // No more events after removing the watch.
+    drop(File::create(&path).unwrap());
+    expect_no_events(&mut os_queue);
+    assert!(watcher.remove_watch(watch).is_err());
+
+    fs::remove_dir_all(&dir).expect("unable to remove temporary directory");
+}
+
+#[test]
+fn watcher_delete_self() {
+    let (mut os_queue, mut events) = init_with_os_queue();
+    let dir = temp_dir("watcher_delete_self");
+
+    let mut watcher = Watcher::new().expect("unable to create watcher");
+    let watch = watcher.add_watch(&dir, WatchMask::DELETE_SELF).expect("unable to add watch");
+    os_queue.register(&mut watcher, ID, Watcher::INTERESTS, RegisterOption::LEVEL)
+        .expect("unable to register watcher");
+
+    fs::remove_dir(&dir).expect("unable to remove temporary directory");
+    expect_events(&mut os_queue, &mut events, vec![Event::new(ID, Ready::READABLE)]);
+    let kinds: Vec<_> = receive_all(&mut watcher).iter()
+        .map(|event| {
+            assert_eq!(event.watch(), Some(watch));
+            event.kind()
+        })
+        .collect();
+    assert_eq!(kinds, vec![WatchEventKind::DeleteSelf, WatchEventKind::Ignored]);
+}
@@ -5,8 +5,9 @@
 #![allow(dead_code)]
 
 use std::net::SocketAddr;
+use std::os::unix::io::RawFd;
 use std::time::Duration;
-use std::{fmt, io};
+use std::{fmt, io, panic};
 
 use log::warn;
 
@@ -120,3 +121,31 @@ pub fn any_local_address() -> SocketAddr {
 pub fn any_local_ipv6_address() -> SocketAddr {
     "[::1]:0".parse().unwrap()
 }
+
+/// Returns `true` if the close-on-exec flag is set on `fd`.
+pub fn is_cloexec(fd: RawFd) -> bool {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    assert_ne!(flags, -1, "unable to get file descriptor flags: {}", io::Error::last_os_error());
+    flags & libc::FD_CLOEXEC != 0
+}
+
+/// Run `child` in a forked child process, returns whether or not it returned
+/// `Ok`.
+pub fn in_child_process<F>(child: F) -> bool
+    where F: FnOnce() -> io::Result<()>,
+{
+    match unsafe { libc::fork() } {
+        -1 => panic!("unable to fork: {}", io::Error::last_os_error()),
+        0 => {
+            let ok = panic::catch_unwind(panic::AssertUnwindSafe(child))
+                .map(|result| result.is_ok())
+                .unwrap_or(false);
+            unsafe { libc::_exit(if ok { 0 } else { 1 }) };
+        },
+        pid => {
+            let mut status = 0;
+            assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+            libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0
+        },
+    }
+}